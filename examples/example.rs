@@ -1,11 +1,13 @@
-use std::{borrow::Borrow, ops::Deref, rc::Rc, sync::Arc};
-
-use iglo::{os::Window, rhi::*};
+use iglo::{
+    os::{Window, WindowDesc},
+    rhi::*,
+};
 
 struct Renderer<'a> {
-    window: &'a Window,
     instance: Instance,
-    surface: Surface<'a>,
+
+    // Kept alive for presenting to the window.
+    _surface: Surface<'a>,
 }
 
 impl<'a> Renderer<'a> {
@@ -16,24 +18,43 @@ impl<'a> Renderer<'a> {
             validation: true,
         };
 
-        let instance = Instance::new(&instance_info).unwrap();
-        let surface = instance.new_surface(window).unwrap();
+        let instance = Instance::new(&instance_info).ok()?;
+        let surface = instance.new_surface(window).ok()?;
 
         Some(Self {
-            window,
             instance,
-            surface,
+            _surface: surface,
         })
     }
+
+    pub fn backend(&self) -> Backend {
+        self.instance.backend()
+    }
+
+    pub fn adapters(&self) -> Vec<Adapter> {
+        self.instance.enumerate_adapters()
+    }
 }
 
 fn main() {
-    let window = Window::new();
+    let window = Window::new(&WindowDesc {
+        title: "Example",
+        ..Default::default()
+    })
+    .expect("Failed to create window!");
     window.show();
 
-    let _renderer = Renderer::new(&window).expect("Failed to create renderer!");
+    let renderer = Renderer::new(&window).expect("Failed to create renderer!");
+    println!("Using the {:?} backend", renderer.backend());
+    for adapter in renderer.adapters() {
+        let info = adapter.info();
+        println!(
+            "{} ({:?}, {:04x}:{:04x}, API {})",
+            info.name, info.device_type, info.vendor_id, info.device_id, info.api_version
+        );
+    }
 
-    loop {
-        window.poll_events()
+    while !window.is_close_requested() {
+        window.poll_events(|_| {})
     }
 }
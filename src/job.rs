@@ -0,0 +1,575 @@
+//! Running work in parallel on a pool of worker threads.
+//!
+//! Work is split into jobs, which are small closures. Every worker has a deque of its own: jobs spawned
+//! by a worker are pushed onto its deque and popped in reverse order, which keeps related data in its cache.
+//! Workers that run out of jobs steal the oldest jobs from other workers, which are usually the largest.
+//!
+//! Spawning a job returns a [`JobCounter`], which counts the jobs that haven't finished yet. Counters are
+//! used to wait for jobs, and as dependencies of other jobs, which are only started once their dependencies
+//! finished. A thread waiting for a counter runs jobs in the meantime, so waiting from within a job is fine.
+
+use std::{
+    any::Any,
+    cell::Cell,
+    collections::VecDeque,
+    ops::Range,
+    panic::{self, AssertUnwindSafe},
+    sync::{
+        atomic::{AtomicBool, AtomicUsize, Ordering},
+        Arc, Condvar, Mutex,
+    },
+    thread::{self, JoinHandle},
+};
+
+type Job = Box<dyn FnOnce() + Send>;
+
+thread_local! {
+    /// The job system the current thread is a worker of, by the address of its shared state, and its index.
+    static WORKER: Cell<Option<(usize, usize)>> = const { Cell::new(None) };
+}
+
+/// Describes how a job system is created.
+#[derive(Debug, Clone, Copy)]
+pub struct JobSystemDesc<'a> {
+    /// The number of worker threads. If 0, one less than the number of logical cores,
+    /// since the thread creating the jobs usually runs jobs while it waits for them.
+    pub worker_count: usize,
+
+    /// The name of the worker threads, followed by their index.
+    pub name: &'a str,
+
+    /// Called on every worker thread before it runs any jobs, with the index of the worker.
+    ///
+    /// This is the place to set up thread-local state, e.g. a command allocator per thread.
+    pub on_worker_start: Option<fn(usize)>,
+
+    /// Called on every worker thread after it ran its last job, with the index of the worker.
+    pub on_worker_stop: Option<fn(usize)>,
+}
+
+impl<'a> Default for JobSystemDesc<'a> {
+    fn default() -> Self {
+        Self {
+            worker_count: 0,
+            name: "iglo-worker",
+            on_worker_start: None,
+            on_worker_stop: None,
+        }
+    }
+}
+
+/// Counts the unfinished jobs it was passed to, see the [module documentation](self).
+///
+/// Counters are cheap to clone, clones count the same jobs.
+#[derive(Clone)]
+pub struct JobCounter {
+    inner: Arc<CounterInner>,
+}
+
+struct CounterInner {
+    remaining: AtomicUsize,
+
+    /// The jobs waiting for the counter to reach zero.
+    continuations: Mutex<Vec<Arc<PendingJob>>>,
+
+    /// The payload of the first job that panicked.
+    panic: Mutex<Option<Box<dyn Any + Send>>>,
+}
+
+impl JobCounter {
+    /// Creates a counter without any jobs, which is finished until jobs are spawned with it.
+    pub fn new() -> Self {
+        Self {
+            inner: Arc::new(CounterInner {
+                remaining: AtomicUsize::new(0),
+                continuations: Mutex::new(Vec::new()),
+                panic: Mutex::new(None),
+            }),
+        }
+    }
+
+    /// Returns whether all jobs counted by this have finished.
+    pub fn is_done(&self) -> bool {
+        self.inner.remaining.load(Ordering::Acquire) == 0
+    }
+
+    /// Returns the number of jobs that haven't finished yet.
+    pub fn remaining(&self) -> usize {
+        self.inner.remaining.load(Ordering::Acquire)
+    }
+
+    fn increment(&self) {
+        self.inner.remaining.fetch_add(1, Ordering::AcqRel);
+    }
+
+    /// Counts a job as finished, and schedules the jobs waiting for the counter once all are.
+    fn decrement(&self, shared: &Shared) {
+        if self.inner.remaining.fetch_sub(1, Ordering::AcqRel) == 1 {
+            let continuations = std::mem::take(&mut *self.inner.continuations.lock().unwrap());
+            for job in continuations {
+                job.dependency_done(shared);
+            }
+
+            // Threads waiting for the counter sleep with the workers.
+            shared.wake_all();
+        }
+    }
+
+    /// Schedules a job once the counter reaches zero, or right away if it already has.
+    fn add_continuation(&self, job: Arc<PendingJob>, shared: &Shared) {
+        {
+            // The counter is checked under the lock, so it can't reach zero between the check and the push.
+            let mut continuations = self.inner.continuations.lock().unwrap();
+            if !self.is_done() {
+                continuations.push(job);
+                return;
+            }
+        }
+        job.dependency_done(shared);
+    }
+}
+
+impl Default for JobCounter {
+    fn default() -> Self {
+        Self::new()
+    }
+}
+
+/// A job waiting for its dependencies.
+struct PendingJob {
+    dependencies: AtomicUsize,
+    job: Mutex<Option<Job>>,
+}
+
+impl PendingJob {
+    fn dependency_done(&self, shared: &Shared) {
+        if self.dependencies.fetch_sub(1, Ordering::AcqRel) == 1 {
+            if let Some(job) = self.job.lock().unwrap().take() {
+                shared.push(job);
+            }
+        }
+    }
+}
+
+/// State shared between the job system and its workers.
+struct Shared {
+    /// The deque of every worker.
+    deques: Vec<Mutex<VecDeque<Job>>>,
+
+    /// Jobs spawned by threads that aren't workers.
+    injector: Mutex<VecDeque<Job>>,
+
+    /// The number of jobs in all queues, so sleeping threads can tell whether there is work.
+    queued: AtomicUsize,
+    sleep: Mutex<()>,
+    wake: Condvar,
+    shutdown: AtomicBool,
+}
+
+impl Shared {
+    /// Returns the index of the current thread if it is a worker of this job system.
+    fn worker_index(&self) -> Option<usize> {
+        let address = self as *const Self as usize;
+        WORKER.with(|worker| match worker.get() {
+            Some((shared, index)) if shared == address => Some(index),
+            _ => None,
+        })
+    }
+
+    fn push(&self, job: Job) {
+        // The job is counted before it is published, so the count never drops below the queued jobs
+        // when a thief takes it right away.
+        self.queued.fetch_add(1, Ordering::AcqRel);
+        match self.worker_index() {
+            Some(index) => self.deques[index].lock().unwrap().push_back(job),
+            None => self.injector.lock().unwrap().push_back(job),
+        }
+
+        // Taking the lock makes sure a thread that just found no work is waiting before it is notified.
+        let _guard = self.sleep.lock().unwrap();
+        self.wake.notify_one();
+    }
+
+    /// Takes a job, preferring the newest job of the current worker, then jobs of other threads.
+    fn find_job(&self) -> Option<Job> {
+        if self.queued.load(Ordering::Acquire) == 0 {
+            return None;
+        }
+
+        let index = self.worker_index();
+        let job = index
+            .and_then(|index| self.deques[index].lock().unwrap().pop_back())
+            .or_else(|| self.injector.lock().unwrap().pop_front())
+            .or_else(|| {
+                // Stealing starts at the next worker, so thieves spread over the deques.
+                let start = index.map_or(0, |index| index + 1);
+                (0..self.deques.len())
+                    .map(|offset| (start + offset) % self.deques.len())
+                    .filter(|&victim| Some(victim) != index)
+                    .find_map(|victim| self.deques[victim].lock().unwrap().pop_front())
+            });
+
+        if job.is_some() {
+            self.queued.fetch_sub(1, Ordering::AcqRel);
+        }
+        job
+    }
+
+    /// Blocks the thread until a job is pushed or `done` returns true.
+    fn sleep_unless(&self, done: impl Fn() -> bool) {
+        let guard = self.sleep.lock().unwrap();
+        if self.queued.load(Ordering::Acquire) == 0 && !done() {
+            let _guard = self.wake.wait(guard).unwrap();
+        }
+    }
+
+    fn wake_all(&self) {
+        let _guard = self.sleep.lock().unwrap();
+        self.wake.notify_all();
+    }
+}
+
+/// A pool of worker threads running jobs, see the [module documentation](self).
+///
+/// Dropping the job system runs the jobs that are still queued, and then stops the workers.
+pub struct JobSystem {
+    shared: Arc<Shared>,
+    threads: Vec<JoinHandle<()>>,
+}
+
+impl JobSystem {
+    /// Starts the worker threads.
+    ///
+    /// # Arguments
+    ///
+    /// - `desc` - Description of the job system.
+    pub fn new(desc: &JobSystemDesc) -> Self {
+        let worker_count = match desc.worker_count {
+            0 => thread::available_parallelism()
+                .map_or(1, |cores| cores.get() - 1)
+                .max(1),
+            count => count,
+        };
+
+        let shared = Arc::new(Shared {
+            deques: (0..worker_count)
+                .map(|_| Mutex::new(VecDeque::new()))
+                .collect(),
+            injector: Mutex::new(VecDeque::new()),
+            queued: AtomicUsize::new(0),
+            sleep: Mutex::new(()),
+            wake: Condvar::new(),
+            shutdown: AtomicBool::new(false),
+        });
+
+        let threads = (0..worker_count)
+            .map(|index| {
+                let shared = shared.clone();
+                let (on_start, on_stop) = (desc.on_worker_start, desc.on_worker_stop);
+                thread::Builder::new()
+                    .name(format!("{}-{}", desc.name, index))
+                    .spawn(move || {
+                        let address = Arc::as_ptr(&shared) as usize;
+                        WORKER.with(|worker| worker.set(Some((address, index))));
+                        if let Some(on_start) = on_start {
+                            on_start(index);
+                        }
+
+                        Self::run_worker(&shared);
+
+                        if let Some(on_stop) = on_stop {
+                            on_stop(index);
+                        }
+                        WORKER.with(|worker| worker.set(None));
+                    })
+                    .expect("Failed to spawn worker thread!")
+            })
+            .collect();
+
+        Self { shared, threads }
+    }
+
+    fn run_worker(shared: &Shared) {
+        loop {
+            match shared.find_job() {
+                Some(job) => job(),
+                None if shared.shutdown.load(Ordering::Acquire) => return,
+                None => shared.sleep_unless(|| shared.shutdown.load(Ordering::Acquire)),
+            }
+        }
+    }
+
+    pub fn worker_count(&self) -> usize {
+        self.threads.len()
+    }
+
+    /// Returns the index of the current thread among the workers of the job system it belongs to,
+    /// or `None` if it isn't a worker.
+    ///
+    /// Together with [`JobSystem::worker_count()`] this indexes per-thread data, e.g. one command list per worker.
+    pub fn worker_index() -> Option<usize> {
+        WORKER.with(|worker| worker.get().map(|(_, index)| index))
+    }
+
+    /// Runs a job on a worker, and returns a counter that reaches zero once it finished.
+    ///
+    /// # Arguments
+    ///
+    /// - `job` - The job to run.
+    pub fn spawn<F: FnOnce() + Send + 'static>(&self, job: F) -> JobCounter {
+        let counter = JobCounter::new();
+        self.spawn_with(&counter, &[], job);
+        counter
+    }
+
+    /// Runs a job on a worker once all `dependencies` have reached zero,
+    /// and returns a counter that reaches zero once it finished.
+    ///
+    /// # Arguments
+    ///
+    /// - `dependencies` - The counters to wait for.
+    /// - `job` - The job to run.
+    pub fn spawn_after<F: FnOnce() + Send + 'static>(
+        &self,
+        dependencies: &[&JobCounter],
+        job: F,
+    ) -> JobCounter {
+        let counter = JobCounter::new();
+        self.spawn_with(&counter, dependencies, job);
+        counter
+    }
+
+    /// Runs a job on a worker once all `dependencies` have reached zero, counted by an existing counter.
+    ///
+    /// Counting several jobs with one counter waits for all of them at once.
+    /// A job that panics still counts as finished, and the panic is resumed by [`JobSystem::wait()`].
+    ///
+    /// # Arguments
+    ///
+    /// - `counter` - The counter to count the job with.
+    /// - `dependencies` - The counters to wait for.
+    /// - `job` - The job to run.
+    pub fn spawn_with<F: FnOnce() + Send + 'static>(
+        &self,
+        counter: &JobCounter,
+        dependencies: &[&JobCounter],
+        job: F,
+    ) {
+        counter.increment();
+
+        let job: Job = {
+            let counter = counter.clone();
+            let shared = self.shared.clone();
+            Box::new(move || {
+                if let Err(payload) = panic::catch_unwind(AssertUnwindSafe(job)) {
+                    counter.inner.panic.lock().unwrap().get_or_insert(payload);
+                }
+                counter.decrement(&shared);
+            })
+        };
+
+        if dependencies.is_empty() {
+            self.shared.push(job);
+            return;
+        }
+
+        // The job is scheduled by the last dependency to finish.
+        let pending = Arc::new(PendingJob {
+            dependencies: AtomicUsize::new(dependencies.len()),
+            job: Mutex::new(Some(job)),
+        });
+        for dependency in dependencies {
+            dependency.add_continuation(pending.clone(), &self.shared);
+        }
+    }
+
+    /// Blocks until the counter reaches zero, running jobs in the meantime.
+    ///
+    /// If one of the counted jobs panicked, the panic is resumed on this thread once all of them finished.
+    ///
+    /// # Arguments
+    ///
+    /// - `counter` - The counter to wait for.
+    pub fn wait(&self, counter: &JobCounter) {
+        while !counter.is_done() {
+            match self.shared.find_job() {
+                Some(job) => job(),
+                None => self.shared.sleep_unless(|| counter.is_done()),
+            }
+        }
+
+        if let Some(payload) = counter.inner.panic.lock().unwrap().take() {
+            panic::resume_unwind(payload);
+        }
+    }
+
+    /// Calls `f` for every index in `range` in parallel, and returns once all calls returned.
+    ///
+    /// The range is split into batches, which are run as jobs. Unlike other jobs, `f` may borrow from the caller.
+    ///
+    /// # Arguments
+    ///
+    /// - `range` - The indices to call `f` with.
+    /// - `batch_size` - The number of indices per job. Larger batches have less overhead,
+    ///   smaller batches are spread more evenly over the workers.
+    /// - `f` - The function to call.
+    pub fn parallel_for<F: Fn(usize) + Sync>(&self, range: Range<usize>, batch_size: usize, f: F) {
+        let batch_size = batch_size.max(1);
+        let f: &(dyn Fn(usize) + Sync) = &f;
+
+        // SAFETY: The jobs can't outlive `f`, since this doesn't return before all of them finished,
+        // not even if one of them panics.
+        let f: &'static (dyn Fn(usize) + Sync) = unsafe { std::mem::transmute(f) };
+
+        let counter = JobCounter::new();
+        let mut start = range.start;
+        while start < range.end {
+            let end = (start + batch_size).min(range.end);
+            self.spawn_with(&counter, &[], move || (start..end).for_each(f));
+            start = end;
+        }
+
+        self.wait(&counter);
+    }
+}
+
+impl Drop for JobSystem {
+    fn drop(&mut self) {
+        self.shared.shutdown.store(true, Ordering::Release);
+        self.shared.wake_all();
+
+        for thread in self.threads.drain(..) {
+            let _ = thread.join();
+        }
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use std::{sync::mpsc, time::Duration};
+
+    use super::*;
+
+    fn job_system(worker_count: usize) -> JobSystem {
+        JobSystem::new(&JobSystemDesc {
+            worker_count,
+            ..Default::default()
+        })
+    }
+
+    #[test]
+    fn parallel_for_sum() {
+        let jobs = job_system(4);
+        let sum = AtomicUsize::new(0);
+
+        jobs.parallel_for(0..100000, 64, |i| {
+            sum.fetch_add(i, Ordering::Relaxed);
+        });
+
+        assert_eq!(sum.into_inner(), (0..100000).sum());
+    }
+
+    #[test]
+    fn parallel_for_empty() {
+        let jobs = job_system(2);
+        jobs.parallel_for(5..5, 0, |_| panic!("Called for an empty range!"));
+    }
+
+    #[test]
+    fn spawn_after_order() {
+        let jobs = job_system(4);
+        let order = Arc::new(Mutex::new(Vec::new()));
+
+        let push = |value: usize| {
+            let order = order.clone();
+            move || order.lock().unwrap().push(value)
+        };
+
+        // Two jobs depending on one, and a last one depending on both of them.
+        let first = jobs.spawn(push(0));
+        let left = jobs.spawn_after(&[&first], push(1));
+        let right = jobs.spawn_after(&[&first], push(1));
+        let last = jobs.spawn_after(&[&left, &right], push(2));
+
+        jobs.wait(&last);
+        assert!(first.is_done() && left.is_done() && right.is_done());
+        assert_eq!(*order.lock().unwrap(), [0, 1, 1, 2]);
+    }
+
+    #[test]
+    fn spawn_after_done_dependency() {
+        let jobs = job_system(1);
+        let done = jobs.spawn(|| ());
+        jobs.wait(&done);
+
+        let ran = Arc::new(AtomicBool::new(false));
+        let counter = {
+            let ran = ran.clone();
+            jobs.spawn_after(&[&done], move || ran.store(true, Ordering::Release))
+        };
+
+        jobs.wait(&counter);
+        assert!(ran.load(Ordering::Acquire));
+    }
+
+    #[test]
+    fn wait_resumes_panic() {
+        let jobs = job_system(2);
+        let counter = jobs.spawn(|| panic!("job panicked"));
+
+        let result = panic::catch_unwind(AssertUnwindSafe(|| jobs.wait(&counter)));
+        assert!(result.is_err());
+        assert!(counter.is_done());
+    }
+
+    #[test]
+    fn stealing_under_contention() {
+        const PRODUCERS: usize = 64;
+        const JOBS: usize = 256;
+
+        let jobs = Arc::new(job_system(8));
+        let ran = Arc::new(AtomicUsize::new(0));
+
+        // Every producer pushes its jobs onto the deque of the worker it runs on, while the other workers
+        // steal from it. Which jobs are stolen depends on the scheduling, only that none is lost is checked.
+        let counter = JobCounter::new();
+        for _ in 0..PRODUCERS {
+            let (system, ran, jobs_counter) = (jobs.clone(), ran.clone(), counter.clone());
+            jobs.spawn_with(&counter, &[], move || {
+                for _ in 0..JOBS {
+                    let ran = ran.clone();
+                    system.spawn_with(&jobs_counter, &[], move || {
+                        ran.fetch_add(1, Ordering::Relaxed);
+                    });
+                }
+            });
+        }
+
+        jobs.wait(&counter);
+        assert_eq!(ran.load(Ordering::Relaxed), PRODUCERS * JOBS);
+        assert_eq!(jobs.shared.queued.load(Ordering::Acquire), 0);
+    }
+
+    #[test]
+    fn stealing() {
+        let jobs = Arc::new(job_system(2));
+        let (sender, receiver) = mpsc::channel();
+
+        // The job is pushed onto the deque of the worker spawning it, which then blocks until it ran,
+        // so it can only run if another worker or the waiting thread steals it.
+        let system = jobs.clone();
+        let owner = jobs.spawn(move || {
+            let (stolen_sender, stolen_receiver) = mpsc::channel();
+            system.spawn(move || stolen_sender.send(thread::current().id()).unwrap());
+
+            let thief = stolen_receiver
+                .recv_timeout(Duration::from_secs(10))
+                .expect("The job wasn't stolen");
+            sender.send((thread::current().id(), thief)).unwrap();
+        });
+
+        jobs.wait(&owner);
+        let (owner, thief) = receiver.recv().unwrap();
+        assert_ne!(owner, thief);
+    }
+}
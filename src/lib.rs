@@ -2,6 +2,7 @@
 
 use std::fmt::Display;
 
+pub mod job;
 pub mod os;
 pub mod rhi;
 
@@ -13,7 +14,7 @@ pub struct Version {
 }
 
 impl Version {
-    pub fn new(major: u8, minor: u8, patch: u16) -> Self {
+    pub const fn new(major: u8, minor: u8, patch: u16) -> Self {
         Self {
             major,
             minor,
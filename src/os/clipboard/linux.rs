@@ -0,0 +1,723 @@
+//! The clipboard through the X11 `CLIPBOARD` selection.
+//!
+//! X11 doesn't store the clipboard, the application owning the selection sends the contents to every
+//! application that asks. So a worker thread with its own connection and a hidden window owns the
+//! selection, which serves the contents even while the application is busy.
+
+use std::{
+    ffi::CString,
+    mem::{size_of, MaybeUninit},
+    os::raw::{c_int, c_long, c_short, c_uchar},
+    ptr, slice,
+    sync::mpsc::{self, Receiver, Sender, TryRecvError},
+    thread::{self, JoinHandle},
+    time::{Duration, Instant},
+};
+
+use x11_dl::xlib;
+
+use super::{png, ClipboardError, ImageData};
+
+/// How long other applications have to answer a request, or to continue an incremental transfer.
+const TIMEOUT: Duration = Duration::from_secs(2);
+
+/// The size of the header of a ChangeProperty request, which is sent with every chunk of data.
+const CHANGE_PROPERTY_HEADER: usize = 24;
+
+#[derive(Debug, Clone, Copy, PartialEq, Eq)]
+enum Format {
+    Text,
+    Image,
+}
+
+enum Request {
+    Get(Format, Sender<Result<Vec<u8>, ClipboardError>>),
+    Set(Format, Vec<u8>, Sender<Result<(), ClipboardError>>),
+    Quit,
+}
+
+pub struct Clipboard {
+    requests: Sender<Request>,
+
+    /// The write end of a pipe, written to after every request to wake the worker.
+    wake: c_int,
+    worker: Option<JoinHandle<()>>,
+}
+
+impl Clipboard {
+    pub fn new() -> Result<Self, ClipboardError> {
+        if crate::os::linux::uses_wayland() {
+            return Err(ClipboardError::NotSupported);
+        }
+
+        let mut pipe = [0; 2];
+        if unsafe { libc::pipe2(pipe.as_mut_ptr(), libc::O_CLOEXEC) } != 0 {
+            return Err(ClipboardError::Unknown);
+        }
+        let [wake_read, wake_write] = pipe;
+
+        let (requests, receiver) = mpsc::channel();
+        let (init_sender, init) = mpsc::channel();
+
+        let worker = thread::Builder::new()
+            .name("iglo clipboard".to_owned())
+            .spawn(move || {
+                // SAFETY: The worker is the only user of its display connection and pipe.
+                match unsafe { Worker::new(receiver, wake_read) } {
+                    Ok(mut worker) => {
+                        let _ = init_sender.send(Ok(()));
+                        worker.run();
+                    }
+                    Err(error) => {
+                        let _ = init_sender.send(Err(error));
+                    }
+                }
+
+                unsafe { libc::close(wake_read) };
+            });
+
+        let worker = match worker {
+            Ok(worker) => worker,
+            _ => {
+                unsafe {
+                    libc::close(wake_read);
+                    libc::close(wake_write);
+                }
+                return Err(ClipboardError::Unknown);
+            }
+        };
+
+        if let Err(error) = init.recv().unwrap_or(Err(ClipboardError::Unknown)) {
+            let _ = worker.join();
+            unsafe { libc::close(wake_write) };
+            return Err(error);
+        }
+
+        Ok(Self {
+            requests,
+            wake: wake_write,
+            worker: Some(worker),
+        })
+    }
+
+    pub fn get_text(&self) -> Result<String, ClipboardError> {
+        let text = self.request(|reply| Request::Get(Format::Text, reply))?;
+        match String::from_utf8(text) {
+            Ok(text) => Ok(text),
+            Err(error) => Ok(String::from_utf8_lossy(error.as_bytes()).into_owned()),
+        }
+    }
+
+    pub fn set_text(&self, text: &str) -> Result<(), ClipboardError> {
+        let text = text.as_bytes().to_vec();
+        self.request(|reply| Request::Set(Format::Text, text, reply))
+    }
+
+    pub fn get_image(&self) -> Result<ImageData, ClipboardError> {
+        let image = self.request(|reply| Request::Get(Format::Image, reply))?;
+        png::decode(&image).map_err(|_| ClipboardError::Unknown)
+    }
+
+    pub fn set_image(&self, image: &ImageData) -> Result<(), ClipboardError> {
+        let image = png::encode(image);
+        self.request(|reply| Request::Set(Format::Image, image, reply))
+    }
+
+    /// Sends a request to the worker and waits for the reply.
+    fn request<T>(
+        &self,
+        request: impl FnOnce(Sender<Result<T, ClipboardError>>) -> Request,
+    ) -> Result<T, ClipboardError> {
+        let (reply, receiver) = mpsc::channel();
+        if self.requests.send(request(reply)).is_err() {
+            return Err(ClipboardError::Unknown);
+        }
+
+        self.wake();
+        receiver.recv().unwrap_or(Err(ClipboardError::Unknown))
+    }
+
+    fn wake(&self) {
+        unsafe { libc::write(self.wake, [0u8].as_ptr() as *const _, 1) };
+    }
+}
+
+impl Drop for Clipboard {
+    fn drop(&mut self) {
+        let _ = self.requests.send(Request::Quit);
+        self.wake();
+
+        if let Some(worker) = self.worker.take() {
+            let _ = worker.join();
+        }
+
+        unsafe { libc::close(self.wake) };
+    }
+}
+
+struct Atoms {
+    clipboard: xlib::Atom,
+    targets: xlib::Atom,
+    incr: xlib::Atom,
+    utf8_string: xlib::Atom,
+    text_plain_utf8: xlib::Atom,
+    image_png: xlib::Atom,
+
+    /// The property of the hidden window that receives the contents of the selection.
+    property: xlib::Atom,
+}
+
+impl Atoms {
+    unsafe fn new(xlib: &xlib::Xlib, display: *mut xlib::Display) -> Self {
+        let intern = |name: &str| {
+            let name = CString::new(name).unwrap();
+            (xlib.XInternAtom)(display, name.as_ptr(), xlib::False)
+        };
+
+        Self {
+            clipboard: intern("CLIPBOARD"),
+            targets: intern("TARGETS"),
+            incr: intern("INCR"),
+            utf8_string: intern("UTF8_STRING"),
+            text_plain_utf8: intern("text/plain;charset=utf-8"),
+            image_png: intern("image/png"),
+            property: intern("IGLO_CLIPBOARD"),
+        }
+    }
+}
+
+/// A transfer of the selection from its owner to us.
+struct Incoming {
+    /// The targets still to try, the first one is the one requested.
+    targets: Vec<xlib::Atom>,
+    data: Vec<u8>,
+
+    /// Whether the owner sends the data in chunks, see [`Worker::property_notify()`].
+    incremental: bool,
+    deadline: Instant,
+    reply: Sender<Result<Vec<u8>, ClipboardError>>,
+}
+
+/// An incremental transfer of our contents to another application.
+struct Outgoing {
+    requestor: xlib::Window,
+    property: xlib::Atom,
+    target: xlib::Atom,
+    data: Vec<u8>,
+
+    /// How much of `data` has been sent.
+    offset: usize,
+    deadline: Instant,
+}
+
+struct Worker {
+    xlib: Box<xlib::Xlib>,
+    display: *mut xlib::Display,
+    window: xlib::Window,
+    atoms: Atoms,
+    requests: Receiver<Request>,
+
+    /// The read end of the pipe that wakes the worker.
+    wake: c_int,
+
+    /// The maximum amount of data sent at once, larger contents are transferred incrementally.
+    chunk_size: usize,
+
+    /// The contents of the selection while we own it, for every target we offer.
+    contents: Vec<(xlib::Atom, Vec<u8>)>,
+    incoming: Option<Incoming>,
+    outgoing: Vec<Outgoing>,
+}
+
+impl Worker {
+    unsafe fn new(requests: Receiver<Request>, wake: c_int) -> Result<Self, ClipboardError> {
+        let xlib = match xlib::Xlib::open().map(Box::new) {
+            Ok(xlib) => xlib,
+            _ => return Err(ClipboardError::NotSupported),
+        };
+
+        let display = (xlib.XOpenDisplay)(ptr::null());
+        if display.is_null() {
+            return Err(ClipboardError::NotSupported);
+        }
+
+        let root = (xlib.XDefaultRootWindow)(display);
+        let window = (xlib.XCreateSimpleWindow)(display, root, 0, 0, 1, 1, 0, 0, 0);
+        (xlib.XSelectInput)(display, window, xlib::PropertyChangeMask);
+
+        // Request sizes are in units of 4 bytes.
+        let max_request_size = (xlib.XMaxRequestSize)(display) as usize * 4;
+        let atoms = Atoms::new(&xlib, display);
+
+        Ok(Self {
+            xlib,
+            display,
+            window,
+            atoms,
+            requests,
+            wake,
+            chunk_size: max_request_size - CHANGE_PROPERTY_HEADER,
+            contents: Vec::new(),
+            incoming: None,
+            outgoing: Vec::new(),
+        })
+    }
+
+    /// Serves requests and other applications until the clipboard is dropped.
+    fn run(&mut self) {
+        loop {
+            unsafe {
+                while (self.xlib.XPending)(self.display) > 0 {
+                    let mut event = MaybeUninit::uninit();
+                    (self.xlib.XNextEvent)(self.display, event.as_mut_ptr());
+                    self.handle_event(&event.assume_init());
+                }
+            }
+
+            // Requests are handled one at a time, the next one waits until the transfer finished.
+            if self.incoming.is_none() {
+                match self.requests.try_recv() {
+                    Ok(Request::Get(format, reply)) => {
+                        self.get(format, reply);
+                        continue;
+                    }
+                    Ok(Request::Set(format, data, reply)) => {
+                        let _ = reply.send(self.set(format, data));
+                        continue;
+                    }
+                    Ok(Request::Quit) | Err(TryRecvError::Disconnected) => return,
+                    Err(TryRecvError::Empty) => {}
+                }
+            }
+
+            let now = Instant::now();
+            if let Some(incoming) = self.incoming.take() {
+                match incoming.deadline <= now {
+                    true => {
+                        let _ = incoming.reply.send(Err(ClipboardError::Timeout));
+                    }
+                    false => self.incoming = Some(incoming),
+                }
+            }
+            self.outgoing.retain(|outgoing| outgoing.deadline > now);
+
+            let deadline = self
+                .incoming
+                .iter()
+                .map(|incoming| incoming.deadline)
+                .chain(self.outgoing.iter().map(|outgoing| outgoing.deadline))
+                .min();
+            let timeout = match deadline {
+                Some(deadline) => (deadline - now).as_millis() as c_int + 1,
+                None => -1,
+            };
+
+            unsafe {
+                (self.xlib.XFlush)(self.display);
+
+                let mut fds = [
+                    libc::pollfd {
+                        fd: (self.xlib.XConnectionNumber)(self.display),
+                        events: libc::POLLIN,
+                        revents: 0,
+                    },
+                    libc::pollfd {
+                        fd: self.wake,
+                        events: libc::POLLIN,
+                        revents: 0,
+                    },
+                ];
+                libc::poll(fds.as_mut_ptr(), fds.len() as _, timeout);
+
+                if fds[1].revents & libc::POLLIN != 0 {
+                    let mut buffer = [0u8; 64];
+                    libc::read(self.wake, buffer.as_mut_ptr() as *mut _, buffer.len());
+                }
+            }
+        }
+    }
+
+    fn handle_event(&mut self, event: &xlib::XEvent) {
+        // SAFETY: The type of the event determines which union field is valid.
+        unsafe {
+            match event.get_type() {
+                xlib::SelectionNotify => self.selection_notify(&event.selection),
+                xlib::PropertyNotify => self.property_notify(&event.property),
+                xlib::SelectionRequest => self.selection_request(&event.selection_request),
+                xlib::SelectionClear if event.selection_clear.selection == self.atoms.clipboard => {
+                    self.contents.clear()
+                }
+                _ => {}
+            }
+        }
+    }
+
+    fn get(&mut self, format: Format, reply: Sender<Result<Vec<u8>, ClipboardError>>) {
+        // Targets in order of preference.
+        let targets = match format {
+            Format::Text => vec![
+                self.atoms.utf8_string,
+                self.atoms.text_plain_utf8,
+                xlib::XA_STRING,
+            ],
+            Format::Image => vec![self.atoms.image_png],
+        };
+
+        let owner = unsafe { (self.xlib.XGetSelectionOwner)(self.display, self.atoms.clipboard) };
+        if owner == 0 {
+            let _ = reply.send(Err(ClipboardError::Empty));
+            return;
+        }
+
+        // We can't wait for ourselves to answer, so our own contents are returned directly.
+        if owner == self.window {
+            let data = targets.iter().find_map(|&target| {
+                self.contents
+                    .iter()
+                    .find(|(offered, _)| *offered == target)
+                    .map(|(_, data)| self.decode(target, data.clone()))
+            });
+            let _ = reply.send(data.ok_or(ClipboardError::Empty));
+            return;
+        }
+
+        self.incoming = Some(Incoming {
+            targets,
+            data: Vec::new(),
+            incremental: false,
+            deadline: Instant::now() + TIMEOUT,
+            reply,
+        });
+        self.convert();
+    }
+
+    /// Asks the owner of the selection to convert it to the first remaining target.
+    fn convert(&self) {
+        if let Some(incoming) = &self.incoming {
+            unsafe {
+                (self.xlib.XConvertSelection)(
+                    self.display,
+                    self.atoms.clipboard,
+                    incoming.targets[0],
+                    self.atoms.property,
+                    self.window,
+                    xlib::CurrentTime,
+                )
+            };
+        }
+    }
+
+    fn set(&mut self, format: Format, data: Vec<u8>) -> Result<(), ClipboardError> {
+        self.contents = match format {
+            Format::Text => {
+                // STRING is Latin-1, so everything else is replaced.
+                let latin1 = String::from_utf8_lossy(&data)
+                    .chars()
+                    .map(|c| u8::try_from(c).unwrap_or(b'?'))
+                    .collect();
+
+                vec![
+                    (self.atoms.utf8_string, data.clone()),
+                    (self.atoms.text_plain_utf8, data),
+                    (xlib::XA_STRING, latin1),
+                ]
+            }
+            Format::Image => vec![(self.atoms.image_png, data)],
+        };
+
+        let owner = unsafe {
+            (self.xlib.XSetSelectionOwner)(
+                self.display,
+                self.atoms.clipboard,
+                self.window,
+                xlib::CurrentTime,
+            );
+            (self.xlib.XGetSelectionOwner)(self.display, self.atoms.clipboard)
+        };
+
+        if owner != self.window {
+            self.contents.clear();
+            return Err(ClipboardError::Unknown);
+        }
+
+        Ok(())
+    }
+
+    /// Handles the answer of the selection owner to [`Worker::convert()`].
+    fn selection_notify(&mut self, event: &xlib::XSelectionEvent) {
+        if event.requestor != self.window || event.selection != self.atoms.clipboard {
+            return;
+        }
+
+        let mut incoming = match self.incoming.take() {
+            Some(incoming) if !incoming.incremental => incoming,
+            incoming => {
+                self.incoming = incoming;
+                return;
+            }
+        };
+
+        // A property of `None` means the owner can't convert to the target, so the next one is tried.
+        if event.property == 0 {
+            incoming.targets.remove(0);
+            match incoming.targets.is_empty() {
+                true => {
+                    let _ = incoming.reply.send(Err(ClipboardError::Empty));
+                }
+                false => {
+                    self.incoming = Some(incoming);
+                    self.convert();
+                }
+            }
+            return;
+        }
+
+        match self.read_property() {
+            // Reading deletes the property, which tells the owner to send the first chunk.
+            Some((kind, _)) if kind == self.atoms.incr => {
+                incoming.incremental = true;
+                incoming.deadline = Instant::now() + TIMEOUT;
+                self.incoming = Some(incoming);
+            }
+            Some((_, data)) => {
+                let data = self.decode(incoming.targets[0], data);
+                let _ = incoming.reply.send(Ok(data));
+            }
+            None => {
+                let _ = incoming.reply.send(Err(ClipboardError::Unknown));
+            }
+        }
+    }
+
+    /// Continues incremental transfers, which exchange chunks through the property.
+    ///
+    /// The receiver deletes the property after reading each chunk, and the sender replaces it with the
+    /// next one. An empty chunk ends the transfer.
+    fn property_notify(&mut self, event: &xlib::XPropertyEvent) {
+        if event.state == xlib::PropertyNewValue {
+            let incremental = matches!(&self.incoming, Some(incoming) if incoming.incremental);
+            if event.window != self.window || event.atom != self.atoms.property || !incremental {
+                return;
+            }
+
+            let chunk = self.read_property();
+            let mut incoming = self.incoming.take().unwrap();
+            match chunk {
+                Some((_, chunk)) if chunk.is_empty() => {
+                    let data = self.decode(incoming.targets[0], incoming.data);
+                    let _ = incoming.reply.send(Ok(data));
+                }
+                Some((_, chunk)) => {
+                    incoming.data.extend_from_slice(&chunk);
+                    incoming.deadline = Instant::now() + TIMEOUT;
+                    self.incoming = Some(incoming);
+                }
+                None => {
+                    let _ = incoming.reply.send(Err(ClipboardError::Unknown));
+                }
+            }
+        } else if event.state == xlib::PropertyDelete {
+            let index = self.outgoing.iter().position(|outgoing| {
+                outgoing.requestor == event.window && outgoing.property == event.atom
+            });
+
+            if let Some(index) = index {
+                self.send_chunk(index);
+            }
+        }
+    }
+
+    /// Sends the next chunk of an outgoing transfer, and ends it after the empty chunk.
+    fn send_chunk(&mut self, index: usize) {
+        let outgoing = &mut self.outgoing[index];
+        let end = (outgoing.offset + self.chunk_size).min(outgoing.data.len());
+        let chunk = &outgoing.data[outgoing.offset..end];
+
+        unsafe {
+            (self.xlib.XChangeProperty)(
+                self.display,
+                outgoing.requestor,
+                outgoing.property,
+                outgoing.target,
+                8,
+                xlib::PropModeReplace,
+                chunk.as_ptr(),
+                chunk.len() as c_int,
+            )
+        };
+
+        match chunk.is_empty() {
+            true => {
+                let outgoing = self.outgoing.remove(index);
+                unsafe { (self.xlib.XSelectInput)(self.display, outgoing.requestor, 0) };
+            }
+            false => {
+                outgoing.offset = end;
+                outgoing.deadline = Instant::now() + TIMEOUT;
+            }
+        }
+    }
+
+    /// Answers another application asking for the contents of the selection.
+    fn selection_request(&mut self, event: &xlib::XSelectionRequestEvent) {
+        // Obsolete clients don't specify a property, and expect the target to be used instead.
+        let mut property = match event.property {
+            0 => event.target,
+            property => property,
+        };
+
+        let contents = self
+            .contents
+            .iter()
+            .find(|(target, _)| *target == event.target);
+
+        unsafe {
+            if event.selection != self.atoms.clipboard || self.contents.is_empty() {
+                property = 0;
+            } else if event.target == self.atoms.targets {
+                let targets: Vec<xlib::Atom> = [self.atoms.targets]
+                    .into_iter()
+                    .chain(self.contents.iter().map(|(target, _)| *target))
+                    .collect();
+
+                (self.xlib.XChangeProperty)(
+                    self.display,
+                    event.requestor,
+                    property,
+                    xlib::XA_ATOM,
+                    32,
+                    xlib::PropModeReplace,
+                    targets.as_ptr() as *const c_uchar,
+                    targets.len() as c_int,
+                );
+            } else if let Some((target, data)) = contents {
+                if data.len() > self.chunk_size {
+                    // The INCR property holds a lower bound of the size, the data follows in chunks
+                    // as the requestor deletes the property.
+                    let size = data.len() as c_long;
+                    (self.xlib.XSelectInput)(
+                        self.display,
+                        event.requestor,
+                        xlib::PropertyChangeMask,
+                    );
+                    (self.xlib.XChangeProperty)(
+                        self.display,
+                        event.requestor,
+                        property,
+                        self.atoms.incr,
+                        32,
+                        xlib::PropModeReplace,
+                        &size as *const _ as *const c_uchar,
+                        1,
+                    );
+
+                    self.outgoing.push(Outgoing {
+                        requestor: event.requestor,
+                        property,
+                        target: *target,
+                        data: data.clone(),
+                        offset: 0,
+                        deadline: Instant::now() + TIMEOUT,
+                    });
+                } else {
+                    (self.xlib.XChangeProperty)(
+                        self.display,
+                        event.requestor,
+                        property,
+                        *target,
+                        8,
+                        xlib::PropModeReplace,
+                        data.as_ptr(),
+                        data.len() as c_int,
+                    );
+                }
+            } else {
+                property = 0;
+            }
+
+            let mut notify = xlib::XEvent {
+                selection: xlib::XSelectionEvent {
+                    type_: xlib::SelectionNotify,
+                    serial: 0,
+                    send_event: xlib::True,
+                    display: self.display,
+                    requestor: event.requestor,
+                    selection: event.selection,
+                    target: event.target,
+                    property,
+                    time: event.time,
+                },
+            };
+            (self.xlib.XSendEvent)(self.display, event.requestor, xlib::False, 0, &mut notify);
+        }
+    }
+
+    /// Reads and deletes the property of the hidden window, returning its type and contents.
+    fn read_property(&self) -> Option<(xlib::Atom, Vec<u8>)> {
+        let mut kind = 0;
+        let mut format = 0;
+        let mut item_count = 0;
+        let mut bytes_after = 0;
+        let mut data = ptr::null_mut();
+
+        unsafe {
+            let status = (self.xlib.XGetWindowProperty)(
+                self.display,
+                self.window,
+                self.atoms.property,
+                0,
+                // Everything, the length is in units of 4 bytes.
+                c_int::MAX as c_long / 4,
+                xlib::True,
+                xlib::AnyPropertyType as xlib::Atom,
+                &mut kind,
+                &mut format,
+                &mut item_count,
+                &mut bytes_after,
+                &mut data,
+            );
+
+            if status != xlib::Success as c_int || kind == 0 {
+                return None;
+            }
+
+            // Items with a format of 16 or 32 are returned as shorts and longs.
+            let item_size = match format {
+                16 => size_of::<c_short>(),
+                32 => size_of::<c_long>(),
+                _ => 1,
+            };
+            let contents = match data.is_null() {
+                true => Vec::new(),
+                false => slice::from_raw_parts(data, item_count as usize * item_size).to_vec(),
+            };
+
+            if !data.is_null() {
+                (self.xlib.XFree)(data as *mut _);
+            }
+            Some((kind, contents))
+        }
+    }
+
+    /// Converts data received as a target to the format it is returned in.
+    fn decode(&self, target: xlib::Atom, data: Vec<u8>) -> Vec<u8> {
+        match target {
+            // STRING is Latin-1, whose characters are the first 256 of Unicode.
+            xlib::XA_STRING => data
+                .into_iter()
+                .map(char::from)
+                .collect::<String>()
+                .into_bytes(),
+            _ => data,
+        }
+    }
+}
+
+impl Drop for Worker {
+    fn drop(&mut self) {
+        unsafe {
+            (self.xlib.XDestroyWindow)(self.display, self.window);
+            (self.xlib.XCloseDisplay)(self.display);
+        }
+    }
+}
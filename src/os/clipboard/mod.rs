@@ -0,0 +1,115 @@
+//! Access to the system clipboard.
+//!
+//! Like gamepads, the clipboard is independent of windows. Text is exchanged as UTF-8,
+//! images as 8-bit RGBA, converted from and to whatever format the platform uses.
+//!
+//! On Linux the clipboard goes through X11 and is only available where windows are created on X11.
+//! Wayland only lets the focused window access the clipboard, which doesn't fit a clipboard that is
+//! independent of windows, and XWayland only synchronizes its clipboard while an X11 window is focused.
+//! So in Wayland sessions, where windows are native Wayland windows, [`Clipboard::new()`] returns
+//! [`ClipboardError::NotSupported`].
+
+#[cfg(target_os = "linux")]
+mod linux;
+
+#[cfg(target_os = "linux")]
+mod png;
+
+#[cfg(target_os = "linux")]
+use linux as imp;
+
+#[cfg(target_os = "windows")]
+mod windows;
+
+#[cfg(target_os = "windows")]
+use self::windows as imp;
+
+#[derive(Debug, Clone, Copy, PartialEq, Eq)]
+pub enum ClipboardError {
+    /// The platform has no clipboard, e.g. because no display server is available.
+    NotSupported,
+
+    /// The clipboard holds nothing in the requested format.
+    Empty,
+
+    /// The application owning the clipboard didn't respond in time.
+    Timeout,
+    Unknown,
+}
+
+/// An image with 8-bit RGBA pixels, stored row by row from the top-left corner.
+#[derive(Debug, Clone, PartialEq, Eq, Hash, Default)]
+pub struct ImageData {
+    pub width: u32,
+    pub height: u32,
+
+    /// The pixels, `width * height * 4` bytes.
+    pub rgba: Vec<u8>,
+}
+
+impl ImageData {
+    /// Returns whether the image isn't empty, and has exactly `width * height` pixels.
+    pub(crate) fn is_valid(&self) -> bool {
+        self.width > 0 && self.height > 0 && self.has_valid_size()
+    }
+
+    /// Returns whether the image has exactly `width * height` pixels.
+    fn has_valid_size(&self) -> bool {
+        (self.width as usize)
+            .checked_mul(self.height as usize)
+            .and_then(|pixels| pixels.checked_mul(4))
+            == Some(self.rgba.len())
+    }
+}
+
+/// The system clipboard.
+///
+/// Contents that were set stay available to other applications while the clipboard exists,
+/// since on X11 they are served by the application that set them.
+/// After that they are lost, unless a clipboard manager took them over.
+pub struct Clipboard {
+    imp: imp::Clipboard,
+}
+
+impl Clipboard {
+    /// Connects to the system clipboard.
+    ///
+    /// Returns [`ClipboardError::NotSupported`] without a display server, and in Wayland sessions.
+    pub fn new() -> Result<Self, ClipboardError> {
+        Ok(Self {
+            imp: imp::Clipboard::new()?,
+        })
+    }
+
+    /// Returns the text on the clipboard.
+    pub fn get_text(&self) -> Result<String, ClipboardError> {
+        self.imp.get_text()
+    }
+
+    /// Replaces the contents of the clipboard with text.
+    ///
+    /// # Arguments
+    ///
+    /// - `text` - The text to put on the clipboard.
+    pub fn set_text(&self, text: &str) -> Result<(), ClipboardError> {
+        self.imp.set_text(text)
+    }
+
+    /// Returns the image on the clipboard.
+    pub fn get_image(&self) -> Result<ImageData, ClipboardError> {
+        self.imp.get_image()
+    }
+
+    /// Replaces the contents of the clipboard with an image.
+    ///
+    /// # Arguments
+    ///
+    /// - `image` - The image to put on the clipboard, `image.rgba` must hold `width * height * 4` bytes.
+    pub fn set_image(&self, image: &ImageData) -> Result<(), ClipboardError> {
+        if !image.has_valid_size() {
+            return Err(ClipboardError::Unknown);
+        }
+
+        self.imp.set_image(image)
+    }
+}
@@ -0,0 +1,679 @@
+//! A minimal PNG codec for exchanging images with other applications as `image/png`.
+//!
+//! Encoding doesn't compress, which keeps it trivial and is fine for the clipboard.
+//! Decoding supports all non-interlaced images, since the encoder is up to the other application.
+
+use super::ImageData;
+
+const SIGNATURE: [u8; 8] = [0x89, b'P', b'N', b'G', b'\r', b'\n', 0x1a, b'\n'];
+
+const COLOR_GRAY: u8 = 0;
+const COLOR_RGB: u8 = 2;
+const COLOR_PALETTE: u8 = 3;
+const COLOR_GRAY_ALPHA: u8 = 4;
+const COLOR_RGBA: u8 = 6;
+
+/// The largest image that is decoded, in bytes of RGBA, so a malicious header can't exhaust memory.
+const MAX_IMAGE_SIZE: usize = 1 << 28;
+
+/// The largest chunk that is written, far below the limit of 2^31 - 1 bytes.
+const MAX_CHUNK_SIZE: usize = 1 << 20;
+
+/// Encodes an image as an 8-bit RGBA PNG.
+pub fn encode(image: &ImageData) -> Vec<u8> {
+    let mut png = SIGNATURE.to_vec();
+
+    let mut header = Vec::with_capacity(13);
+    header.extend_from_slice(&image.width.to_be_bytes());
+    header.extend_from_slice(&image.height.to_be_bytes());
+    // Bit depth, color type, compression, filter and interlace method.
+    header.extend_from_slice(&[8, COLOR_RGBA, 0, 0, 0]);
+    write_chunk(&mut png, b"IHDR", &header);
+
+    // Every row is prefixed with its filter type, 0 meaning unfiltered.
+    let stride = image.width as usize * 4;
+    let mut filtered = Vec::with_capacity(image.rgba.len() + image.height as usize);
+    for row in image
+        .rgba
+        .chunks_exact(stride.max(1))
+        .take(image.height as usize)
+    {
+        filtered.push(0);
+        filtered.extend_from_slice(row);
+    }
+    for data in zlib_store(&filtered).chunks(MAX_CHUNK_SIZE) {
+        write_chunk(&mut png, b"IDAT", data);
+    }
+
+    write_chunk(&mut png, b"IEND", &[]);
+    png
+}
+
+/// Decodes a PNG into 8-bit RGBA.
+pub fn decode(png: &[u8]) -> Result<ImageData, ()> {
+    if png.len() < SIGNATURE.len() || png[..SIGNATURE.len()] != SIGNATURE {
+        return Err(());
+    }
+
+    let mut header = None;
+    let mut palette: &[u8] = &[];
+    let mut transparency: &[u8] = &[];
+    let mut compressed = Vec::new();
+
+    let mut rest = &png[SIGNATURE.len()..];
+    while rest.len() >= 12 {
+        let length = u32::from_be_bytes([rest[0], rest[1], rest[2], rest[3]]) as usize;
+        if rest.len() < 12 + length {
+            return Err(());
+        }
+
+        let kind = &rest[4..8];
+        let data = &rest[8..8 + length];
+        match kind {
+            b"IHDR" if data.len() == 13 => header = Some(data),
+            b"PLTE" => palette = data,
+            b"tRNS" => transparency = data,
+            b"IDAT" => compressed.extend_from_slice(data),
+            b"IEND" => break,
+            _ => {}
+        }
+
+        rest = &rest[12 + length..];
+    }
+
+    let header = header.ok_or(())?;
+    let width = u32::from_be_bytes([header[0], header[1], header[2], header[3]]);
+    let height = u32::from_be_bytes([header[4], header[5], header[6], header[7]]);
+    let (depth, color, interlace) = (header[8], header[9], header[12]);
+
+    let channels = match color {
+        COLOR_GRAY | COLOR_PALETTE => 1,
+        COLOR_RGB => 3,
+        COLOR_GRAY_ALPHA => 2,
+        COLOR_RGBA => 4,
+        _ => return Err(()),
+    };
+
+    let valid_depth = match color {
+        COLOR_GRAY => matches!(depth, 1 | 2 | 4 | 8 | 16),
+        COLOR_PALETTE => matches!(depth, 1 | 2 | 4 | 8),
+        _ => matches!(depth, 8 | 16),
+    };
+    if !valid_depth || interlace != 0 || width == 0 || height == 0 {
+        return Err(());
+    }
+
+    // The header is untrusted, so the sizes are checked before anything is allocated.
+    let image_size = (width as usize)
+        .checked_mul(height as usize)
+        .and_then(|pixels| pixels.checked_mul(4))
+        .filter(|&size| size <= MAX_IMAGE_SIZE)
+        .ok_or(())?;
+    let bits_per_pixel = channels * depth as usize;
+    let stride = (width as usize)
+        .checked_mul(bits_per_pixel)
+        .ok_or(())?
+        .div_ceil(8);
+    let data_size = (stride + 1).checked_mul(height as usize).ok_or(())?;
+
+    let mut data = inflate(zlib_data(&compressed)?, data_size)?;
+    if data.len() < data_size {
+        return Err(());
+    }
+    unfilter(
+        &mut data,
+        stride,
+        bits_per_pixel.div_ceil(8),
+        height as usize,
+    )?;
+
+    // Samples are read as 8 bits. 16-bit samples keep their high byte,
+    // smaller ones are scaled up, except for palette indices.
+    let sample = |row: &[u8], index: usize| -> u8 {
+        match depth {
+            8 => row[index],
+            16 => row[index * 2],
+            _ => {
+                let per_byte = 8 / depth as usize;
+                let shift = 8 - depth as usize * (index % per_byte + 1);
+                let value = (row[index / per_byte] >> shift) & ((1 << depth) - 1);
+                match color {
+                    COLOR_PALETTE => value,
+                    _ => (value as u32 * 255 / ((1 << depth) - 1)) as u8,
+                }
+            }
+        }
+    };
+
+    let mut rgba = Vec::with_capacity(image_size);
+    for y in 0..height as usize {
+        let row = &data[y * (stride + 1) + 1..(y + 1) * (stride + 1)];
+        for x in 0..width as usize {
+            let pixel = |channel: usize| sample(row, x * channels + channel);
+            match color {
+                COLOR_GRAY => {
+                    let gray = pixel(0);
+                    rgba.extend_from_slice(&[gray, gray, gray, 255]);
+                }
+                COLOR_GRAY_ALPHA => {
+                    let gray = pixel(0);
+                    rgba.extend_from_slice(&[gray, gray, gray, pixel(1)]);
+                }
+                COLOR_RGB => rgba.extend_from_slice(&[pixel(0), pixel(1), pixel(2), 255]),
+                COLOR_RGBA => rgba.extend_from_slice(&[pixel(0), pixel(1), pixel(2), pixel(3)]),
+                _ => {
+                    let index = pixel(0) as usize;
+                    let color = palette.get(index * 3..index * 3 + 3).ok_or(())?;
+                    let alpha = transparency.get(index).copied().unwrap_or(255);
+                    rgba.extend_from_slice(&[color[0], color[1], color[2], alpha]);
+                }
+            }
+        }
+    }
+
+    Ok(ImageData {
+        width,
+        height,
+        rgba,
+    })
+}
+
+fn write_chunk(png: &mut Vec<u8>, kind: &[u8; 4], data: &[u8]) {
+    png.extend_from_slice(&(data.len() as u32).to_be_bytes());
+    let start = png.len();
+    png.extend_from_slice(kind);
+    png.extend_from_slice(data);
+    let crc = crc32(&png[start..]);
+    png.extend_from_slice(&crc.to_be_bytes());
+}
+
+fn crc32(data: &[u8]) -> u32 {
+    let mut crc = !0u32;
+    for &byte in data {
+        crc ^= byte as u32;
+        for _ in 0..8 {
+            crc = match crc & 1 {
+                1 => (crc >> 1) ^ 0xedb8_8320,
+                _ => crc >> 1,
+            };
+        }
+    }
+    !crc
+}
+
+fn adler32(data: &[u8]) -> u32 {
+    let (mut a, mut b) = (1u32, 0u32);
+    for chunk in data.chunks(5552) {
+        for &byte in chunk {
+            a += byte as u32;
+            b += a;
+        }
+        a %= 65521;
+        b %= 65521;
+    }
+    b << 16 | a
+}
+
+/// Wraps data in a zlib stream of uncompressed deflate blocks.
+fn zlib_store(data: &[u8]) -> Vec<u8> {
+    const MAX_BLOCK: usize = 0xffff;
+
+    let mut zlib = vec![0x78, 0x01];
+    let mut blocks = data.chunks(MAX_BLOCK).peekable();
+    if blocks.peek().is_none() {
+        zlib.extend_from_slice(&[1, 0, 0, 0xff, 0xff]);
+    }
+    while let Some(block) = blocks.next() {
+        let last = blocks.peek().is_none() as u8;
+        let length = block.len() as u16;
+        zlib.push(last);
+        zlib.extend_from_slice(&length.to_le_bytes());
+        zlib.extend_from_slice(&(!length).to_le_bytes());
+        zlib.extend_from_slice(block);
+    }
+    zlib.extend_from_slice(&adler32(data).to_be_bytes());
+    zlib
+}
+
+/// Returns the deflate stream of a zlib stream.
+fn zlib_data(zlib: &[u8]) -> Result<&[u8], ()> {
+    // The method must be deflate, and no preset dictionary may be used.
+    match zlib {
+        [cmf, flg, data @ ..] if cmf & 0x0f == 8 && flg & 0x20 == 0 => Ok(data),
+        _ => Err(()),
+    }
+}
+
+/// Reverses the per-row filters, in place.
+fn unfilter(
+    data: &mut [u8],
+    stride: usize,
+    bytes_per_pixel: usize,
+    height: usize,
+) -> Result<(), ()> {
+    for y in 0..height {
+        let (previous, current) = data.split_at_mut(y * (stride + 1));
+        let previous = match y {
+            0 => None,
+            _ => Some(&previous[previous.len() - stride..]),
+        };
+        let (filter, row) = current[..stride + 1].split_first_mut().ok_or(())?;
+
+        for x in 0..stride {
+            let left = match x >= bytes_per_pixel {
+                true => row[x - bytes_per_pixel],
+                false => 0,
+            };
+            let up = previous.map_or(0, |previous| previous[x]);
+            let up_left = match x >= bytes_per_pixel {
+                true => previous.map_or(0, |previous| previous[x - bytes_per_pixel]),
+                false => 0,
+            };
+
+            let predicted = match *filter {
+                0 => 0,
+                1 => left,
+                2 => up,
+                3 => ((left as u16 + up as u16) / 2) as u8,
+                4 => paeth(left, up, up_left),
+                _ => return Err(()),
+            };
+            row[x] = row[x].wrapping_add(predicted);
+        }
+    }
+
+    Ok(())
+}
+
+fn paeth(a: u8, b: u8, c: u8) -> u8 {
+    let p = a as i16 + b as i16 - c as i16;
+    let (pa, pb, pc) = (
+        (p - a as i16).abs(),
+        (p - b as i16).abs(),
+        (p - c as i16).abs(),
+    );
+    if pa <= pb && pa <= pc {
+        a
+    } else if pb <= pc {
+        b
+    } else {
+        c
+    }
+}
+
+/// Reads a deflate stream least significant bit first.
+struct BitReader<'a> {
+    data: &'a [u8],
+    position: usize,
+    bit: u32,
+}
+
+impl<'a> BitReader<'a> {
+    fn bits(&mut self, count: u32) -> Result<u32, ()> {
+        let mut value = 0;
+        for i in 0..count {
+            let byte = *self.data.get(self.position).ok_or(())?;
+            value |= ((byte >> self.bit) as u32 & 1) << i;
+            self.bit += 1;
+            if self.bit == 8 {
+                self.bit = 0;
+                self.position += 1;
+            }
+        }
+        Ok(value)
+    }
+
+    fn align(&mut self) {
+        if self.bit != 0 {
+            self.bit = 0;
+            self.position += 1;
+        }
+    }
+}
+
+/// A canonical Huffman code, stored as the number of codes per length and the symbols in code order.
+struct Huffman {
+    counts: [u16; 16],
+    symbols: Vec<u16>,
+}
+
+impl Huffman {
+    fn new(lengths: &[u8]) -> Self {
+        let mut counts = [0u16; 16];
+        for &length in lengths {
+            counts[length as usize] += 1;
+        }
+        counts[0] = 0;
+
+        let mut offsets = [0u16; 16];
+        for length in 1..16 {
+            offsets[length] = offsets[length - 1] + counts[length - 1];
+        }
+
+        let mut symbols = vec![0; lengths.len()];
+        for (symbol, &length) in lengths.iter().enumerate() {
+            if length != 0 {
+                symbols[offsets[length as usize] as usize] = symbol as u16;
+                offsets[length as usize] += 1;
+            }
+        }
+
+        Self { counts, symbols }
+    }
+
+    fn decode(&self, reader: &mut BitReader) -> Result<u16, ()> {
+        // Codes are read one bit at a time, comparing against the first code of each length.
+        let (mut code, mut first, mut index) = (0i32, 0i32, 0i32);
+        for length in 1..16 {
+            code |= reader.bits(1)? as i32;
+            let count = self.counts[length] as i32;
+            if code - first < count {
+                return self
+                    .symbols
+                    .get((index + code - first) as usize)
+                    .copied()
+                    .ok_or(());
+            }
+            index += count;
+            first = (first + count) << 1;
+            code <<= 1;
+        }
+        Err(())
+    }
+}
+
+/// Decompresses a deflate stream, failing if it holds more than `limit` bytes.
+fn inflate(data: &[u8], limit: usize) -> Result<Vec<u8>, ()> {
+    const LENGTH_BASE: [u16; 29] = [
+        3, 4, 5, 6, 7, 8, 9, 10, 11, 13, 15, 17, 19, 23, 27, 31, 35, 43, 51, 59, 67, 83, 99, 115,
+        131, 163, 195, 227, 258,
+    ];
+    const LENGTH_EXTRA: [u8; 29] = [
+        0, 0, 0, 0, 0, 0, 0, 0, 1, 1, 1, 1, 2, 2, 2, 2, 3, 3, 3, 3, 4, 4, 4, 4, 5, 5, 5, 5, 0,
+    ];
+    const DISTANCE_BASE: [u16; 30] = [
+        1, 2, 3, 4, 5, 7, 9, 13, 17, 25, 33, 49, 65, 97, 129, 193, 257, 385, 513, 769, 1025, 1537,
+        2049, 3073, 4097, 6145, 8193, 12289, 16385, 24577,
+    ];
+    const DISTANCE_EXTRA: [u8; 30] = [
+        0, 0, 0, 0, 1, 1, 2, 2, 3, 3, 4, 4, 5, 5, 6, 6, 7, 7, 8, 8, 9, 9, 10, 10, 11, 11, 12, 12,
+        13, 13,
+    ];
+    // The order in which the code lengths of the code length alphabet are stored.
+    const CODE_LENGTH_ORDER: [usize; 19] = [
+        16, 17, 18, 0, 8, 7, 9, 6, 10, 5, 11, 4, 12, 3, 13, 2, 14, 1, 15,
+    ];
+
+    let mut reader = BitReader {
+        data,
+        position: 0,
+        bit: 0,
+    };
+    let mut output = Vec::new();
+
+    loop {
+        let last = reader.bits(1)? == 1;
+        match reader.bits(2)? {
+            0 => {
+                reader.align();
+                let header = data.get(reader.position..reader.position + 4).ok_or(())?;
+                let length = u16::from_le_bytes([header[0], header[1]]);
+                if length != !u16::from_le_bytes([header[2], header[3]]) {
+                    return Err(());
+                }
+
+                let (start, length) = (reader.position + 4, length as usize);
+                if output.len() + length > limit {
+                    return Err(());
+                }
+                output.extend_from_slice(data.get(start..start + length).ok_or(())?);
+                reader.position = start + length;
+            }
+            kind @ (1 | 2) => {
+                let (literals, distances) = match kind {
+                    1 => {
+                        let mut lengths = [8u8; 288];
+                        lengths[144..256].fill(9);
+                        lengths[256..280].fill(7);
+                        (Huffman::new(&lengths), Huffman::new(&[5; 30]))
+                    }
+                    _ => {
+                        let literal_count = reader.bits(5)? as usize + 257;
+                        let distance_count = reader.bits(5)? as usize + 1;
+                        let code_length_count = reader.bits(4)? as usize + 4;
+
+                        let mut code_lengths = [0u8; 19];
+                        for &index in &CODE_LENGTH_ORDER[..code_length_count] {
+                            code_lengths[index] = reader.bits(3)? as u8;
+                        }
+                        let code_lengths = Huffman::new(&code_lengths);
+
+                        let mut lengths = Vec::with_capacity(literal_count + distance_count);
+                        while lengths.len() < literal_count + distance_count {
+                            let (value, repeat) = match code_lengths.decode(&mut reader)? {
+                                symbol @ 0..=15 => (symbol as u8, 1),
+                                16 => (*lengths.last().ok_or(())?, 3 + reader.bits(2)?),
+                                17 => (0, 3 + reader.bits(3)?),
+                                18 => (0, 11 + reader.bits(7)?),
+                                _ => return Err(()),
+                            };
+                            lengths.extend(std::iter::repeat_n(value, repeat as usize));
+                        }
+                        if lengths.len() != literal_count + distance_count {
+                            return Err(());
+                        }
+
+                        (
+                            Huffman::new(&lengths[..literal_count]),
+                            Huffman::new(&lengths[literal_count..]),
+                        )
+                    }
+                };
+
+                loop {
+                    match literals.decode(&mut reader)? {
+                        symbol @ 0..=255 if output.len() < limit => output.push(symbol as u8),
+                        0..=255 => return Err(()),
+                        256 => break,
+                        symbol => {
+                            let index = symbol as usize - 257;
+                            let length = *LENGTH_BASE.get(index).ok_or(())? as usize
+                                + reader.bits(LENGTH_EXTRA[index] as u32)? as usize;
+
+                            let index = distances.decode(&mut reader)? as usize;
+                            let distance = *DISTANCE_BASE.get(index).ok_or(())? as usize
+                                + reader.bits(DISTANCE_EXTRA[index] as u32)? as usize;
+                            if distance > output.len() || output.len() + length > limit {
+                                return Err(());
+                            }
+
+                            // The match may overlap the bytes it produces, so it is copied byte by byte.
+                            let start = output.len() - distance;
+                            for i in 0..length {
+                                output.push(output[start + i]);
+                            }
+                        }
+                    }
+                }
+            }
+            _ => return Err(()),
+        }
+
+        if last {
+            return Ok(output);
+        }
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    /// An 8x5 RGB image compressed with dynamic Huffman codes, whose rows use every filter type.
+    const FILTERED_RGB: [u8; 130] = [
+        137, 80, 78, 71, 13, 10, 26, 10, 0, 0, 0, 13, 73, 72, 68, 82, 0, 0, 0, 8, 0, 0, 0, 5, 8, 2,
+        0, 0, 0, 247, 243, 58, 2, 0, 0, 0, 73, 73, 68, 65, 84, 120, 218, 21, 140, 65, 1, 192, 64,
+        12, 194, 160, 76, 12, 254, 85, 68, 214, 184, 95, 105, 32, 114, 117, 74, 66, 172, 236, 168,
+        200, 225, 122, 111, 137, 42, 56, 168, 30, 242, 25, 95, 214, 47, 50, 156, 56, 232, 146, 239,
+        186, 194, 214, 49, 147, 249, 202, 227, 153, 192, 95, 115, 83, 68, 71, 219, 193, 103, 119,
+        235, 31, 242, 101, 21, 194, 103, 81, 166, 136, 0, 0, 0, 0, 73, 69, 78, 68, 174, 66, 96,
+        130,
+    ];
+    const FILTERED_RGB_PIXELS: [u8; 160] = [
+        1, 40, 0, 255, 2, 0, 3, 255, 3, 3, 200, 255, 3, 1, 0, 255, 3, 0, 3, 255, 3, 40, 0, 255,
+        200, 3, 2, 255, 200, 1, 40, 255, 0, 2, 0, 255, 0, 2, 200, 255, 40, 2, 203, 255, 240, 3,
+        206, 255, 184, 3, 246, 255, 185, 6, 249, 255, 225, 7, 251, 255, 226, 207, 252, 255, 3, 4,
+        0, 255, 3, 42, 144, 255, 40, 3, 147, 255, 184, 5, 206, 255, 128, 5, 190, 255, 129, 46, 252,
+        255, 9, 207, 252, 255, 228, 209, 36, 255, 4, 42, 3, 255, 43, 42, 76, 255, 42, 222, 114,
+        255, 116, 57, 161, 255, 124, 71, 119, 255, 70, 2, 187, 255, 39, 107, 163, 255, 173, 158,
+        100, 255, 44, 45, 5, 255, 47, 245, 76, 255, 50, 245, 116, 255, 60, 97, 201, 255, 100, 100,
+        105, 255, 71, 3, 227, 255, 40, 107, 188, 255, 213, 198, 101, 255,
+    ];
+
+    /// A 3x2 image with a 2-bit palette and transparency.
+    const PALETTE: [u8; 107] = [
+        137, 80, 78, 71, 13, 10, 26, 10, 0, 0, 0, 13, 73, 72, 68, 82, 0, 0, 0, 3, 0, 0, 0, 2, 2, 3,
+        0, 0, 0, 224, 26, 142, 137, 0, 0, 0, 12, 80, 76, 84, 69, 255, 0, 0, 0, 255, 0, 0, 0, 255,
+        10, 20, 30, 34, 136, 41, 4, 0, 0, 0, 2, 116, 82, 78, 83, 255, 128, 8, 15, 179, 106, 0, 0,
+        0, 12, 73, 68, 65, 84, 120, 218, 99, 144, 96, 120, 2, 0, 1, 48, 0, 253, 104, 48, 207, 223,
+        0, 0, 0, 0, 73, 69, 78, 68, 174, 66, 96, 130,
+    ];
+    const PALETTE_PIXELS: [u8; 24] = [
+        255, 0, 0, 255, 0, 255, 0, 128, 0, 0, 255, 255, 10, 20, 30, 255, 0, 0, 255, 255, 0, 255, 0,
+        128,
+    ];
+
+    fn image(width: u32, height: u32) -> ImageData {
+        ImageData {
+            width,
+            height,
+            rgba: (0..width * height * 4)
+                .map(|i| (i * 7 % 251) as u8)
+                .collect(),
+        }
+    }
+
+    /// Writes a PNG around a raw deflate stream, for images the encoder can't produce.
+    fn png_with_deflate(width: u32, height: u32, deflate: &[u8]) -> Vec<u8> {
+        let mut png = encode(&image(width, height));
+        let header_end = SIGNATURE.len() + 12 + 13;
+        png.truncate(header_end);
+
+        let mut zlib = vec![0x78, 0x01];
+        zlib.extend_from_slice(deflate);
+        zlib.extend_from_slice(&[0; 4]);
+        write_chunk(&mut png, b"IDAT", &zlib);
+        write_chunk(&mut png, b"IEND", &[]);
+        png
+    }
+
+    /// A deflate block with fixed Huffman codes, that repeats a zero byte `repeats * 258 + 1` times.
+    fn zeros_deflate(repeats: usize) -> Vec<u8> {
+        let mut bits = vec![];
+        let mut push = |value: u32, count: u32, msb_first: bool| {
+            for i in 0..count {
+                let shift = match msb_first {
+                    true => count - 1 - i,
+                    false => i,
+                };
+                bits.push((value >> shift) & 1 == 1);
+            }
+        };
+
+        // The last block, with fixed codes.
+        push(1, 1, false);
+        push(1, 2, false);
+        // The literal 0, then matches of length 258 at distance 1, then the end of the block.
+        push(0b0011_0000, 8, true);
+        for _ in 0..repeats {
+            push(0b1100_0101, 8, true);
+            push(0, 5, true);
+        }
+        push(0, 7, true);
+
+        bits.chunks(8)
+            .map(|byte| {
+                byte.iter()
+                    .enumerate()
+                    .fold(0u8, |value, (i, &bit)| value | (bit as u8) << i)
+            })
+            .collect()
+    }
+
+    #[test]
+    fn round_trip() {
+        // 150x150 needs more than one stored deflate block.
+        for (width, height) in [(1, 1), (3, 2), (2, 7), (150, 150)] {
+            let image = image(width, height);
+            assert_eq!(decode(&encode(&image)), Ok(image));
+        }
+    }
+
+    #[test]
+    fn decode_compressed() {
+        let image = decode(&FILTERED_RGB).unwrap();
+        assert_eq!((image.width, image.height), (8, 5));
+        assert_eq!(image.rgba, FILTERED_RGB_PIXELS);
+
+        let image = decode(&PALETTE).unwrap();
+        assert_eq!((image.width, image.height), (3, 2));
+        assert_eq!(image.rgba, PALETTE_PIXELS);
+    }
+
+    #[test]
+    fn decode_fixed_huffman() {
+        // The 259 zeros are the 7 unfiltered rows of a 9x7 RGBA image, 37 bytes each.
+        let image = decode(&png_with_deflate(9, 7, &zeros_deflate(1))).unwrap();
+        assert_eq!(image.rgba, vec![0; 9 * 7 * 4]);
+    }
+
+    #[test]
+    fn decode_truncated() {
+        for png in [
+            encode(&image(5, 3)),
+            FILTERED_RGB.to_vec(),
+            PALETTE.to_vec(),
+        ] {
+            // Everything up to the IEND chunk is needed.
+            for length in 0..png.len() - 12 {
+                assert!(decode(&png[..length]).is_err(), "{length}");
+            }
+        }
+    }
+
+    #[test]
+    fn decode_corrupt() {
+        for png in [
+            encode(&image(5, 3)),
+            FILTERED_RGB.to_vec(),
+            PALETTE.to_vec(),
+        ] {
+            // Whatever is corrupted, decoding must fail or succeed without panicking.
+            for index in 0..png.len() {
+                for mask in [0x01, 0x10, 0xff] {
+                    let mut corrupt = png.clone();
+                    corrupt[index] ^= mask;
+                    let _ = decode(&corrupt);
+                }
+            }
+        }
+    }
+
+    #[test]
+    fn decode_oversized() {
+        // The header claims a 65536x65536 image for a single pixel of data.
+        let mut png = encode(&image(1, 1));
+        let header = SIGNATURE.len() + 8;
+        png[header..header + 8].copy_from_slice(&[0, 1, 0, 0, 0, 1, 0, 0]);
+        assert!(decode(&png).is_err());
+
+        png[header..header + 8].fill(0xff);
+        assert!(decode(&png).is_err());
+    }
+
+    #[test]
+    fn decode_decompression_bomb() {
+        // A few hundred bytes that inflate to a megabyte, for a 1x1 image.
+        assert!(decode(&png_with_deflate(1, 1, &zeros_deflate(4096))).is_err());
+    }
+}
@@ -0,0 +1,256 @@
+//! The Win32 clipboard.
+//!
+//! Text is exchanged as `CF_UNICODETEXT`, images as device independent bitmaps. Bitmaps are written as
+//! `CF_DIBV5` with an alpha mask, from which Windows synthesizes `CF_DIB` for older applications.
+
+use std::{slice, thread, time::Duration};
+
+use windows::Win32::{
+    Foundation::{HANDLE, HWND},
+    System::DataExchange::*,
+    System::Memory::*,
+    System::SystemServices::{CF_DIB, CF_DIBV5, CF_UNICODETEXT},
+};
+
+use super::{ClipboardError, ImageData};
+
+/// How often opening the clipboard is attempted, as other applications may have it open.
+const OPEN_ATTEMPTS: u32 = 10;
+
+/// Bitmap header fields, which are read from and written to raw bytes as their layout depends on the version.
+const BITMAPINFOHEADER_SIZE: usize = 40;
+const BITMAPV5HEADER_SIZE: usize = 124;
+const BI_RGB: u32 = 0;
+const BI_BITFIELDS: u32 = 3;
+const LCS_SRGB: u32 = u32::from_be_bytes(*b"sRGB");
+const LCS_GM_IMAGES: u32 = 4;
+
+pub struct Clipboard {}
+
+impl Clipboard {
+    pub fn new() -> Result<Self, ClipboardError> {
+        Ok(Self {})
+    }
+
+    pub fn get_text(&self) -> Result<String, ClipboardError> {
+        let _guard = ClipboardGuard::open()?;
+        let text = unsafe { read_global(GetClipboardData(CF_UNICODETEXT).0)? };
+
+        let text: Vec<u16> = text
+            .chunks_exact(2)
+            .map(|c| u16::from_le_bytes([c[0], c[1]]))
+            .take_while(|&c| c != 0)
+            .collect();
+        Ok(String::from_utf16_lossy(&text))
+    }
+
+    pub fn set_text(&self, text: &str) -> Result<(), ClipboardError> {
+        let text: Vec<u8> = text
+            .encode_utf16()
+            .chain([0])
+            .flat_map(u16::to_le_bytes)
+            .collect();
+
+        let _guard = ClipboardGuard::open()?;
+        unsafe {
+            EmptyClipboard();
+            write_global(CF_UNICODETEXT, &text)
+        }
+    }
+
+    pub fn get_image(&self) -> Result<ImageData, ClipboardError> {
+        let _guard = ClipboardGuard::open()?;
+
+        // The V5 header may carry an alpha mask, which is lost in the synthesized CF_DIB.
+        let format = match unsafe { IsClipboardFormatAvailable(CF_DIBV5) }.as_bool() {
+            true => CF_DIBV5,
+            false => CF_DIB,
+        };
+        let dib = unsafe { read_global(GetClipboardData(format).0)? };
+        decode_dib(&dib).ok_or(ClipboardError::NotSupported)
+    }
+
+    pub fn set_image(&self, image: &ImageData) -> Result<(), ClipboardError> {
+        let dib = encode_dib(image);
+
+        let _guard = ClipboardGuard::open()?;
+        unsafe {
+            EmptyClipboard();
+            write_global(CF_DIBV5, &dib)
+        }
+    }
+}
+
+/// Keeps the clipboard open until dropped.
+struct ClipboardGuard;
+
+impl ClipboardGuard {
+    fn open() -> Result<Self, ClipboardError> {
+        for _ in 0..OPEN_ATTEMPTS {
+            if unsafe { OpenClipboard(HWND::default()) }.as_bool() {
+                return Ok(Self);
+            }
+            thread::sleep(Duration::from_millis(10));
+        }
+        Err(ClipboardError::Timeout)
+    }
+}
+
+impl Drop for ClipboardGuard {
+    fn drop(&mut self) {
+        unsafe { CloseClipboard() };
+    }
+}
+
+/// Copies the contents of a global memory object.
+unsafe fn read_global(memory: isize) -> Result<Vec<u8>, ClipboardError> {
+    if memory == 0 {
+        return Err(ClipboardError::Empty);
+    }
+
+    let data = GlobalLock(memory);
+    if data.is_null() {
+        return Err(ClipboardError::Unknown);
+    }
+
+    let contents = slice::from_raw_parts(data as *const u8, GlobalSize(memory)).to_vec();
+    GlobalUnlock(memory);
+    Ok(contents)
+}
+
+/// Puts data on the open clipboard, which takes ownership of the memory.
+unsafe fn write_global(format: u32, data: &[u8]) -> Result<(), ClipboardError> {
+    let memory = GlobalAlloc(GMEM_MOVEABLE, data.len());
+    if memory == 0 {
+        return Err(ClipboardError::Unknown);
+    }
+
+    let locked = GlobalLock(memory);
+    if locked.is_null() {
+        GlobalFree(memory);
+        return Err(ClipboardError::Unknown);
+    }
+    std::ptr::copy_nonoverlapping(data.as_ptr(), locked as *mut u8, data.len());
+    GlobalUnlock(memory);
+
+    if SetClipboardData(format, HANDLE(memory)).is_invalid() {
+        GlobalFree(memory);
+        return Err(ClipboardError::Unknown);
+    }
+
+    Ok(())
+}
+
+/// Writes a 32-bit bottom-up BITMAPV5HEADER bitmap, which most applications expect.
+fn encode_dib(image: &ImageData) -> Vec<u8> {
+    let mut dib = Vec::with_capacity(BITMAPV5HEADER_SIZE + image.rgba.len());
+    let mut write = |value: u32| dib.extend_from_slice(&value.to_le_bytes());
+
+    write(BITMAPV5HEADER_SIZE as u32);
+    write(image.width);
+    write(image.height);
+    // Planes and bits per pixel.
+    write(1 | 32 << 16);
+    write(BI_BITFIELDS);
+    write(image.rgba.len() as u32);
+    // Resolution and color table.
+    for _ in 0..4 {
+        write(0);
+    }
+    // Red, green, blue and alpha masks, for pixels stored as BGRA.
+    write(0x00ff_0000);
+    write(0x0000_ff00);
+    write(0x0000_00ff);
+    write(0xff00_0000);
+    write(LCS_SRGB);
+    // Endpoints and gamma, which are only used by calibrated color spaces.
+    for _ in 0..12 {
+        write(0);
+    }
+    write(LCS_GM_IMAGES);
+    // Profile data and size, and a reserved field.
+    for _ in 0..3 {
+        write(0);
+    }
+
+    let stride = image.width as usize * 4;
+    for row in image.rgba.chunks_exact(stride.max(1)).rev() {
+        for pixel in row.chunks_exact(4) {
+            dib.extend_from_slice(&[pixel[2], pixel[1], pixel[0], pixel[3]]);
+        }
+    }
+
+    dib
+}
+
+/// Reads a 24 or 32-bit uncompressed bitmap.
+fn decode_dib(dib: &[u8]) -> Option<ImageData> {
+    let read = |offset: usize| {
+        let bytes = dib.get(offset..offset + 4)?;
+        Some(u32::from_le_bytes([bytes[0], bytes[1], bytes[2], bytes[3]]))
+    };
+
+    let header_size = read(0)? as usize;
+    let width = read(4)? as i32;
+    let height = read(8)? as i32;
+    let bit_count = (read(12)? >> 16) as usize;
+    let compression = read(16)?;
+    let color_table_size = read(32)? as usize * 4;
+    if header_size < BITMAPINFOHEADER_SIZE || width <= 0 || height == 0 {
+        return None;
+    }
+
+    // Masks follow BITMAPINFOHEADER, while later versions include them in the header.
+    let (masks, data_offset) = match (compression, bit_count) {
+        (BI_BITFIELDS, 32) if header_size == BITMAPINFOHEADER_SIZE => {
+            ([read(40)?, read(44)?, read(48)?, 0], header_size + 12)
+        }
+        (BI_BITFIELDS, 32) => {
+            let alpha = match header_size >= 56 {
+                true => read(52)?,
+                false => 0,
+            };
+            ([read(40)?, read(44)?, read(48)?, alpha], header_size)
+        }
+        (BI_RGB, 24 | 32) => ([0xff_0000, 0xff00, 0xff, 0], header_size),
+        _ => return None,
+    };
+
+    let bytes_per_pixel = bit_count / 8;
+    let stride = (width as usize * bytes_per_pixel + 3) & !3;
+    let rows = height.unsigned_abs() as usize;
+    let data_offset = data_offset + color_table_size;
+    let data = dib.get(data_offset..data_offset + stride * rows)?;
+
+    let channel = |pixel: u32, mask: u32| -> u8 {
+        if mask == 0 {
+            return 255;
+        }
+        let value = (pixel & mask) >> mask.trailing_zeros();
+        let max = mask >> mask.trailing_zeros();
+        (value as u64 * 255 / max as u64) as u8
+    };
+
+    let mut rgba = Vec::with_capacity(width as usize * rows * 4);
+    for y in 0..rows {
+        // Positive heights are stored bottom-up.
+        let row = match height > 0 {
+            true => rows - 1 - y,
+            false => y,
+        };
+        let row = &data[row * stride..row * stride + width as usize * bytes_per_pixel];
+
+        for pixel in row.chunks_exact(bytes_per_pixel) {
+            let mut bytes = [0; 4];
+            bytes[..bytes_per_pixel].copy_from_slice(pixel);
+            let pixel = u32::from_le_bytes(bytes);
+            rgba.extend(masks.iter().map(|&mask| channel(pixel, mask)));
+        }
+    }
+
+    Some(ImageData {
+        width: width as u32,
+        height: rows as u32,
+        rgba,
+    })
+}
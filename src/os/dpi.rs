@@ -0,0 +1,113 @@
+/// A size in logical pixels, which stay the same physical size on every monitor.
+///
+/// Multiplying by the scale factor of a window gives the size in [`PhysicalSize`],
+/// so UI layout is done in logical pixels, while swapchains are sized in physical ones.
+#[derive(Debug, Clone, Copy, PartialEq, Default)]
+pub struct LogicalSize {
+    pub width: f64,
+    pub height: f64,
+}
+
+impl LogicalSize {
+    pub const fn new(width: f64, height: f64) -> Self {
+        Self { width, height }
+    }
+
+    /// Converts to physical pixels, rounding to the nearest pixel.
+    ///
+    /// # Arguments
+    ///
+    /// - `scale_factor` - The scale factor of the window, see [`Window::scale_factor()`](super::Window::scale_factor).
+    pub fn to_physical(self, scale_factor: f64) -> PhysicalSize {
+        PhysicalSize {
+            width: (self.width * scale_factor).round().max(0.0) as u32,
+            height: (self.height * scale_factor).round().max(0.0) as u32,
+        }
+    }
+}
+
+impl From<(f64, f64)> for LogicalSize {
+    fn from((width, height): (f64, f64)) -> Self {
+        Self { width, height }
+    }
+}
+
+/// A size in pixels of the display.
+#[derive(Debug, Clone, Copy, PartialEq, Eq, Hash, Default)]
+pub struct PhysicalSize {
+    pub width: u32,
+    pub height: u32,
+}
+
+impl PhysicalSize {
+    pub const fn new(width: u32, height: u32) -> Self {
+        Self { width, height }
+    }
+
+    /// Converts to logical pixels.
+    ///
+    /// # Arguments
+    ///
+    /// - `scale_factor` - The scale factor of the window, see [`Window::scale_factor()`](super::Window::scale_factor).
+    pub fn to_logical(self, scale_factor: f64) -> LogicalSize {
+        LogicalSize {
+            width: self.width as f64 / scale_factor,
+            height: self.height as f64 / scale_factor,
+        }
+    }
+}
+
+impl From<(u32, u32)> for PhysicalSize {
+    fn from((width, height): (u32, u32)) -> Self {
+        Self { width, height }
+    }
+}
+
+impl From<PhysicalSize> for (u32, u32) {
+    fn from(size: PhysicalSize) -> Self {
+        (size.width, size.height)
+    }
+}
+
+/// A size in either unit, converted with the scale factor of the window it is applied to.
+///
+/// Plain `(u32, u32)` tuples convert to physical sizes.
+#[derive(Debug, Clone, Copy, PartialEq)]
+pub enum Size {
+    Logical(LogicalSize),
+    Physical(PhysicalSize),
+}
+
+impl Size {
+    pub fn to_physical(self, scale_factor: f64) -> PhysicalSize {
+        match self {
+            Self::Logical(size) => size.to_physical(scale_factor),
+            Self::Physical(size) => size,
+        }
+    }
+
+    pub fn to_logical(self, scale_factor: f64) -> LogicalSize {
+        match self {
+            Self::Logical(size) => size,
+            Self::Physical(size) => size.to_logical(scale_factor),
+        }
+    }
+}
+
+impl From<LogicalSize> for Size {
+    fn from(size: LogicalSize) -> Self {
+        Self::Logical(size)
+    }
+}
+
+impl From<PhysicalSize> for Size {
+    fn from(size: PhysicalSize) -> Self {
+        Self::Physical(size)
+    }
+}
+
+impl From<(u32, u32)> for Size {
+    fn from(size: (u32, u32)) -> Self {
+        Self::Physical(size.into())
+    }
+}
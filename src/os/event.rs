@@ -0,0 +1,133 @@
+use std::path::PathBuf;
+
+use super::{KeyCode, Modifiers, ScanCode};
+
+/// An event delivered by [`Window::poll_events()`](super::Window::poll_events).
+///
+/// Sizes and positions are in physical pixels. Events a platform can't observe are never emitted,
+/// e.g. Wayland doesn't tell clients about their position, so [`Event::Moved`] never occurs there.
+#[derive(Debug, Clone, PartialEq)]
+pub enum Event {
+    /// The user requested the window to be closed.
+    CloseRequested,
+
+    /// The inner size of the window changed, in physical pixels.
+    ///
+    /// Native windows also deliver it on their first poll, with their initial inner size.
+    Resized { width: u32, height: u32 },
+
+    /// The window was moved to the given position in screen coordinates.
+    Moved { x: i32, y: i32 },
+
+    /// The window gained (`true`) or lost (`false`) input focus.
+    Focused(bool),
+
+    /// The window was minimized (`true`) or restored (`false`).
+    Minimized(bool),
+
+    /// The scale factor of the window changed, e.g. because it was moved to another monitor.
+    ///
+    /// Native windows keep their logical size, so this is followed by [`Event::Resized`].
+    ScaleFactorChanged { scale_factor: f64 },
+
+    /// A key was pressed or released.
+    KeyboardInput {
+        /// The physical key.
+        scancode: ScanCode,
+
+        /// The key as labeled by the keyboard layout, `None` if there is no matching [`KeyCode`].
+        keycode: Option<KeyCode>,
+
+        state: ElementState,
+
+        /// The modifiers that were active when the key event occurred.
+        modifiers: Modifiers,
+
+        /// Whether this is a repeated press, generated while the key is held down.
+        repeat: bool,
+    },
+
+    /// A character was typed, only emitted while text input is enabled, see [`Window::set_text_input()`](super::Window::set_text_input).
+    ///
+    /// Unlike [`Event::KeyboardInput`] this has the keyboard layout, modifiers and dead keys applied.
+    /// Control characters, like backspace or enter, are not reported.
+    ReceivedCharacter(char),
+
+    /// An input method composed text, only emitted while text input is enabled.
+    Ime(Ime),
+
+    /// The cursor moved to the given position relative to the top-left corner of the window.
+    CursorMoved { x: f64, y: f64 },
+
+    /// The cursor entered the window.
+    CursorEntered,
+
+    /// The cursor left the window.
+    CursorLeft,
+
+    /// A mouse button was pressed or released.
+    MouseInput {
+        button: MouseButton,
+        state: ElementState,
+    },
+
+    /// The mouse wheel was scrolled. One notch of a wheel equals a delta of 1.0.
+    ///
+    /// Positive `delta_x` scrolls right, positive `delta_y` scrolls up.
+    /// Devices with a finer resolution, like touchpads, report fractions of a notch.
+    MouseWheel { delta_x: f32, delta_y: f32 },
+
+    /// The mouse moved by the given amount, only emitted while the window is focused.
+    ///
+    /// Unlike [`Event::CursorMoved`] this is the raw motion of the device, without acceleration,
+    /// and it isn't limited by the edges of the screen or a [`CursorGrabMode`](super::CursorGrabMode).
+    MouseMotion { delta_x: f64, delta_y: f64 },
+
+    /// A file is dragged over the window, only emitted while drag and drop is enabled, see
+    /// [`Window::set_drag_and_drop()`](super::Window::set_drag_and_drop).
+    ///
+    /// Emitted once for every dragged file when the drag enters the window, at the position of the cursor.
+    /// While the files hover the window, the cursor position is reported with [`Event::CursorMoved`].
+    HoveredFile { path: PathBuf, x: f64, y: f64 },
+
+    /// The files hovering the window left it, or the drag was cancelled.
+    HoveredFileCancelled,
+
+    /// A file was dropped on the window at the given position, emitted once for every dropped file.
+    DroppedFile { path: PathBuf, x: f64, y: f64 },
+}
+
+/// The composition of text by an input method, e.g. to enter CJK characters.
+///
+/// While composing, the keys the user types go to the input method instead of the window,
+/// and the text is shown by the application at the position set with
+/// [`Window::set_ime_position()`](super::Window::set_ime_position).
+#[derive(Debug, Clone, PartialEq, Eq)]
+pub enum Ime {
+    /// The text being composed changed. An empty text ends the composition.
+    Preedit {
+        text: String,
+
+        /// The position of the caret within `text` in bytes, `None` if it should be hidden.
+        cursor: Option<usize>,
+    },
+
+    /// The composition is done and `text` should be inserted.
+    Commit(String),
+}
+
+#[derive(Debug, Clone, Copy, PartialEq, Eq, Hash)]
+pub enum ElementState {
+    Pressed,
+    Released,
+}
+
+#[derive(Debug, Clone, Copy, PartialEq, Eq, Hash)]
+pub enum MouseButton {
+    Left,
+    Right,
+    Middle,
+    Back,
+    Forward,
+    Other(u16),
+}
@@ -0,0 +1,106 @@
+//! Driving any number of windows from a single loop.
+
+use std::{
+    cell::RefCell,
+    rc::{Rc, Weak},
+    sync::atomic::{AtomicU64, Ordering},
+};
+
+use super::{Event, Window, WindowDesc, WindowError, WindowInner};
+
+/// Identifies a window. Identifiers are never reused, not even after the window is dropped.
+#[derive(Debug, Clone, Copy, PartialEq, Eq, PartialOrd, Ord, Hash)]
+pub struct WindowId(u64);
+
+impl WindowId {
+    pub(super) fn next() -> Self {
+        static NEXT: AtomicU64 = AtomicU64::new(0);
+        Self(NEXT.fetch_add(1, Ordering::Relaxed))
+    }
+}
+
+/// Pumps the events of all windows created through it, tagged with the window they belong to.
+///
+/// The windows are owned by the application, the loop only keeps track of them.
+/// A window is destroyed when it is dropped, which is also allowed while events are being polled.
+/// Once all windows are dropped, [`EventLoop::is_empty()`] tells the application it can exit.
+#[derive(Default)]
+pub struct EventLoop {
+    windows: RefCell<Vec<Weak<WindowInner>>>,
+}
+
+impl EventLoop {
+    pub fn new() -> Self {
+        Self::default()
+    }
+
+    /// Creates a new window, whose events are delivered by [`EventLoop::poll_events()`].
+    ///
+    /// See [`Window::new()`].
+    ///
+    /// # Arguments
+    ///
+    /// - `desc` - Description of the window.
+    pub fn create_window(&self, desc: &WindowDesc) -> Result<Window, WindowError> {
+        let window = Window::new(desc)?;
+        self.register(&window);
+        Ok(window)
+    }
+
+    /// Creates a new headless window, whose events are delivered by [`EventLoop::poll_events()`].
+    ///
+    /// See [`Window::new_headless()`].
+    ///
+    /// # Arguments
+    ///
+    /// - `desc` - Description of the window.
+    pub fn create_headless_window(&self, desc: &WindowDesc) -> Window {
+        let window = Window::new_headless(desc);
+        self.register(&window);
+        window
+    }
+
+    fn register(&self, window: &Window) {
+        self.windows.borrow_mut().push(Rc::downgrade(&window.inner));
+    }
+
+    /// Returns the identifiers of the windows that haven't been dropped, in the order they were created.
+    pub fn window_ids(&self) -> Vec<WindowId> {
+        let windows = self.windows.borrow();
+        windows
+            .iter()
+            .filter_map(Weak::upgrade)
+            .map(|window| window.id)
+            .collect()
+    }
+
+    /// Returns whether all windows created through the loop have been dropped.
+    pub fn is_empty(&self) -> bool {
+        self.windows
+            .borrow()
+            .iter()
+            .all(|window| window.strong_count() == 0)
+    }
+
+    /// Processes the pending events of all windows and passes them to `callback`,
+    /// window by window in the order they were created.
+    ///
+    /// Like [`Window::poll_events()`] this never blocks, and updates the input states of the windows.
+    /// Windows created by `callback` are polled starting with the next call,
+    /// windows dropped by it receive no further events.
+    pub fn poll_events<F: FnMut(WindowId, Event)>(&self, mut callback: F) {
+        // The list is copied, so the callback can create windows while it is iterated.
+        let windows = {
+            let mut windows = self.windows.borrow_mut();
+            windows.retain(|window| window.strong_count() > 0);
+            windows.clone()
+        };
+
+        // Each window is kept alive while its own events are delivered,
+        // since the callback may drop it while the platform is still processing its events.
+        for window in windows.iter().filter_map(Weak::upgrade) {
+            let id = window.id;
+            window.poll_events(&mut |event| callback(id, event));
+        }
+    }
+}
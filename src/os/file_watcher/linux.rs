@@ -0,0 +1,296 @@
+//! Watching directories through inotify.
+//!
+//! inotify watches single directories, so recursive watches add a watch for every subdirectory,
+//! including those created or moved in later.
+
+use std::{
+    collections::HashMap,
+    ffi::{CString, OsStr},
+    fs, io,
+    mem::size_of,
+    os::{raw::c_int, unix::ffi::OsStrExt},
+    path::{Path, PathBuf},
+    time::{Duration, Instant},
+};
+
+use super::{FileEvent, WatchError};
+
+const WATCH_MASK: u32 = libc::IN_CREATE
+    | libc::IN_MODIFY
+    | libc::IN_ATTRIB
+    | libc::IN_DELETE
+    | libc::IN_MOVED_FROM
+    | libc::IN_MOVED_TO
+    | libc::IN_DELETE_SELF
+    | libc::IN_MOVE_SELF
+    | libc::IN_ONLYDIR;
+
+/// The size of the buffer events are read into, enough for a few hundred events per read.
+const BUFFER_SIZE: usize = 64 * 1024;
+
+struct Watch {
+    path: PathBuf,
+    recursive: bool,
+
+    /// Whether the directory was passed to `watch`, rather than being a subdirectory of one.
+    root: bool,
+}
+
+/// The source of a rename that hasn't been matched with its destination yet.
+struct MovedFrom {
+    cookie: u32,
+    path: PathBuf,
+    is_dir: bool,
+    since: Instant,
+}
+
+pub struct FileWatcher {
+    fd: c_int,
+    watches: HashMap<c_int, Watch>,
+    buffer: Vec<u8>,
+
+    /// The kernel may queue the destination of a rename after a poll already read its source,
+    /// so sources are kept for the debounce time before the file counts as removed.
+    moved_from: Vec<MovedFrom>,
+}
+
+impl FileWatcher {
+    pub fn new() -> Result<Self, WatchError> {
+        let fd = unsafe { libc::inotify_init1(libc::IN_NONBLOCK | libc::IN_CLOEXEC) };
+        if fd < 0 {
+            return Err(error(io::Error::last_os_error()));
+        }
+
+        Ok(Self {
+            fd,
+            watches: HashMap::new(),
+            buffer: vec![0; BUFFER_SIZE],
+            moved_from: Vec::new(),
+        })
+    }
+
+    pub fn watch(&mut self, path: &Path, recursive: bool) -> Result<(), WatchError> {
+        // A directory that was watched recursively loses the watches of its subdirectories.
+        if !recursive {
+            self.remove_watches(|watch| {
+                watch.path.starts_with(path) && watch.path != path && !watch.root
+            });
+        }
+
+        self.add_tree(path, recursive, true, &mut None)
+    }
+
+    pub fn unwatch(&mut self, path: &Path) {
+        let recursive = self
+            .watches
+            .values()
+            .any(|watch| watch.root && watch.recursive && watch.path == path);
+
+        self.remove_watches(|watch| {
+            watch.path == path || (recursive && watch.path.starts_with(path) && !watch.root)
+        });
+    }
+
+    pub fn poll(&mut self, debounce: Duration, callback: &mut dyn FnMut(FileEvent)) {
+        loop {
+            let read = unsafe {
+                libc::read(
+                    self.fd,
+                    self.buffer.as_mut_ptr() as *mut _,
+                    self.buffer.len(),
+                )
+            };
+            if read <= 0 {
+                break;
+            }
+
+            let mut offset = 0;
+            while offset + size_of::<libc::inotify_event>() <= read as usize {
+                // SAFETY: The kernel only writes whole events, the buffer may not be aligned for them.
+                let event = unsafe {
+                    (self.buffer.as_ptr().add(offset) as *const libc::inotify_event)
+                        .read_unaligned()
+                };
+                let name_start = offset + size_of::<libc::inotify_event>();
+                let name = &self.buffer[name_start..name_start + event.len as usize];
+                let name = OsStr::from_bytes(name.split(|&c| c == 0).next().unwrap_or_default())
+                    .to_owned();
+                offset = name_start + event.len as usize;
+
+                self.handle_event(&event, Path::new(&name), callback);
+            }
+        }
+
+        // Files moved out of the watched directories only have a source.
+        let now = Instant::now();
+        let (expired, moved_from) = std::mem::take(&mut self.moved_from)
+            .into_iter()
+            .partition(|moved_from| now.duration_since(moved_from.since) >= debounce);
+        self.moved_from = moved_from;
+
+        for MovedFrom { path, is_dir, .. } in expired {
+            if is_dir {
+                self.remove_watches(|watch| watch.path.starts_with(&path) && !watch.root);
+            }
+            callback(FileEvent::Removed(path));
+        }
+    }
+
+    fn handle_event(
+        &mut self,
+        event: &libc::inotify_event,
+        name: &Path,
+        callback: &mut dyn FnMut(FileEvent),
+    ) {
+        let mask = event.mask;
+        if mask & libc::IN_IGNORED != 0 {
+            self.watches.remove(&event.wd);
+            return;
+        }
+
+        // Events are lost on overflow, which can't be recovered from.
+        let watch = match self.watches.get(&event.wd) {
+            Some(watch) => watch,
+            None => return,
+        };
+        let is_dir = mask & libc::IN_ISDIR != 0;
+        let recursive = watch.recursive;
+
+        // Events without a name are about the watched directory itself, which its parent reports,
+        // unless it is the root of a watch.
+        if name.as_os_str().is_empty() {
+            if watch.root && mask & (libc::IN_DELETE_SELF | libc::IN_MOVE_SELF) != 0 {
+                callback(FileEvent::Removed(watch.path.clone()));
+            }
+            return;
+        }
+        let path = watch.path.join(name);
+
+        if mask & libc::IN_CREATE != 0 {
+            callback(FileEvent::Created(path.clone()));
+
+            // Files may have been created in the directory before it was watched.
+            if is_dir && recursive {
+                let _ = self.add_tree(&path, true, false, &mut Some(callback));
+            }
+        } else if mask & (libc::IN_MODIFY | libc::IN_ATTRIB) != 0 && !is_dir {
+            callback(FileEvent::Modified(path));
+        } else if mask & libc::IN_DELETE != 0 {
+            callback(FileEvent::Removed(path));
+        } else if mask & libc::IN_MOVED_FROM != 0 {
+            self.moved_from.push(MovedFrom {
+                cookie: event.cookie,
+                path,
+                is_dir,
+                since: Instant::now(),
+            });
+        } else if mask & libc::IN_MOVED_TO != 0 {
+            match self
+                .moved_from
+                .iter()
+                .position(|moved_from| moved_from.cookie == event.cookie)
+            {
+                Some(index) => {
+                    let from = self.moved_from.remove(index).path;
+
+                    // The watches of a moved directory stay, only their paths change.
+                    if is_dir {
+                        for watch in self.watches.values_mut() {
+                            if let Ok(relative) = watch.path.strip_prefix(&from) {
+                                watch.path = path.join(relative);
+                            }
+                        }
+                    }
+                    callback(FileEvent::Renamed { from, to: path });
+                }
+                None => {
+                    callback(FileEvent::Created(path.clone()));
+                    if is_dir && recursive {
+                        let _ = self.add_tree(&path, true, false, &mut Some(callback));
+                    }
+                }
+            }
+        }
+    }
+
+    /// Watches a directory, and its subdirectories if `recursive` is set.
+    ///
+    /// The files found in a directory created after the watch are passed to `callback` if given,
+    /// since they may have been added before the directory was watched.
+    fn add_tree(
+        &mut self,
+        path: &Path,
+        recursive: bool,
+        root: bool,
+        callback: &mut Option<&mut dyn FnMut(FileEvent)>,
+    ) -> Result<(), WatchError> {
+        let name = match CString::new(path.as_os_str().as_bytes()) {
+            Ok(name) => name,
+            _ => return Err(WatchError::NotFound),
+        };
+
+        let wd = unsafe { libc::inotify_add_watch(self.fd, name.as_ptr(), WATCH_MASK) };
+        if wd < 0 {
+            return Err(error(io::Error::last_os_error()));
+        }
+
+        // The same directory watched again keeps being a root.
+        let root = root || self.watches.get(&wd).is_some_and(|watch| watch.root);
+        self.watches.insert(
+            wd,
+            Watch {
+                path: path.to_owned(),
+                recursive,
+                root,
+            },
+        );
+
+        if !recursive {
+            return Ok(());
+        }
+
+        let entries = match fs::read_dir(path) {
+            Ok(entries) => entries,
+            Err(_) => return Ok(()),
+        };
+        for entry in entries.flatten() {
+            let entry_path = entry.path();
+            if let Some(callback) = callback {
+                callback(FileEvent::Created(entry_path.clone()));
+            }
+
+            // Subdirectories that can't be watched, e.g. for lack of permission, are skipped.
+            if entry.file_type().is_ok_and(|kind| kind.is_dir()) {
+                let _ = self.add_tree(&entry_path, true, false, callback);
+            }
+        }
+
+        Ok(())
+    }
+
+    fn remove_watches(&mut self, mut filter: impl FnMut(&Watch) -> bool) {
+        let fd = self.fd;
+        self.watches.retain(|&wd, watch| {
+            let remove = filter(watch);
+            if remove {
+                unsafe { libc::inotify_rm_watch(fd, wd) };
+            }
+            !remove
+        });
+    }
+}
+
+impl Drop for FileWatcher {
+    fn drop(&mut self) {
+        // Closing the descriptor removes all watches.
+        unsafe { libc::close(self.fd) };
+    }
+}
+
+fn error(error: io::Error) -> WatchError {
+    match error.raw_os_error() {
+        Some(libc::ENOSPC | libc::EMFILE) => WatchError::LimitReached,
+        Some(libc::ENOENT | libc::ENOTDIR) => WatchError::NotFound,
+        _ => WatchError::Io(error.kind()),
+    }
+}
@@ -0,0 +1,378 @@
+//! Watching directories for changed files, e.g. to reload shaders and assets while the engine runs.
+//!
+//! Editors and build tools rarely change a file with a single operation. They write it in several chunks,
+//! or write a temporary file and rename it over the original. The platforms report every step, so events
+//! of the same file are held back until it hasn't changed for a while, and merged into one.
+
+#[cfg(target_os = "linux")]
+mod linux;
+
+#[cfg(target_os = "linux")]
+use linux as imp;
+
+#[cfg(target_os = "windows")]
+mod windows;
+
+#[cfg(target_os = "windows")]
+use self::windows as imp;
+
+use std::{
+    io,
+    path::{Path, PathBuf},
+    time::{Duration, Instant},
+};
+
+/// How long a file must be left alone before its events are delivered, see [`FileWatcher::set_debounce()`].
+const DEFAULT_DEBOUNCE: Duration = Duration::from_millis(100);
+
+#[derive(Debug, Clone, Copy, PartialEq, Eq)]
+pub enum WatchError {
+    /// The directory doesn't exist, or isn't a directory.
+    NotFound,
+
+    /// The system limit on watches or watchers was reached.
+    ///
+    /// On Linux the limits are set by `fs.inotify.max_user_watches` and `fs.inotify.max_user_instances`.
+    LimitReached,
+    Io(io::ErrorKind),
+}
+
+impl From<io::Error> for WatchError {
+    fn from(error: io::Error) -> Self {
+        Self::Io(error.kind())
+    }
+}
+
+/// A change to a file or directory within a watched directory.
+#[derive(Debug, Clone, PartialEq, Eq, Hash)]
+pub enum FileEvent {
+    Created(PathBuf),
+    Modified(PathBuf),
+    Removed(PathBuf),
+
+    /// The file was moved within the watched directories.
+    ///
+    /// Files moved into a watched directory from elsewhere are reported as created,
+    /// and files moved out of them as removed.
+    Renamed {
+        from: PathBuf,
+        to: PathBuf,
+    },
+}
+
+/// The merged events of a file that are held back.
+#[derive(Debug, Clone, PartialEq, Eq)]
+enum Pending {
+    Created,
+    Modified,
+    Removed,
+    Renamed { from: PathBuf },
+}
+
+/// Watches directories for changes to the files in them.
+///
+/// Events are collected in the background by the platform, and delivered by [`FileWatcher::poll()`],
+/// which never blocks and is meant to be called once per frame, e.g. next to
+/// [`Window::poll_events()`](super::Window::poll_events).
+///
+/// The events of a file are merged until it hasn't changed for the debounce time:
+///
+/// - A file that is created and then modified is reported as created.
+/// - A file that is removed and created again is reported as modified.
+/// - A file that is created and removed again isn't reported at all.
+/// - A file that is created and then renamed is reported as created under its new name. Editors that save
+///   through a temporary file replace the original this way, so a file that is already known may be reported as created.
+///
+/// **Note**: If the platform drops events, e.g. because too many files changed at once, they are lost.
+pub struct FileWatcher {
+    imp: imp::FileWatcher,
+    debounce: Duration,
+
+    /// The held back events by the path they apply to, in the order the paths last changed.
+    pending: Vec<(PathBuf, Pending, Instant)>,
+}
+
+impl FileWatcher {
+    pub fn new() -> Result<Self, WatchError> {
+        Ok(Self {
+            imp: imp::FileWatcher::new()?,
+            debounce: DEFAULT_DEBOUNCE,
+            pending: Vec::new(),
+        })
+    }
+
+    /// Starts watching a directory. Watching a directory again changes whether it is watched recursively.
+    ///
+    /// # Arguments
+    ///
+    /// - `path` - The directory to watch. Events carry paths starting with this path.
+    /// - `recursive` - Whether subdirectories are watched as well, including those created later.
+    pub fn watch(&mut self, path: impl AsRef<Path>, recursive: bool) -> Result<(), WatchError> {
+        let path = path.as_ref();
+        if !path.is_dir() {
+            return Err(WatchError::NotFound);
+        }
+
+        self.imp.watch(path, recursive)
+    }
+
+    /// Stops watching a directory. Events that were already collected are still delivered.
+    ///
+    /// # Arguments
+    ///
+    /// - `path` - The directory, as it was passed to [`FileWatcher::watch()`].
+    pub fn unwatch(&mut self, path: impl AsRef<Path>) {
+        self.imp.unwatch(path.as_ref())
+    }
+
+    /// Sets how long a file must be left alone before its events are delivered, 100ms by default.
+    ///
+    /// # Arguments
+    ///
+    /// - `debounce` - The time to wait, zero delivers events on the next poll.
+    pub fn set_debounce(&mut self, debounce: Duration) {
+        self.debounce = debounce;
+    }
+
+    /// Collects new events, and passes those that are due to `callback`.
+    pub fn poll<F: FnMut(FileEvent)>(&mut self, mut callback: F) {
+        let now = Instant::now();
+        let mut events = Vec::new();
+        self.imp
+            .poll(self.debounce, &mut |event| events.push(event));
+        for event in events {
+            self.merge(event, now);
+        }
+
+        let debounce = self.debounce;
+        let (due, pending) = std::mem::take(&mut self.pending)
+            .into_iter()
+            .partition(|(_, _, changed)| now.duration_since(*changed) >= debounce);
+        self.pending = pending;
+
+        for (path, pending, _) in due {
+            callback(match pending {
+                Pending::Created => FileEvent::Created(path),
+                Pending::Modified => FileEvent::Modified(path),
+                Pending::Removed => FileEvent::Removed(path),
+                Pending::Renamed { from } => FileEvent::Renamed { from, to: path },
+            });
+        }
+    }
+
+    /// Merges an event into the held back events of its file.
+    fn merge(&mut self, event: FileEvent, now: Instant) {
+        let (path, pending) = match event {
+            FileEvent::Created(path) => {
+                let pending = match self.take(&path) {
+                    None => Some(Pending::Created),
+                    Some(Pending::Removed) => Some(Pending::Modified),
+                    Some(pending) => Some(pending),
+                };
+                (path, pending)
+            }
+            FileEvent::Modified(path) => {
+                let pending = match self.take(&path) {
+                    None | Some(Pending::Removed) => Some(Pending::Modified),
+                    Some(pending) => Some(pending),
+                };
+                (path, pending)
+            }
+            FileEvent::Removed(path) => match self.take(&path) {
+                Some(Pending::Created) => (path, None),
+                Some(Pending::Renamed { from }) => (from, Some(Pending::Removed)),
+                _ => (path, Some(Pending::Removed)),
+            },
+            FileEvent::Renamed { from, to } => {
+                let pending = match self.take(&from) {
+                    Some(Pending::Created) => Pending::Created,
+                    Some(Pending::Renamed { from }) if from == to => Pending::Modified,
+                    Some(Pending::Renamed { from }) => Pending::Renamed { from },
+                    _ => Pending::Renamed { from },
+                };
+
+                // A file that was removed before is replaced.
+                let pending = match (self.take(&to), pending) {
+                    (Some(Pending::Removed), Pending::Created) => Pending::Modified,
+                    (_, pending) => pending,
+                };
+                (to, Some(pending))
+            }
+        };
+
+        if let Some(pending) = pending {
+            self.pending.push((path, pending, now));
+        }
+    }
+
+    /// Removes the held back events of a path.
+    fn take(&mut self, path: &Path) -> Option<Pending> {
+        let index = self
+            .pending
+            .iter()
+            .position(|(pending, _, _)| pending == path)?;
+        Some(self.pending.remove(index).1)
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    /// Merges the events, and returns what is delivered once they are due.
+    fn merged(events: impl IntoIterator<Item = FileEvent>) -> Vec<FileEvent> {
+        let mut watcher = FileWatcher::new().unwrap();
+        watcher.set_debounce(Duration::ZERO);
+
+        let now = Instant::now();
+        for event in events {
+            watcher.merge(event, now);
+        }
+
+        let mut merged = Vec::new();
+        watcher.poll(|event| merged.push(event));
+        merged
+    }
+
+    fn path(name: &str) -> PathBuf {
+        PathBuf::from(name)
+    }
+
+    #[test]
+    fn create_and_modify() {
+        let events = [
+            FileEvent::Created(path("a")),
+            FileEvent::Modified(path("a")),
+            FileEvent::Modified(path("a")),
+        ];
+        assert_eq!(merged(events), [FileEvent::Created(path("a"))]);
+    }
+
+    #[test]
+    fn remove_and_create() {
+        let events = [FileEvent::Removed(path("a")), FileEvent::Created(path("a"))];
+        assert_eq!(merged(events), [FileEvent::Modified(path("a"))]);
+    }
+
+    #[test]
+    fn create_and_remove() {
+        let events = [
+            FileEvent::Created(path("a")),
+            FileEvent::Modified(path("a")),
+            FileEvent::Removed(path("a")),
+        ];
+        assert_eq!(merged(events), []);
+    }
+
+    #[test]
+    fn rename_chain() {
+        let events = [
+            FileEvent::Renamed {
+                from: path("a"),
+                to: path("b"),
+            },
+            FileEvent::Renamed {
+                from: path("b"),
+                to: path("c"),
+            },
+        ];
+        assert_eq!(
+            merged(events),
+            [FileEvent::Renamed {
+                from: path("a"),
+                to: path("c"),
+            }]
+        );
+    }
+
+    #[test]
+    fn rename_back() {
+        let events = [
+            FileEvent::Renamed {
+                from: path("a"),
+                to: path("b"),
+            },
+            FileEvent::Renamed {
+                from: path("b"),
+                to: path("a"),
+            },
+        ];
+        assert_eq!(merged(events), [FileEvent::Modified(path("a"))]);
+    }
+
+    #[test]
+    fn rename_and_remove() {
+        let events = [
+            FileEvent::Renamed {
+                from: path("a"),
+                to: path("b"),
+            },
+            FileEvent::Removed(path("b")),
+        ];
+        assert_eq!(merged(events), [FileEvent::Removed(path("a"))]);
+    }
+
+    #[test]
+    fn save_through_temporary_file() {
+        // The way editors replace a file, which is reported as modified since it existed before.
+        let events = [
+            FileEvent::Created(path("a.tmp")),
+            FileEvent::Modified(path("a.tmp")),
+            FileEvent::Removed(path("a")),
+            FileEvent::Renamed {
+                from: path("a.tmp"),
+                to: path("a"),
+            },
+        ];
+        assert_eq!(merged(events), [FileEvent::Modified(path("a"))]);
+    }
+
+    #[test]
+    fn held_back_until_due() {
+        let mut watcher = FileWatcher::new().unwrap();
+        watcher.set_debounce(Duration::from_secs(60));
+        watcher.merge(FileEvent::Created(path("a")), Instant::now());
+        watcher.merge(FileEvent::Created(path("b")), Instant::now());
+
+        let mut events = Vec::new();
+        watcher.poll(|event| events.push(event));
+        assert_eq!(events, []);
+
+        // Events are delivered in the order their files last changed.
+        watcher.merge(FileEvent::Modified(path("a")), Instant::now());
+        watcher.set_debounce(Duration::ZERO);
+        watcher.poll(|event| events.push(event));
+        assert_eq!(
+            events,
+            [FileEvent::Created(path("b")), FileEvent::Created(path("a"))]
+        );
+    }
+
+    #[test]
+    fn rename_in_watched_directory() {
+        let dir = std::env::temp_dir().join(format!("iglo-file-watcher-{}", std::process::id()));
+        let _ = std::fs::remove_dir_all(&dir);
+        std::fs::create_dir_all(&dir).unwrap();
+        std::fs::write(dir.join("a"), "a").unwrap();
+
+        let mut watcher = FileWatcher::new().unwrap();
+        watcher.watch(&dir, false).unwrap();
+        std::fs::rename(dir.join("a"), dir.join("b")).unwrap();
+
+        let mut events = Vec::new();
+        let deadline = Instant::now() + Duration::from_secs(5);
+        while events.is_empty() && Instant::now() < deadline {
+            std::thread::sleep(Duration::from_millis(10));
+            watcher.poll(|event| events.push(event));
+        }
+        std::fs::remove_dir_all(&dir).unwrap();
+
+        assert_eq!(
+            events,
+            [FileEvent::Renamed {
+                from: dir.join("a"),
+                to: dir.join("b"),
+            }]
+        );
+    }
+}
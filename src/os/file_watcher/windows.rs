@@ -0,0 +1,195 @@
+//! Watching directories through `ReadDirectoryChangesW`.
+//!
+//! Every watched directory has a read pending in the background, which the kernel completes
+//! with the changes it collected. Polling checks the reads without waiting, and starts them again.
+
+use std::{
+    ffi::OsString,
+    io,
+    os::windows::ffi::{OsStrExt, OsStringExt},
+    path::{Path, PathBuf},
+    ptr, slice,
+    time::Duration,
+};
+
+use windows::Win32::{Foundation::*, Storage::FileSystem::*, System::IO::*};
+
+use super::{FileEvent, WatchError};
+
+const NOTIFY_FILTER: FILE_NOTIFY_CHANGE =
+    FILE_NOTIFY_CHANGE_FILE_NAME | FILE_NOTIFY_CHANGE_DIR_NAME | FILE_NOTIFY_CHANGE_LAST_WRITE;
+
+/// The size of the buffer changes are collected in, in 4-byte units since the records must be aligned.
+const BUFFER_SIZE: usize = 16 * 1024;
+
+struct Watch {
+    path: PathBuf,
+    handle: HANDLE,
+    recursive: bool,
+
+    // Both are written by the kernel while a read is pending, so they are boxed to stay in place.
+    overlapped: Box<OVERLAPPED>,
+    buffer: Box<[u32]>,
+}
+
+impl Watch {
+    /// Starts collecting changes, returns whether the read is pending.
+    fn read(&mut self) -> bool {
+        unsafe {
+            ReadDirectoryChangesW(
+                self.handle,
+                self.buffer.as_mut_ptr() as *mut _,
+                (self.buffer.len() * 4) as u32,
+                self.recursive,
+                NOTIFY_FILTER,
+                ptr::null_mut(),
+                &mut *self.overlapped,
+                None,
+            )
+        }
+        .as_bool()
+    }
+}
+
+impl Drop for Watch {
+    fn drop(&mut self) {
+        // The read must be finished before the buffer is freed, cancelling it completes it right away.
+        unsafe {
+            CancelIoEx(self.handle, &*self.overlapped);
+            let mut bytes = 0;
+            GetOverlappedResult(self.handle, &*self.overlapped, &mut bytes, true);
+            CloseHandle(self.handle);
+        }
+    }
+}
+
+pub struct FileWatcher {
+    watches: Vec<Watch>,
+}
+
+impl FileWatcher {
+    pub fn new() -> Result<Self, WatchError> {
+        Ok(Self {
+            watches: Vec::new(),
+        })
+    }
+
+    pub fn watch(&mut self, path: &Path, recursive: bool) -> Result<(), WatchError> {
+        // Whether a directory is watched recursively is fixed for a read, so it is watched anew.
+        self.unwatch(path);
+
+        let mut name: Vec<u16> = path.as_os_str().encode_wide().chain([0]).collect();
+        let handle = unsafe {
+            CreateFileW(
+                PWSTR(name.as_mut_ptr()),
+                FILE_LIST_DIRECTORY,
+                FILE_SHARE_READ | FILE_SHARE_WRITE | FILE_SHARE_DELETE,
+                ptr::null(),
+                OPEN_EXISTING,
+                FILE_FLAG_BACKUP_SEMANTICS | FILE_FLAG_OVERLAPPED,
+                HANDLE::default(),
+            )
+        };
+        if handle == INVALID_HANDLE_VALUE {
+            return Err(error(io::Error::last_os_error()));
+        }
+
+        let mut watch = Watch {
+            path: path.to_owned(),
+            handle,
+            recursive,
+            overlapped: Box::default(),
+            buffer: vec![0; BUFFER_SIZE].into_boxed_slice(),
+        };
+        if !watch.read() {
+            return Err(error(io::Error::last_os_error()));
+        }
+
+        self.watches.push(watch);
+        Ok(())
+    }
+
+    pub fn unwatch(&mut self, path: &Path) {
+        self.watches.retain(|watch| watch.path != path);
+    }
+
+    /// Both names of a rename are reported in the same buffer, so unlike inotify nothing is held back
+    /// for the debounce time.
+    pub fn poll(&mut self, _debounce: Duration, callback: &mut dyn FnMut(FileEvent)) {
+        self.watches.retain_mut(|watch| {
+            let mut bytes = 0;
+            let completed =
+                unsafe { GetOverlappedResult(watch.handle, &*watch.overlapped, &mut bytes, false) }
+                    .as_bool();
+
+            if !completed {
+                return match unsafe { GetLastError() } {
+                    ERROR_IO_INCOMPLETE => true,
+
+                    // The directory itself was removed.
+                    _ => {
+                        callback(FileEvent::Removed(watch.path.clone()));
+                        false
+                    }
+                };
+            }
+
+            // Nothing is returned if the buffer overflowed, in which case the changes are lost.
+            let buffer = &watch.buffer;
+            let bytes =
+                unsafe { slice::from_raw_parts(buffer.as_ptr() as *const u8, bytes as usize) };
+            handle_changes(&watch.path, bytes, callback);
+
+            watch.read()
+        });
+    }
+}
+
+/// Reports the changes in a buffer of `FILE_NOTIFY_INFORMATION` records.
+fn handle_changes(directory: &Path, bytes: &[u8], callback: &mut dyn FnMut(FileEvent)) {
+    // The old name of a rename, which is followed by the new name.
+    let mut renamed_from = None;
+
+    let mut offset = 0;
+    while offset + std::mem::size_of::<FILE_NOTIFY_INFORMATION>() <= bytes.len() {
+        // SAFETY: The kernel writes whole, aligned records.
+        let info = unsafe { &*(bytes.as_ptr().add(offset) as *const FILE_NOTIFY_INFORMATION) };
+        let name = unsafe {
+            slice::from_raw_parts(
+                ptr::addr_of!(info.FileName) as *const u16,
+                info.FileNameLength as usize / 2,
+            )
+        };
+        let path = directory.join(OsString::from_wide(name));
+
+        match info.Action {
+            FILE_ACTION_ADDED => callback(FileEvent::Created(path)),
+            FILE_ACTION_REMOVED => callback(FileEvent::Removed(path)),
+
+            // Directories are modified whenever their contents change, which is already reported.
+            FILE_ACTION_MODIFIED if !path.is_dir() => callback(FileEvent::Modified(path)),
+            FILE_ACTION_RENAMED_OLD_NAME => renamed_from = Some(path),
+            FILE_ACTION_RENAMED_NEW_NAME => match renamed_from.take() {
+                Some(from) => callback(FileEvent::Renamed { from, to: path }),
+                None => callback(FileEvent::Created(path)),
+            },
+            _ => {}
+        }
+
+        if info.NextEntryOffset == 0 {
+            break;
+        }
+        offset += info.NextEntryOffset as usize;
+    }
+
+    if let Some(from) = renamed_from {
+        callback(FileEvent::Removed(from));
+    }
+}
+
+fn error(error: io::Error) -> WatchError {
+    match error.raw_os_error().map(|code| code as u32) {
+        Some(ERROR_FILE_NOT_FOUND | ERROR_PATH_NOT_FOUND | ERROR_DIRECTORY) => WatchError::NotFound,
+        _ => WatchError::Io(error.kind()),
+    }
+}
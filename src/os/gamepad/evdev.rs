@@ -0,0 +1,794 @@
+//! Translation of evdev input events to the standard layout.
+//!
+//! Nothing in here talks to the kernel, so recorded event streams can be fed to an [`EvdevMapper`] directly.
+
+use std::collections::HashSet;
+
+use super::{
+    AxisRange, GamepadAxis, GamepadButton, GamepadInput, GamepadState, Mapping, MappingInput,
+    MappingOutput,
+};
+use crate::os::ElementState;
+
+pub const EV_SYN: u16 = 0x00;
+pub const EV_KEY: u16 = 0x01;
+pub const EV_ABS: u16 = 0x03;
+
+pub const SYN_REPORT: u16 = 0;
+pub const SYN_DROPPED: u16 = 3;
+
+pub const KEY_MAX: u16 = 0x2ff;
+pub const ABS_MAX: u16 = 0x3f;
+
+const BTN_MISC: u16 = 0x100;
+const BTN_JOYSTICK: u16 = 0x120;
+const BTN_SOUTH: u16 = 0x130;
+const BTN_EAST: u16 = 0x131;
+const BTN_NORTH: u16 = 0x133;
+const BTN_WEST: u16 = 0x134;
+const BTN_TL: u16 = 0x136;
+const BTN_TR: u16 = 0x137;
+const BTN_TL2: u16 = 0x138;
+const BTN_TR2: u16 = 0x139;
+const BTN_SELECT: u16 = 0x13a;
+const BTN_START: u16 = 0x13b;
+const BTN_MODE: u16 = 0x13c;
+const BTN_THUMBL: u16 = 0x13d;
+const BTN_THUMBR: u16 = 0x13e;
+const BTN_DPAD_UP: u16 = 0x220;
+const BTN_DPAD_DOWN: u16 = 0x221;
+const BTN_DPAD_LEFT: u16 = 0x222;
+const BTN_DPAD_RIGHT: u16 = 0x223;
+
+const ABS_X: u16 = 0x00;
+const ABS_Y: u16 = 0x01;
+const ABS_Z: u16 = 0x02;
+const ABS_RX: u16 = 0x03;
+const ABS_RY: u16 = 0x04;
+const ABS_RZ: u16 = 0x05;
+const ABS_HAT0X: u16 = 0x10;
+const ABS_HAT3Y: u16 = 0x17;
+
+/// The range of an absolute axis, like `struct input_absinfo`.
+#[derive(Debug, Default, Clone, Copy, PartialEq, Eq)]
+pub struct AbsInfo {
+    pub value: i32,
+    pub minimum: i32,
+    pub maximum: i32,
+    pub fuzz: i32,
+
+    /// Values this close to the centre are reported as 0.
+    pub flat: i32,
+    pub resolution: i32,
+}
+
+/// What a device reports about itself.
+#[derive(Debug, Default, Clone, PartialEq, Eq)]
+pub struct DeviceInfo {
+    pub name: String,
+    pub bustype: u16,
+    pub vendor: u16,
+    pub product: u16,
+    pub version: u16,
+
+    /// The `EV_KEY` codes the device supports, in ascending order.
+    pub keys: Vec<u16>,
+
+    /// The `EV_KEY` codes that were held down when the info was read, in ascending order.
+    pub keys_down: Vec<u16>,
+
+    /// The `EV_ABS` codes the device supports, in ascending order.
+    pub axes: Vec<(u16, AbsInfo)>,
+}
+
+impl DeviceInfo {
+    /// Returns the GUID SDL uses to look up the mapping of the device.
+    pub fn guid(&self) -> String {
+        let mut words = [self.bustype, 0, 0, 0, 0, 0, 0, 0];
+        if self.vendor != 0 && self.product != 0 {
+            words[2] = self.vendor;
+            words[4] = self.product;
+            words[6] = self.version;
+        } else {
+            // Devices without ids are identified by the start of their name instead.
+            for (i, byte) in self.name.bytes().take(12).enumerate() {
+                words[2 + i / 2] |= (byte as u16) << (8 * (i % 2));
+            }
+        }
+
+        words
+            .iter()
+            .flat_map(|word| word.to_le_bytes())
+            .map(|byte| format!("{:02x}", byte))
+            .collect()
+    }
+
+    /// Returns whether the driver reports the buttons of a gamepad, so it can be used without a mapping.
+    pub fn is_gamepad(&self) -> bool {
+        self.keys.contains(&BTN_SOUTH)
+    }
+}
+
+/// A single evdev event, like `struct input_event` without the timestamp.
+#[derive(Debug, Clone, Copy, PartialEq, Eq)]
+pub struct RawEvent {
+    pub kind: u16,
+    pub code: u16,
+    pub value: i32,
+}
+
+#[derive(Debug, Clone, Copy)]
+enum Source {
+    Key(u16),
+    Abs {
+        code: u16,
+        range: AxisRange,
+        inverted: bool,
+    },
+    Hat {
+        x: u16,
+        y: u16,
+        mask: u8,
+    },
+}
+
+/// Maps the events of a device to the standard layout and tracks its [`GamepadState`].
+pub struct EvdevMapper {
+    bindings: Vec<(Source, MappingOutput)>,
+    axes: Vec<(u16, AbsInfo)>,
+    keys_down: HashSet<u16>,
+    dropped: bool,
+
+    /// Whether events were dropped, so the state must be read from the device again.
+    resync_pending: bool,
+    state: GamepadState,
+}
+
+impl EvdevMapper {
+    /// Creates a mapper for a device with a mapping from a gamecontrollerdb.
+    ///
+    /// # Arguments
+    ///
+    /// - `info` - What the device reports about itself.
+    /// - `mapping` - The mapping, which addresses buttons, axes and hats by the indices SDL assigns to them.
+    pub fn new(info: &DeviceInfo, mapping: &Mapping) -> Self {
+        // SDL numbers buttons from BTN_JOYSTICK up, followed by the ones below it.
+        let buttons: Vec<u16> = info
+            .keys
+            .iter()
+            .copied()
+            .filter(|&key| key >= BTN_JOYSTICK)
+            .chain(
+                info.keys
+                    .iter()
+                    .copied()
+                    .filter(|&key| (BTN_MISC..BTN_JOYSTICK).contains(&key)),
+            )
+            .collect();
+
+        let abs: Vec<u16> = info.axes.iter().map(|&(code, _)| code).collect();
+        let axes: Vec<u16> = abs
+            .iter()
+            .copied()
+            .filter(|code| !(ABS_HAT0X..=ABS_HAT3Y).contains(code))
+            .collect();
+        let hats: Vec<u16> = (ABS_HAT0X..=ABS_HAT3Y)
+            .step_by(2)
+            .filter(|&x| abs.contains(&x) || abs.contains(&(x + 1)))
+            .collect();
+
+        let bindings = mapping
+            .bindings
+            .iter()
+            .filter_map(|&(input, output)| {
+                let source = match input {
+                    MappingInput::Button(index) => Source::Key(*buttons.get(index as usize)?),
+                    MappingInput::Axis {
+                        index,
+                        range,
+                        inverted,
+                    } => Source::Abs {
+                        code: *axes.get(index as usize)?,
+                        range,
+                        inverted,
+                    },
+                    MappingInput::Hat { index, mask } => {
+                        let x = *hats.get(index as usize)?;
+                        Source::Hat { x, y: x + 1, mask }
+                    }
+                };
+                Some((source, output))
+            })
+            .collect();
+
+        Self::with_bindings(info, bindings)
+    }
+
+    /// Creates a mapper for a device without a mapping, based on the semantic codes its driver reports.
+    ///
+    /// # Arguments
+    ///
+    /// - `info` - What the device reports about itself.
+    pub fn with_default_mapping(info: &DeviceInfo) -> Self {
+        use GamepadAxis::*;
+        use GamepadButton::*;
+
+        let button = |code, button| (Source::Key(code), MappingOutput::Button(button));
+        let axis = |code, axis| {
+            (
+                Source::Abs {
+                    code,
+                    range: AxisRange::Full,
+                    inverted: false,
+                },
+                MappingOutput::Axis {
+                    axis,
+                    range: AxisRange::Full,
+                },
+            )
+        };
+        let hat = |mask, button| {
+            (
+                Source::Hat {
+                    x: ABS_HAT0X,
+                    y: ABS_HAT0X + 1,
+                    mask,
+                },
+                MappingOutput::Button(button),
+            )
+        };
+
+        let mut bindings = vec![
+            button(BTN_SOUTH, South),
+            button(BTN_EAST, East),
+            button(BTN_WEST, West),
+            button(BTN_NORTH, North),
+            button(BTN_TL, LeftShoulder),
+            button(BTN_TR, RightShoulder),
+            button(BTN_SELECT, Back),
+            button(BTN_START, Start),
+            button(BTN_MODE, Guide),
+            button(BTN_THUMBL, LeftStick),
+            button(BTN_THUMBR, RightStick),
+            button(BTN_DPAD_UP, DPadUp),
+            button(BTN_DPAD_DOWN, DPadDown),
+            button(BTN_DPAD_LEFT, DPadLeft),
+            button(BTN_DPAD_RIGHT, DPadRight),
+            hat(1, DPadUp),
+            hat(2, DPadRight),
+            hat(4, DPadDown),
+            hat(8, DPadLeft),
+            axis(ABS_X, LeftX),
+            axis(ABS_Y, LeftY),
+            axis(ABS_RX, RightX),
+            axis(ABS_RY, RightY),
+            axis(ABS_Z, LeftTrigger),
+            axis(ABS_RZ, RightTrigger),
+        ];
+
+        // Some controllers only have digital triggers.
+        for (code, abs, trigger) in [
+            (BTN_TL2, ABS_Z, LeftTrigger),
+            (BTN_TR2, ABS_RZ, RightTrigger),
+        ] {
+            if !info.axes.iter().any(|&(code, _)| code == abs) {
+                bindings.push((
+                    Source::Key(code),
+                    MappingOutput::Axis {
+                        axis: trigger,
+                        range: AxisRange::Full,
+                    },
+                ));
+            }
+        }
+
+        Self::with_bindings(info, bindings)
+    }
+
+    fn with_bindings(info: &DeviceInfo, mut bindings: Vec<(Source, MappingOutput)>) -> Self {
+        // Missing axes would read as centred, which is halfway pressed for triggers.
+        bindings.retain(|(source, _)| match *source {
+            Source::Abs { code, .. } => info.axes.iter().any(|&(abs, _)| abs == code),
+            _ => true,
+        });
+
+        let mut mapper = Self {
+            bindings,
+            axes: info.axes.clone(),
+            keys_down: info.keys_down.iter().copied().collect(),
+            dropped: false,
+            resync_pending: false,
+            state: GamepadState::default(),
+        };
+
+        // Apply the initial state of the device without reporting it, not even as presses.
+        mapper.update(|_| {});
+        mapper.state.begin_poll();
+        mapper
+    }
+
+    /// Returns the current state of the device.
+    pub fn state(&self) -> &GamepadState {
+        &self.state
+    }
+
+    /// Forgets the presses and releases of the previous poll, see [`GamepadState`].
+    pub fn begin_poll(&mut self) {
+        self.state.begin_poll();
+    }
+
+    /// Processes a single event and passes every change of the standard layout to `callback`.
+    ///
+    /// Like the kernel, changes are applied as a whole when a `SYN_REPORT` event arrives.
+    /// When the kernel drops events, everything up to the next `SYN_REPORT` is discarded,
+    /// after which [`EvdevMapper::needs_resync()`] returns `true` until [`EvdevMapper::resync()`] is called.
+    ///
+    /// # Arguments
+    ///
+    /// - `event` - The event read from the device.
+    /// - `callback` - Called with each change.
+    pub fn feed(&mut self, event: &RawEvent, callback: impl FnMut(GamepadInput)) {
+        match (event.kind, event.code) {
+            (EV_SYN, SYN_REPORT) if self.dropped => {
+                self.dropped = false;
+                self.resync_pending = true;
+            }
+            (EV_SYN, SYN_REPORT) => self.update(callback),
+            (EV_SYN, SYN_DROPPED) => self.dropped = true,
+            _ if self.dropped => {}
+            (EV_KEY, code) => match event.value {
+                0 => {
+                    self.keys_down.remove(&code);
+                }
+                // 2 is a key repeat, which gamepads don't send, but keyboards with gamepad buttons might.
+                _ => {
+                    self.keys_down.insert(code);
+                }
+            },
+            (EV_ABS, code) => {
+                if let Some((_, info)) = self.axes.iter_mut().find(|(abs, _)| *abs == code) {
+                    info.value = event.value;
+                }
+            }
+            _ => {}
+        }
+    }
+
+    /// Returns whether events were dropped since the last resync, so the state of the mapper may be stale.
+    pub fn needs_resync(&self) -> bool {
+        self.resync_pending
+    }
+
+    /// Replaces the state of the device with one read from it, and reports the outputs that changed.
+    ///
+    /// # Arguments
+    ///
+    /// - `info` - What the device reports about itself, read after the events were dropped.
+    /// - `callback` - Called with each change.
+    pub fn resync(&mut self, info: &DeviceInfo, callback: impl FnMut(GamepadInput)) {
+        self.keys_down = info.keys_down.iter().copied().collect();
+        for (code, abs) in &mut self.axes {
+            if let Some((_, current)) = info.axes.iter().find(|(other, _)| other == code) {
+                abs.value = current.value;
+            }
+        }
+        self.resync_pending = false;
+        self.update(callback);
+    }
+
+    /// Evaluates all bindings and reports the outputs that changed.
+    fn update(&mut self, mut callback: impl FnMut(GamepadInput)) {
+        let mut buttons = HashSet::new();
+        let mut axes = [0.0f32; GamepadAxis::ALL.len()];
+
+        for &(source, output) in &self.bindings {
+            let value = self.evaluate(source);
+            match output {
+                MappingOutput::Button(button) => {
+                    if value > 0.5 {
+                        buttons.insert(button);
+                    }
+                }
+                MappingOutput::Axis { axis, range } => {
+                    let is_trigger =
+                        matches!(axis, GamepadAxis::LeftTrigger | GamepadAxis::RightTrigger);
+                    let is_full_axis = matches!(
+                        source,
+                        Source::Abs {
+                            range: AxisRange::Full,
+                            ..
+                        }
+                    );
+
+                    axes[axis as usize] += match range {
+                        // Full axes bound to triggers cover the whole range of the trigger.
+                        AxisRange::Full if is_trigger && is_full_axis => (value + 1.0) / 2.0,
+                        AxisRange::Full => value,
+                        AxisRange::Positive => value.abs(),
+                        AxisRange::Negative => -value.abs(),
+                    };
+                }
+            }
+        }
+
+        // Changes are reported in the order of the layout, presses before releases.
+        let down = &self.state.down;
+        let changes: Vec<GamepadInput> = GamepadButton::ALL
+            .into_iter()
+            .filter(|button| buttons.contains(button) && !down.contains(button))
+            .map(|button| GamepadInput::Button {
+                button,
+                state: ElementState::Pressed,
+            })
+            .chain(
+                GamepadButton::ALL
+                    .into_iter()
+                    .filter(|button| !buttons.contains(button) && down.contains(button))
+                    .map(|button| GamepadInput::Button {
+                        button,
+                        state: ElementState::Released,
+                    }),
+            )
+            .chain(
+                GamepadAxis::ALL
+                    .into_iter()
+                    .map(|axis| (axis, axes[axis as usize].clamp(-1.0, 1.0)))
+                    .filter(|&(axis, value)| value != self.state.axis(axis))
+                    .map(|(axis, value)| GamepadInput::Axis { axis, value }),
+            )
+            .collect();
+
+        for change in changes {
+            self.state.handle_input(&change);
+            callback(change);
+        }
+    }
+
+    /// Returns the value of a source, from -1.0 to 1.0 for full axes and from 0.0 to 1.0 otherwise.
+    fn evaluate(&self, source: Source) -> f32 {
+        match source {
+            Source::Key(code) => self.keys_down.contains(&code) as u8 as f32,
+            Source::Abs {
+                code,
+                range,
+                inverted,
+            } => {
+                let value = self.abs(code).map_or(0.0, Self::normalize);
+                let value = if inverted { -value } else { value };
+                match range {
+                    AxisRange::Full => value,
+                    AxisRange::Positive => value.max(0.0),
+                    AxisRange::Negative => -value.min(0.0),
+                }
+            }
+            Source::Hat { x, y, mask } => {
+                let x = self.abs(x).map_or(0, |info| info.value.signum());
+                let y = self.abs(y).map_or(0, |info| info.value.signum());
+                let directions = match y {
+                    -1 => 1,
+                    1 => 4,
+                    _ => 0,
+                } | match x {
+                    1 => 2,
+                    -1 => 8,
+                    _ => 0,
+                };
+                (directions & mask != 0) as u8 as f32
+            }
+        }
+    }
+
+    fn abs(&self, code: u16) -> Option<&AbsInfo> {
+        self.axes
+            .iter()
+            .find(|&&(abs, _)| abs == code)
+            .map(|(_, info)| info)
+    }
+
+    /// Scales the value of an axis to -1.0..1.0, applying its deadzone.
+    fn normalize(info: &AbsInfo) -> f32 {
+        let range = info.maximum as f32 - info.minimum as f32;
+        if range <= 0.0 {
+            return 0.0;
+        }
+
+        let centre = (info.minimum as f32 + info.maximum as f32) / 2.0;
+        if (info.value as f32 - centre).abs() <= info.flat as f32 {
+            return 0.0;
+        }
+
+        ((info.value as f32 - info.minimum as f32) / range * 2.0 - 1.0).clamp(-1.0, 1.0)
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    const REPORT: RawEvent = RawEvent {
+        kind: EV_SYN,
+        code: SYN_REPORT,
+        value: 0,
+    };
+
+    const DROPPED: RawEvent = RawEvent {
+        kind: EV_SYN,
+        code: SYN_DROPPED,
+        value: 0,
+    };
+
+    fn key(code: u16, value: i32) -> RawEvent {
+        RawEvent {
+            kind: EV_KEY,
+            code,
+            value,
+        }
+    }
+
+    fn abs(code: u16, value: i32) -> RawEvent {
+        RawEvent {
+            kind: EV_ABS,
+            code,
+            value,
+        }
+    }
+
+    /// A controller like the Xbox 360 pad as reported by the `xpad` driver.
+    fn xpad() -> DeviceInfo {
+        let stick = AbsInfo {
+            minimum: -32768,
+            maximum: 32767,
+            fuzz: 16,
+            flat: 128,
+            ..Default::default()
+        };
+        let trigger = AbsInfo {
+            maximum: 255,
+            ..Default::default()
+        };
+        let hat = AbsInfo {
+            minimum: -1,
+            maximum: 1,
+            ..Default::default()
+        };
+
+        DeviceInfo {
+            name: "Microsoft X-Box 360 pad".to_owned(),
+            bustype: 3,
+            vendor: 0x045e,
+            product: 0x028e,
+            version: 0x0114,
+            keys: vec![
+                BTN_SOUTH, BTN_EAST, BTN_NORTH, BTN_WEST, BTN_TL, BTN_TR, BTN_SELECT, BTN_START,
+                BTN_MODE, BTN_THUMBL, BTN_THUMBR,
+            ],
+            keys_down: vec![],
+            axes: vec![
+                (ABS_X, stick),
+                (ABS_Y, stick),
+                (ABS_Z, trigger),
+                (ABS_RX, stick),
+                (ABS_RY, stick),
+                (ABS_RZ, trigger),
+                (ABS_HAT0X, hat),
+                (ABS_HAT0X + 1, hat),
+            ],
+        }
+    }
+
+    /// Feeds a recorded event stream and returns the reported changes.
+    fn feed_all(mapper: &mut EvdevMapper, events: &[RawEvent]) -> Vec<GamepadInput> {
+        let mut changes = vec![];
+        for event in events {
+            mapper.feed(event, |input| changes.push(input));
+        }
+        changes
+    }
+
+    fn button(button: GamepadButton, state: ElementState) -> GamepadInput {
+        GamepadInput::Button { button, state }
+    }
+
+    #[test]
+    fn guid() {
+        assert_eq!(xpad().guid(), "030000005e0400008e02000014010000");
+    }
+
+    #[test]
+    fn press_and_release() {
+        let mut mapper = EvdevMapper::with_default_mapping(&xpad());
+
+        assert_eq!(
+            feed_all(&mut mapper, &[key(BTN_SOUTH, 1), REPORT]),
+            [button(GamepadButton::South, ElementState::Pressed)]
+        );
+        assert!(mapper.state().is_down(GamepadButton::South));
+        assert!(mapper.state().was_pressed(GamepadButton::South));
+
+        mapper.begin_poll();
+        assert_eq!(
+            feed_all(&mut mapper, &[key(BTN_SOUTH, 0), REPORT]),
+            [button(GamepadButton::South, ElementState::Released)]
+        );
+        assert!(!mapper.state().is_down(GamepadButton::South));
+        assert!(mapper.state().was_released(GamepadButton::South));
+    }
+
+    #[test]
+    fn changes_wait_for_report() {
+        let mut mapper = EvdevMapper::with_default_mapping(&xpad());
+
+        assert!(feed_all(&mut mapper, &[key(BTN_EAST, 1), abs(ABS_X, 32767)]).is_empty());
+        assert!(!mapper.state().is_down(GamepadButton::East));
+
+        assert_eq!(feed_all(&mut mapper, &[REPORT]).len(), 2);
+        assert!(mapper.state().is_down(GamepadButton::East));
+    }
+
+    #[test]
+    fn changes_in_layout_order() {
+        let mut mapper = EvdevMapper::with_default_mapping(&xpad());
+        feed_all(&mut mapper, &[key(BTN_START, 1), REPORT]);
+
+        let changes = feed_all(
+            &mut mapper,
+            &[
+                key(BTN_START, 0),
+                key(BTN_WEST, 1),
+                key(BTN_TR, 1),
+                key(BTN_SOUTH, 1),
+                abs(ABS_RY, 32767),
+                abs(ABS_X, -32768),
+                REPORT,
+            ],
+        );
+        assert_eq!(
+            changes,
+            [
+                button(GamepadButton::South, ElementState::Pressed),
+                button(GamepadButton::West, ElementState::Pressed),
+                button(GamepadButton::RightShoulder, ElementState::Pressed),
+                button(GamepadButton::Start, ElementState::Released),
+                GamepadInput::Axis {
+                    axis: GamepadAxis::LeftX,
+                    value: -1.0,
+                },
+                GamepadInput::Axis {
+                    axis: GamepadAxis::RightY,
+                    value: 1.0,
+                },
+            ]
+        );
+    }
+
+    #[test]
+    fn axes() {
+        let mut mapper = EvdevMapper::with_default_mapping(&xpad());
+
+        // Within the flat range the stick reads as centred.
+        assert!(feed_all(&mut mapper, &[abs(ABS_X, 100), REPORT]).is_empty());
+        assert_eq!(mapper.state().axis(GamepadAxis::LeftX), 0.0);
+
+        feed_all(&mut mapper, &[abs(ABS_X, 32767), abs(ABS_Z, 255), REPORT]);
+        assert_eq!(mapper.state().axis(GamepadAxis::LeftX), 1.0);
+        assert_eq!(mapper.state().axis(GamepadAxis::LeftTrigger), 1.0);
+
+        feed_all(&mut mapper, &[abs(ABS_Z, 0), REPORT]);
+        assert_eq!(mapper.state().axis(GamepadAxis::LeftTrigger), 0.0);
+    }
+
+    #[test]
+    fn hat() {
+        let mut mapper = EvdevMapper::with_default_mapping(&xpad());
+
+        assert_eq!(
+            feed_all(
+                &mut mapper,
+                &[abs(ABS_HAT0X + 1, -1), abs(ABS_HAT0X, 1), REPORT]
+            ),
+            [
+                button(GamepadButton::DPadUp, ElementState::Pressed),
+                button(GamepadButton::DPadRight, ElementState::Pressed),
+            ]
+        );
+        assert_eq!(
+            feed_all(&mut mapper, &[abs(ABS_HAT0X + 1, 0), REPORT]),
+            [button(GamepadButton::DPadUp, ElementState::Released)]
+        );
+    }
+
+    #[test]
+    fn dropped_events_resync() {
+        let mut mapper = EvdevMapper::with_default_mapping(&xpad());
+        feed_all(&mut mapper, &[key(BTN_SOUTH, 1), abs(ABS_X, 32767), REPORT]);
+
+        // The release is lost in the overflow, the events up to the next report are discarded.
+        let changes = feed_all(
+            &mut mapper,
+            &[DROPPED, key(BTN_SOUTH, 0), abs(ABS_X, 0), REPORT],
+        );
+        assert!(changes.is_empty());
+        assert!(mapper.needs_resync());
+        assert!(mapper.state().is_down(GamepadButton::South));
+
+        // The device reports its current state, which the mapper catches up with.
+        let mut changes = vec![];
+        let mut info = xpad();
+        info.axes[0].1.value = 0;
+        mapper.resync(&info, |input| changes.push(input));
+        assert_eq!(
+            changes,
+            [
+                button(GamepadButton::South, ElementState::Released),
+                GamepadInput::Axis {
+                    axis: GamepadAxis::LeftX,
+                    value: 0.0,
+                },
+            ]
+        );
+        assert!(!mapper.needs_resync());
+        assert!(!mapper.state().is_down(GamepadButton::South));
+
+        // Events after the resync are applied again.
+        assert_eq!(
+            feed_all(&mut mapper, &[key(BTN_EAST, 1), REPORT]),
+            [button(GamepadButton::East, ElementState::Pressed)]
+        );
+    }
+
+    #[test]
+    fn initial_state() {
+        let mut info = xpad();
+        info.keys_down = vec![BTN_TL];
+        info.axes[2].1.value = 255;
+
+        let mut mapper = EvdevMapper::with_default_mapping(&info);
+        assert!(mapper.state().is_down(GamepadButton::LeftShoulder));
+        assert_eq!(mapper.state().axis(GamepadAxis::LeftTrigger), 1.0);
+
+        // The initial state is not reported as a change.
+        assert!(!mapper.state().was_pressed(GamepadButton::LeftShoulder));
+        assert_eq!(
+            feed_all(&mut mapper, &[key(BTN_TL, 0), REPORT]),
+            [button(GamepadButton::LeftShoulder, ElementState::Released)]
+        );
+    }
+
+    #[test]
+    fn sdl_mapping() {
+        // SDL numbers buttons in ascending order, so b0 is BTN_SOUTH and b2 is BTN_NORTH.
+        let mapping = Mapping::parse(
+            "030000005e0400008e02000014010000,X360 Controller,a:b0,y:b2,lefty:a1~,dpup:h0.1,platform:Linux,",
+        )
+        .unwrap();
+        let mut mapper = EvdevMapper::new(&xpad(), &mapping);
+
+        assert_eq!(
+            feed_all(
+                &mut mapper,
+                &[
+                    key(BTN_SOUTH, 1),
+                    key(BTN_NORTH, 1),
+                    abs(ABS_Y, 32767),
+                    REPORT
+                ]
+            ),
+            [
+                button(GamepadButton::South, ElementState::Pressed),
+                button(GamepadButton::North, ElementState::Pressed),
+                GamepadInput::Axis {
+                    axis: GamepadAxis::LeftY,
+                    value: -1.0,
+                },
+            ]
+        );
+
+        // Elements without a binding are ignored.
+        assert!(feed_all(&mut mapper, &[key(BTN_EAST, 1), REPORT]).is_empty());
+
+        assert_eq!(
+            feed_all(&mut mapper, &[abs(ABS_HAT0X + 1, -1), REPORT]),
+            [button(GamepadButton::DPadUp, ElementState::Pressed)]
+        );
+    }
+}
@@ -0,0 +1,178 @@
+//! Gamepads through the evdev interface of the kernel.
+
+use std::{
+    ffi::CStr,
+    fs::{self, File, OpenOptions},
+    io, mem,
+    os::unix::{fs::OpenOptionsExt, io::AsRawFd},
+    path::{Path, PathBuf},
+};
+
+use super::{AbsInfo, DeviceInfo, RawEvent, ABS_MAX, EV_ABS, EV_KEY, KEY_MAX};
+
+/// The `_IOC_READ` direction of an ioctl request.
+const IOC_READ: u32 = 2;
+
+/// Builds an evdev ioctl request, like the `_IOC` macro with type `'E'`.
+const fn eviocg(nr: u32, size: usize) -> u32 {
+    IOC_READ << 30 | (size as u32) << 16 | (b'E' as u32) << 8 | nr
+}
+
+const EVIOCGID: u32 = eviocg(0x02, mem::size_of::<libc::input_id>());
+
+const fn eviocgname(len: usize) -> u32 {
+    eviocg(0x06, len)
+}
+
+const fn eviocgkey(len: usize) -> u32 {
+    eviocg(0x18, len)
+}
+
+const fn eviocgbit(ev: u16, len: usize) -> u32 {
+    eviocg(0x20 + ev as u32, len)
+}
+
+const fn eviocgabs(abs: u16) -> u32 {
+    eviocg(0x40 + abs as u32, mem::size_of::<libc::input_absinfo>())
+}
+
+/// Returns the paths of all evdev devices.
+pub fn device_paths() -> Vec<PathBuf> {
+    let entries = match fs::read_dir("/dev/input") {
+        Ok(entries) => entries,
+        _ => return Vec::new(),
+    };
+
+    entries
+        .filter_map(|entry| entry.ok())
+        .filter(|entry| entry.file_name().to_string_lossy().starts_with("event"))
+        .map(|entry| entry.path())
+        .collect()
+}
+
+pub struct Device {
+    file: File,
+}
+
+impl Device {
+    pub fn open(path: &Path) -> Result<Self, ()> {
+        match OpenOptions::new()
+            .read(true)
+            .custom_flags(libc::O_NONBLOCK | libc::O_CLOEXEC)
+            .open(path)
+        {
+            Ok(file) => Ok(Self { file }),
+            _ => Err(()),
+        }
+    }
+
+    pub fn info(&self) -> Result<DeviceInfo, ()> {
+        // SAFETY: All of the kernel structs below are plain integers.
+        let mut id: libc::input_id = unsafe { mem::zeroed() };
+        self.ioctl(EVIOCGID, &mut id)?;
+
+        let mut name = [0u8; 256];
+        self.ioctl(eviocgname(name.len()), &mut name)?;
+        let name = CStr::from_bytes_until_nul(&name)
+            .map(|name| name.to_string_lossy().into_owned())
+            .unwrap_or_default();
+
+        let mut key_bits = [0u8; KEY_MAX as usize / 8 + 1];
+        self.ioctl(eviocgbit(EV_KEY, key_bits.len()), &mut key_bits)?;
+
+        let mut key_state = [0u8; KEY_MAX as usize / 8 + 1];
+        self.ioctl(eviocgkey(key_state.len()), &mut key_state)?;
+
+        let mut abs_bits = [0u8; ABS_MAX as usize / 8 + 1];
+        self.ioctl(eviocgbit(EV_ABS, abs_bits.len()), &mut abs_bits)?;
+
+        let mut axes = Vec::new();
+        for code in (0..=ABS_MAX).filter(|&code| Self::test_bit(&abs_bits, code)) {
+            let mut abs: libc::input_absinfo = unsafe { mem::zeroed() };
+            self.ioctl(eviocgabs(code), &mut abs)?;
+            axes.push((
+                code,
+                AbsInfo {
+                    value: abs.value,
+                    minimum: abs.minimum,
+                    maximum: abs.maximum,
+                    fuzz: abs.fuzz,
+                    flat: abs.flat,
+                    resolution: abs.resolution,
+                },
+            ));
+        }
+
+        Ok(DeviceInfo {
+            name,
+            bustype: id.bustype,
+            vendor: id.vendor,
+            product: id.product,
+            version: id.version,
+            keys: (0..=KEY_MAX)
+                .filter(|&code| Self::test_bit(&key_bits, code))
+                .collect(),
+            keys_down: (0..=KEY_MAX)
+                .filter(|&code| Self::test_bit(&key_state, code))
+                .collect(),
+            axes,
+        })
+    }
+
+    /// Appends all pending events to `events`.
+    ///
+    /// Returns an error once the device is unplugged.
+    pub fn read_events(&mut self, events: &mut Vec<RawEvent>) -> Result<(), ()> {
+        const BATCH: usize = 64;
+
+        // SAFETY: `input_event` only consists of integers.
+        let mut buffer: [libc::input_event; BATCH] = unsafe { mem::zeroed() };
+
+        loop {
+            // SAFETY: The buffer is valid for `BATCH` events and the kernel only writes whole events.
+            let read = unsafe {
+                libc::read(
+                    self.file.as_raw_fd(),
+                    buffer.as_mut_ptr().cast(),
+                    mem::size_of_val(&buffer),
+                )
+            };
+
+            if read < 0 {
+                return match io::Error::last_os_error().raw_os_error() {
+                    Some(libc::EAGAIN) | Some(libc::EINTR) => Ok(()),
+                    _ => Err(()),
+                };
+            }
+
+            let count = read as usize / mem::size_of::<libc::input_event>();
+            events.extend(buffer[..count].iter().map(|event| RawEvent {
+                kind: event.type_,
+                code: event.code,
+                value: event.value,
+            }));
+
+            if count < BATCH {
+                return Ok(());
+            }
+        }
+    }
+
+    fn ioctl<T: ?Sized>(&self, request: u32, data: &mut T) -> Result<(), ()> {
+        // SAFETY: Every request is built with the size of the data it is passed.
+        match unsafe {
+            libc::ioctl(
+                self.file.as_raw_fd(),
+                request as _,
+                data as *mut T as *mut u8,
+            )
+        } {
+            -1 => Err(()),
+            _ => Ok(()),
+        }
+    }
+
+    fn test_bit(bits: &[u8], bit: u16) -> bool {
+        bits[bit as usize / 8] & (1 << (bit % 8)) != 0
+    }
+}
@@ -0,0 +1,372 @@
+use std::collections::HashMap;
+
+use super::{GamepadAxis, GamepadButton};
+
+/// The part of an axis that is bound.
+#[derive(Debug, Clone, Copy, PartialEq, Eq)]
+pub enum AxisRange {
+    /// The whole axis, from -1.0 to 1.0.
+    Full,
+
+    /// The positive half of the axis, from 0.0 to 1.0.
+    Positive,
+
+    /// The negative half of the axis, from 0.0 to -1.0.
+    Negative,
+}
+
+/// An element of a device, addressed by the indices SDL assigns to them.
+#[derive(Debug, Clone, Copy, PartialEq, Eq)]
+pub enum MappingInput {
+    /// `bN`, a button.
+    Button(u8),
+
+    /// `aN`, `+aN`, `-aN` or `aN~`, an axis.
+    Axis {
+        index: u8,
+        range: AxisRange,
+        inverted: bool,
+    },
+
+    /// `hN.M`, a direction of a hat, where `mask` is 1 for up, 2 for right, 4 for down and 8 for left.
+    Hat { index: u8, mask: u8 },
+}
+
+/// An element of the standard layout.
+#[derive(Debug, Clone, Copy, PartialEq, Eq)]
+pub enum MappingOutput {
+    Button(GamepadButton),
+    Axis { axis: GamepadAxis, range: AxisRange },
+}
+
+/// The error returned by [`Mapping::parse()`].
+#[derive(Debug, Clone, PartialEq, Eq)]
+pub enum MappingError {
+    /// The GUID is not 32 hexadecimal digits.
+    InvalidGuid,
+
+    /// The entry has no name.
+    MissingName,
+
+    /// A binding couldn't be parsed. Contains the offending binding.
+    InvalidBinding(String),
+}
+
+/// Maps the elements of a device to the standard layout.
+///
+/// Mappings are written in the format of SDL's gamecontrollerdb, e.g.
+/// `030000005e0400008e02000014010000,Xbox 360 Controller,a:b0,b:b1,leftx:a0,platform:Linux,`.
+/// Bindings for elements that are not part of the standard layout are ignored.
+#[derive(Debug, Clone, PartialEq, Eq)]
+pub struct Mapping {
+    /// The GUID of the device, as 32 lowercase hexadecimal digits.
+    pub guid: String,
+    pub name: String,
+
+    /// The platform the mapping is meant for, if it names one.
+    pub platform: Option<String>,
+    pub bindings: Vec<(MappingInput, MappingOutput)>,
+}
+
+impl Mapping {
+    /// Parses a single mapping string.
+    ///
+    /// # Arguments
+    ///
+    /// - `mapping` - The mapping in the format of SDL's gamecontrollerdb.
+    pub fn parse(mapping: &str) -> Result<Self, MappingError> {
+        let mut fields = mapping.trim().split(',');
+
+        let guid = fields.next().unwrap_or_default().to_ascii_lowercase();
+        if guid.len() != 32 || !guid.bytes().all(|b| b.is_ascii_hexdigit()) {
+            return Err(MappingError::InvalidGuid);
+        }
+
+        let name = match fields.next() {
+            Some(name) if !name.is_empty() => name.to_owned(),
+            _ => return Err(MappingError::MissingName),
+        };
+
+        let mut platform = None;
+        let mut bindings = Vec::new();
+        for field in fields.filter(|field| !field.is_empty()) {
+            let (output, input) = match field.split_once(':') {
+                Some(binding) => binding,
+                None => return Err(MappingError::InvalidBinding(field.to_owned())),
+            };
+
+            if output == "platform" {
+                platform = Some(input.to_owned());
+                continue;
+            }
+
+            let output = match Self::parse_output(output) {
+                Some(output) => output,
+                None => continue,
+            };
+
+            match Self::parse_input(input) {
+                Some(input) => bindings.push((input, output)),
+                None => return Err(MappingError::InvalidBinding(field.to_owned())),
+            }
+        }
+
+        Ok(Self {
+            guid,
+            name,
+            platform,
+            bindings,
+        })
+    }
+
+    /// Returns `None` for elements that are not part of the standard layout, e.g. `paddle1`.
+    fn parse_output(output: &str) -> Option<MappingOutput> {
+        let (range, name) = match output.as_bytes().first() {
+            Some(b'+') => (AxisRange::Positive, &output[1..]),
+            Some(b'-') => (AxisRange::Negative, &output[1..]),
+            _ => (AxisRange::Full, output),
+        };
+
+        let button = match name {
+            "a" => GamepadButton::South,
+            "b" => GamepadButton::East,
+            "x" => GamepadButton::West,
+            "y" => GamepadButton::North,
+            "back" => GamepadButton::Back,
+            "guide" => GamepadButton::Guide,
+            "start" => GamepadButton::Start,
+            "leftstick" => GamepadButton::LeftStick,
+            "rightstick" => GamepadButton::RightStick,
+            "leftshoulder" => GamepadButton::LeftShoulder,
+            "rightshoulder" => GamepadButton::RightShoulder,
+            "dpup" => GamepadButton::DPadUp,
+            "dpdown" => GamepadButton::DPadDown,
+            "dpleft" => GamepadButton::DPadLeft,
+            "dpright" => GamepadButton::DPadRight,
+            _ => {
+                let axis = match name {
+                    "leftx" => GamepadAxis::LeftX,
+                    "lefty" => GamepadAxis::LeftY,
+                    "rightx" => GamepadAxis::RightX,
+                    "righty" => GamepadAxis::RightY,
+                    "lefttrigger" => GamepadAxis::LeftTrigger,
+                    "righttrigger" => GamepadAxis::RightTrigger,
+                    _ => return None,
+                };
+                return Some(MappingOutput::Axis { axis, range });
+            }
+        };
+
+        Some(MappingOutput::Button(button))
+    }
+
+    fn parse_input(input: &str) -> Option<MappingInput> {
+        let (range, input) = match input.as_bytes().first() {
+            Some(b'+') => (AxisRange::Positive, &input[1..]),
+            Some(b'-') => (AxisRange::Negative, &input[1..]),
+            _ => (AxisRange::Full, input),
+        };
+
+        let (inverted, input) = match input.strip_suffix('~') {
+            Some(input) => (true, input),
+            None => (false, input),
+        };
+
+        if let Some(index) = input.strip_prefix('b') {
+            Some(MappingInput::Button(index.parse().ok()?))
+        } else if let Some(index) = input.strip_prefix('a') {
+            Some(MappingInput::Axis {
+                index: index.parse().ok()?,
+                range,
+                inverted,
+            })
+        } else if let Some(hat) = input.strip_prefix('h') {
+            let (index, mask) = hat.split_once('.')?;
+            Some(MappingInput::Hat {
+                index: index.parse().ok()?,
+                mask: mask.parse().ok()?,
+            })
+        } else {
+            None
+        }
+    }
+}
+
+/// A collection of mappings, looked up by the GUID of a device.
+#[derive(Debug, Default)]
+pub struct MappingDb {
+    mappings: HashMap<String, Mapping>,
+}
+
+impl MappingDb {
+    /// Adds the mappings of a gamecontrollerdb file, replacing existing mappings with the same GUID.
+    ///
+    /// Empty lines, comments and mappings for other platforms are skipped.
+    /// Returns the number of mappings that were added.
+    ///
+    /// # Arguments
+    ///
+    /// - `mappings` - The mappings, one per line.
+    pub fn add_mappings(&mut self, mappings: &str) -> usize {
+        let mut count = 0;
+        for line in mappings.lines().map(str::trim) {
+            if line.is_empty() || line.starts_with('#') {
+                continue;
+            }
+
+            match Mapping::parse(line) {
+                Ok(mapping) if matches!(mapping.platform.as_deref(), None | Some("Linux")) => {
+                    self.mappings
+                        .insert(Self::normalize_guid(&mapping.guid), mapping);
+                    count += 1;
+                }
+                _ => {}
+            }
+        }
+        count
+    }
+
+    /// Returns the mapping for a device.
+    ///
+    /// # Arguments
+    ///
+    /// - `guid` - The GUID of the device, see [`DeviceInfo::guid()`](super::DeviceInfo::guid).
+    pub fn get(&self, guid: &str) -> Option<&Mapping> {
+        self.mappings.get(&Self::normalize_guid(guid))
+    }
+
+    /// Newer versions of SDL store a checksum of the device name in bytes 2 and 3 of the GUID,
+    /// which is ignored so mappings from all versions match.
+    fn normalize_guid(guid: &str) -> String {
+        let mut guid = guid.to_ascii_lowercase();
+        if guid.len() == 32 && guid.is_ascii() {
+            guid.replace_range(4..8, "0000");
+        }
+        guid
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    const GUID: &str = "030000005e0400008e02000014010000";
+
+    #[test]
+    fn parse() {
+        let mapping = Mapping::parse(&format!(
+            "{GUID},Xbox 360 Controller,a:b0,dpup:h0.1,+leftx:+a0,lefty:a1~,righttrigger:-a5,paddle1:b9,platform:Linux,"
+        ))
+        .unwrap();
+
+        assert_eq!(mapping.guid, GUID);
+        assert_eq!(mapping.name, "Xbox 360 Controller");
+        assert_eq!(mapping.platform.as_deref(), Some("Linux"));
+        assert_eq!(
+            mapping.bindings,
+            [
+                (
+                    MappingInput::Button(0),
+                    MappingOutput::Button(GamepadButton::South)
+                ),
+                (
+                    MappingInput::Hat { index: 0, mask: 1 },
+                    MappingOutput::Button(GamepadButton::DPadUp)
+                ),
+                (
+                    MappingInput::Axis {
+                        index: 0,
+                        range: AxisRange::Positive,
+                        inverted: false,
+                    },
+                    MappingOutput::Axis {
+                        axis: GamepadAxis::LeftX,
+                        range: AxisRange::Positive,
+                    }
+                ),
+                (
+                    MappingInput::Axis {
+                        index: 1,
+                        range: AxisRange::Full,
+                        inverted: true,
+                    },
+                    MappingOutput::Axis {
+                        axis: GamepadAxis::LeftY,
+                        range: AxisRange::Full,
+                    }
+                ),
+                (
+                    MappingInput::Axis {
+                        index: 5,
+                        range: AxisRange::Negative,
+                        inverted: false,
+                    },
+                    MappingOutput::Axis {
+                        axis: GamepadAxis::RightTrigger,
+                        range: AxisRange::Full,
+                    }
+                ),
+            ]
+        );
+    }
+
+    #[test]
+    fn parse_uppercase_guid() {
+        let mapping = Mapping::parse(&format!("{},Pad,a:b0", GUID.to_uppercase())).unwrap();
+        assert_eq!(mapping.guid, GUID);
+        assert_eq!(mapping.platform, None);
+    }
+
+    #[test]
+    fn parse_malformed() {
+        assert_eq!(Mapping::parse(""), Err(MappingError::InvalidGuid));
+        assert_eq!(
+            Mapping::parse("0300,Pad,a:b0"),
+            Err(MappingError::InvalidGuid)
+        );
+        assert_eq!(
+            Mapping::parse(&format!("{}g,Pad,a:b0", &GUID[..31])),
+            Err(MappingError::InvalidGuid)
+        );
+        assert_eq!(
+            Mapping::parse(&format!("{GUID},,a:b0")),
+            Err(MappingError::MissingName)
+        );
+        assert_eq!(Mapping::parse(GUID), Err(MappingError::MissingName));
+
+        for binding in [
+            "a", "a:", "a:x0", "a:b", "a:b-1", "a:b256", "dpup:h0", "dpup:h.1", "a:é", "a:bé",
+            "é:é",
+        ] {
+            let result = Mapping::parse(&format!("{GUID},Pad,{binding}"));
+            match binding {
+                // Unknown outputs are skipped before their input is parsed.
+                "é:é" => assert!(result.is_ok()),
+                _ => assert_eq!(
+                    result,
+                    Err(MappingError::InvalidBinding(binding.to_owned())),
+                    "{binding}"
+                ),
+            }
+        }
+    }
+
+    #[test]
+    fn database() {
+        let mut db = MappingDb::default();
+        let count = db.add_mappings(&format!(
+            "# A comment\n\n{GUID},Linux Pad,a:b0,platform:Linux,\n\
+             030000005e040000a102000000000000,Windows Pad,a:b0,platform:Windows,\n\
+             not a mapping\n"
+        ));
+        assert_eq!(count, 1);
+
+        // Bytes 2 and 3 hold a checksum of the name in newer versions of SDL, which is ignored.
+        let mapping = db.get(&format!("03001234{}", &GUID[8..])).unwrap();
+        assert_eq!(mapping.name, "Linux Pad");
+        assert!(db.get("030000005e040000a102000000000000").is_none());
+
+        // Lookups with malformed GUIDs just fail, even if they are 32 bytes long.
+        assert!(db.get(&format!("000€{}", "0".repeat(26))).is_none());
+    }
+}
@@ -0,0 +1,378 @@
+//! Gamepads, mapped to a standard layout.
+//!
+//! Gamepads are independent of windows. Create a [`Gamepads`] and call [`Gamepads::poll()`]
+//! once per frame, e.g. next to [`Window::poll_events()`](super::Window::poll_events).
+//!
+//! Devices are mapped with SDL's gamecontrollerdb format, see [`Mapping`]. Devices without a mapping
+//! are mapped by the buttons and axes their driver reports, which works for most modern controllers.
+//! Only Linux is supported for now; on other platforms no gamepad ever connects.
+
+#[cfg(target_os = "linux")]
+mod linux;
+
+#[cfg(target_os = "linux")]
+use linux as imp;
+
+#[cfg(not(target_os = "linux"))]
+mod unsupported;
+
+#[cfg(not(target_os = "linux"))]
+use unsupported as imp;
+
+pub use evdev::*;
+pub use mapping::*;
+
+mod evdev;
+mod mapping;
+
+use std::{
+    collections::HashSet,
+    env,
+    path::PathBuf,
+    time::{Duration, Instant},
+};
+
+use super::ElementState;
+
+/// How often the system is scanned for newly connected gamepads.
+const SCAN_INTERVAL: Duration = Duration::from_secs(1);
+
+/// Identifies a connected gamepad. Ids are not reused when a gamepad reconnects.
+#[derive(Debug, Clone, Copy, PartialEq, Eq, Hash)]
+pub struct GamepadId(u32);
+
+/// A button of the standard layout, named after its position like on an Xbox controller.
+#[derive(Debug, Clone, Copy, PartialEq, Eq, Hash)]
+pub enum GamepadButton {
+    /// A on Xbox, cross on PlayStation.
+    South,
+
+    /// B on Xbox, circle on PlayStation.
+    East,
+
+    /// X on Xbox, square on PlayStation.
+    West,
+
+    /// Y on Xbox, triangle on PlayStation.
+    North,
+    LeftShoulder,
+    RightShoulder,
+    Back,
+    Start,
+    Guide,
+    LeftStick,
+    RightStick,
+    DPadUp,
+    DPadDown,
+    DPadLeft,
+    DPadRight,
+}
+
+/// An axis of the standard layout.
+///
+/// Sticks range from -1.0 to 1.0, with positive values pointing right and down.
+/// Triggers range from 0.0 to 1.0.
+#[derive(Debug, Clone, Copy, PartialEq, Eq, Hash)]
+pub enum GamepadAxis {
+    LeftX,
+    LeftY,
+    RightX,
+    RightY,
+    LeftTrigger,
+    RightTrigger,
+}
+
+impl GamepadButton {
+    const ALL: [Self; 15] = [
+        Self::South,
+        Self::East,
+        Self::West,
+        Self::North,
+        Self::LeftShoulder,
+        Self::RightShoulder,
+        Self::Back,
+        Self::Start,
+        Self::Guide,
+        Self::LeftStick,
+        Self::RightStick,
+        Self::DPadUp,
+        Self::DPadDown,
+        Self::DPadLeft,
+        Self::DPadRight,
+    ];
+}
+
+impl GamepadAxis {
+    const ALL: [Self; 6] = [
+        Self::LeftX,
+        Self::LeftY,
+        Self::RightX,
+        Self::RightY,
+        Self::LeftTrigger,
+        Self::RightTrigger,
+    ];
+}
+
+#[derive(Debug, Clone, PartialEq)]
+pub enum GamepadEvent {
+    Connected(GamepadId),
+    Disconnected(GamepadId),
+    Button {
+        id: GamepadId,
+        button: GamepadButton,
+        state: ElementState,
+    },
+    Axis {
+        id: GamepadId,
+        axis: GamepadAxis,
+        value: f32,
+    },
+}
+
+/// A change of a single button or axis of a gamepad.
+#[derive(Debug, Clone, Copy, PartialEq)]
+pub enum GamepadInput {
+    Button {
+        button: GamepadButton,
+        state: ElementState,
+    },
+    Axis {
+        axis: GamepadAxis,
+        value: f32,
+    },
+}
+
+impl GamepadInput {
+    fn into_event(self, id: GamepadId) -> GamepadEvent {
+        match self {
+            Self::Button { button, state } => GamepadEvent::Button { id, button, state },
+            Self::Axis { axis, value } => GamepadEvent::Axis { id, axis, value },
+        }
+    }
+}
+
+/// The state of a gamepad, updated by [`Gamepads::poll()`].
+///
+/// Like [`KeyboardState`](super::KeyboardState), "pressed" and "released" refer to
+/// the inputs delivered by the most recent call to `poll`.
+#[derive(Debug, Default)]
+pub struct GamepadState {
+    down: HashSet<GamepadButton>,
+    pressed: HashSet<GamepadButton>,
+    released: HashSet<GamepadButton>,
+    axes: [f32; GamepadAxis::ALL.len()],
+}
+
+impl GamepadState {
+    /// Returns whether `button` is held down.
+    pub fn is_down(&self, button: GamepadButton) -> bool {
+        self.down.contains(&button)
+    }
+
+    /// Returns whether `button` went down during the last poll.
+    pub fn was_pressed(&self, button: GamepadButton) -> bool {
+        self.pressed.contains(&button)
+    }
+
+    /// Returns whether `button` went up during the last poll.
+    pub fn was_released(&self, button: GamepadButton) -> bool {
+        self.released.contains(&button)
+    }
+
+    /// Returns the current value of `axis`.
+    pub fn axis(&self, axis: GamepadAxis) -> f32 {
+        self.axes[axis as usize]
+    }
+
+    /// Forgets the presses and releases of the previous poll.
+    fn begin_poll(&mut self) {
+        self.pressed.clear();
+        self.released.clear();
+    }
+
+    fn handle_input(&mut self, input: &GamepadInput) {
+        match *input {
+            GamepadInput::Button { button, state } => match state {
+                ElementState::Pressed => {
+                    self.down.insert(button);
+                    self.pressed.insert(button);
+                }
+                ElementState::Released => {
+                    self.down.remove(&button);
+                    self.released.insert(button);
+                }
+            },
+            GamepadInput::Axis { axis, value } => self.axes[axis as usize] = value,
+        }
+    }
+}
+
+struct Gamepad {
+    id: GamepadId,
+    path: PathBuf,
+    name: String,
+    device: imp::Device,
+    mapper: EvdevMapper,
+}
+
+/// The connected gamepads.
+pub struct Gamepads {
+    mappings: MappingDb,
+    gamepads: Vec<Gamepad>,
+
+    /// Devices that were opened but are not gamepads, so they aren't opened again on every scan.
+    ignored: HashSet<PathBuf>,
+    next_id: u32,
+    last_scan: Option<Instant>,
+    events: Vec<RawEvent>,
+}
+
+impl Gamepads {
+    /// Creates an empty set of gamepads. Gamepads that are already connected are reported by the first poll.
+    ///
+    /// Mappings in the `SDL_GAMECONTROLLERCONFIG` environment variable are added, like SDL does.
+    pub fn new() -> Self {
+        let mut mappings = MappingDb::default();
+        if let Ok(config) = env::var("SDL_GAMECONTROLLERCONFIG") {
+            mappings.add_mappings(&config);
+        }
+
+        Self {
+            mappings,
+            gamepads: Vec::new(),
+            ignored: HashSet::new(),
+            next_id: 0,
+            last_scan: None,
+            events: Vec::new(),
+        }
+    }
+
+    /// Adds mappings for gamepads that connect from now on, see [`MappingDb::add_mappings()`].
+    ///
+    /// # Arguments
+    ///
+    /// - `mappings` - The contents of a gamecontrollerdb file.
+    pub fn add_mappings(&mut self, mappings: &str) -> usize {
+        self.mappings.add_mappings(mappings)
+    }
+
+    /// Processes all pending input of all gamepads and passes it to `callback`.
+    ///
+    /// Also looks for newly connected gamepads about once per second.
+    ///
+    /// # Arguments
+    ///
+    /// - `callback` - Called with each event.
+    pub fn poll(&mut self, mut callback: impl FnMut(GamepadEvent)) {
+        for gamepad in &mut self.gamepads {
+            gamepad.mapper.begin_poll();
+        }
+
+        let now = Instant::now();
+        if self
+            .last_scan
+            .is_none_or(|last_scan| now - last_scan >= SCAN_INTERVAL)
+        {
+            self.last_scan = Some(now);
+            self.scan(&mut callback);
+        }
+
+        let events = &mut self.events;
+        self.gamepads.retain_mut(|gamepad| {
+            let id = gamepad.id;
+            events.clear();
+            let connected = gamepad.device.read_events(events).is_ok();
+
+            for event in events.iter() {
+                gamepad
+                    .mapper
+                    .feed(event, |input| callback(input.into_event(id)));
+
+                // Releases that were dropped would otherwise leave buttons held forever.
+                if gamepad.mapper.needs_resync() {
+                    if let Ok(info) = gamepad.device.info() {
+                        gamepad
+                            .mapper
+                            .resync(&info, |input| callback(input.into_event(id)));
+                    }
+                }
+            }
+
+            if !connected {
+                callback(GamepadEvent::Disconnected(id));
+            }
+            connected
+        });
+    }
+
+    fn scan(&mut self, callback: &mut impl FnMut(GamepadEvent)) {
+        let paths = imp::device_paths();
+        self.ignored.retain(|path| paths.contains(path));
+
+        for path in paths {
+            if self.ignored.contains(&path)
+                || self.gamepads.iter().any(|gamepad| gamepad.path == path)
+            {
+                continue;
+            }
+
+            // Devices that can't be opened are retried on the next scan, as permissions
+            // are usually granted shortly after a device appears.
+            let device = match imp::Device::open(&path) {
+                Ok(device) => device,
+                _ => continue,
+            };
+
+            let info = match device.info() {
+                Ok(info) => info,
+                _ => continue,
+            };
+
+            let mapper = match self.mappings.get(&info.guid()) {
+                Some(mapping) => EvdevMapper::new(&info, mapping),
+                None if info.is_gamepad() => EvdevMapper::with_default_mapping(&info),
+                None => {
+                    self.ignored.insert(path);
+                    continue;
+                }
+            };
+
+            let id = GamepadId(self.next_id);
+            self.next_id += 1;
+
+            self.gamepads.push(Gamepad {
+                id,
+                path,
+                name: info.name,
+                device,
+                mapper,
+            });
+            callback(GamepadEvent::Connected(id));
+        }
+    }
+
+    /// Returns the ids of all connected gamepads.
+    pub fn ids(&self) -> impl Iterator<Item = GamepadId> + '_ {
+        self.gamepads.iter().map(|gamepad| gamepad.id)
+    }
+
+    /// Returns the state of a gamepad, or `None` if it is not connected.
+    pub fn state(&self, id: GamepadId) -> Option<&GamepadState> {
+        self.get(id).map(|gamepad| gamepad.mapper.state())
+    }
+
+    /// Returns the name the driver reports for a gamepad, or `None` if it is not connected.
+    pub fn name(&self, id: GamepadId) -> Option<&str> {
+        self.get(id).map(|gamepad| gamepad.name.as_str())
+    }
+
+    fn get(&self, id: GamepadId) -> Option<&Gamepad> {
+        self.gamepads.iter().find(|gamepad| gamepad.id == id)
+    }
+}
+
+impl Default for Gamepads {
+    fn default() -> Self {
+        Self::new()
+    }
+}
@@ -0,0 +1,25 @@
+//! Stand-in for platforms without gamepad support, where no device is ever found.
+
+use std::path::{Path, PathBuf};
+
+use super::{DeviceInfo, RawEvent};
+
+pub fn device_paths() -> Vec<PathBuf> {
+    Vec::new()
+}
+
+pub enum Device {}
+
+impl Device {
+    pub fn open(_path: &Path) -> Result<Self, ()> {
+        Err(())
+    }
+
+    pub fn info(&self) -> Result<DeviceInfo, ()> {
+        match *self {}
+    }
+
+    pub fn read_events(&mut self, _events: &mut Vec<RawEvent>) -> Result<(), ()> {
+        match *self {}
+    }
+}
@@ -0,0 +1,330 @@
+use std::{
+    cell::{Cell, RefCell},
+    collections::VecDeque,
+};
+
+use super::{
+    Cursor, CursorGrabMode, Event, Fullscreen, Icon, Monitor, PhysicalSize, Size, VideoMode,
+    WindowApi, WindowDesc,
+};
+
+/// The position and size of a window.
+type Geometry = ((i32, i32), (u32, u32));
+
+/// A window that is never presented on a display.
+///
+/// Headless windows are used where no display server is available, e.g. tests, CI and servers.
+/// Since nothing generates events for them, events have to be injected with [`Window::inject_event()`].
+pub struct Window {
+    title: RefCell<String>,
+    size: Cell<(u32, u32)>,
+    position: Cell<(i32, i32)>,
+    min_size: Cell<Option<Size>>,
+    max_size: Cell<Option<Size>>,
+    scale_factor: Cell<f64>,
+    resizable: Cell<bool>,
+    decorations: Cell<bool>,
+    always_on_top: Cell<bool>,
+    visible: Cell<bool>,
+    close_requested: Cell<bool>,
+    cursor_grab: Cell<CursorGrabMode>,
+    cursor_visible: Cell<bool>,
+    cursor: RefCell<Cursor>,
+    icon: RefCell<Option<Icon>>,
+    text_input: Cell<bool>,
+    ime_position: Cell<(i32, i32)>,
+    drag_and_drop: Cell<bool>,
+    fullscreen: RefCell<Option<Fullscreen>>,
+
+    /// The geometry to return to when leaving fullscreen, `None` while windowed.
+    windowed: Cell<Option<Geometry>>,
+    events: RefCell<VecDeque<Event>>,
+}
+
+impl Window {
+    pub fn new(desc: &WindowDesc) -> Self {
+        Self {
+            title: RefCell::new(desc.title.to_owned()),
+            size: Cell::new(desc.size.to_physical(1.0).into()),
+            position: Cell::new(desc.position.unwrap_or_default()),
+            min_size: Cell::new(desc.min_size),
+            max_size: Cell::new(desc.max_size),
+            scale_factor: Cell::new(1.0),
+            resizable: Cell::new(desc.resizable),
+            decorations: Cell::new(desc.decorations),
+            always_on_top: Cell::new(desc.always_on_top),
+            visible: Cell::new(desc.visible),
+            close_requested: Cell::new(false),
+            cursor_grab: Cell::new(CursorGrabMode::None),
+            cursor_visible: Cell::new(true),
+            cursor: RefCell::new(Cursor::default()),
+            icon: RefCell::new(desc.icon.cloned()),
+            text_input: Cell::new(false),
+            ime_position: Cell::new((0, 0)),
+            drag_and_drop: Cell::new(desc.drag_and_drop),
+            fullscreen: RefCell::new(None),
+            windowed: Cell::new(None),
+            events: RefCell::new(VecDeque::new()),
+        }
+    }
+
+    /// Queues a synthetic event, which is delivered on the next call to [`WindowApi::poll_events()`].
+    ///
+    /// Events that describe a state change, like [`Event::Resized`], are also applied to the window,
+    /// once they are delivered by [`WindowApi::poll_events()`] rather than when they are injected.
+    ///
+    /// # Arguments
+    ///
+    /// - `event` - The event to deliver.
+    pub fn inject_event(&self, event: Event) {
+        self.events.borrow_mut().push_back(event);
+    }
+
+    pub fn title(&self) -> String {
+        self.title.borrow().clone()
+    }
+
+    /// Returns the size of the window in pixels.
+    pub fn size(&self) -> (u32, u32) {
+        self.size.get()
+    }
+
+    pub fn position(&self) -> (i32, i32) {
+        self.position.get()
+    }
+
+    pub fn is_resizable(&self) -> bool {
+        self.resizable.get()
+    }
+
+    pub fn is_decorated(&self) -> bool {
+        self.decorations.get()
+    }
+
+    pub fn is_always_on_top(&self) -> bool {
+        self.always_on_top.get()
+    }
+
+    pub fn is_visible(&self) -> bool {
+        self.visible.get()
+    }
+
+    /// Returns whether a close request has been delivered.
+    pub fn is_close_requested(&self) -> bool {
+        self.close_requested.get()
+    }
+
+    /// Forgets a delivered close request.
+    pub fn cancel_close_request(&self) {
+        self.close_requested.set(false)
+    }
+
+    pub fn cursor_grab(&self) -> CursorGrabMode {
+        self.cursor_grab.get()
+    }
+
+    pub fn is_cursor_visible(&self) -> bool {
+        self.cursor_visible.get()
+    }
+
+    pub fn cursor(&self) -> Cursor {
+        self.cursor.borrow().clone()
+    }
+
+    pub fn icon(&self) -> Option<Icon> {
+        self.icon.borrow().clone()
+    }
+
+    pub fn is_text_input_enabled(&self) -> bool {
+        self.text_input.get()
+    }
+
+    pub fn ime_position(&self) -> (i32, i32) {
+        self.ime_position.get()
+    }
+
+    pub fn is_drag_and_drop_enabled(&self) -> bool {
+        self.drag_and_drop.get()
+    }
+
+    pub fn fullscreen(&self) -> Option<Fullscreen> {
+        self.fullscreen.borrow().clone()
+    }
+
+    /// Returns the single virtual monitor, a 1920x1080 display at 60 Hz.
+    fn monitor() -> Monitor {
+        let video_mode = VideoMode {
+            size: (1920, 1080),
+            bit_depth: 24,
+            refresh_rate_millihertz: 60000,
+        };
+
+        Monitor {
+            name: "Headless".to_owned(),
+            position: (0, 0),
+            size: video_mode.size,
+            physical_size: (0, 0),
+            scale_factor: 1.0,
+            video_mode,
+            video_modes: vec![video_mode],
+            handle: 0,
+        }
+    }
+
+    /// Covers the given area, remembering the windowed geometry unless already fullscreen.
+    fn enter_fullscreen(&self, position: (i32, i32), size: (u32, u32)) {
+        if self.windowed.get().is_none() {
+            self.windowed
+                .set(Some((self.position.get(), self.size.get())));
+        }
+        self.position.set(position);
+        self.size.set(size);
+    }
+
+    /// Clamps a size to the minimum and maximum size, like a window manager would.
+    fn clamp_size(&self, (width, height): (u32, u32)) -> (u32, u32) {
+        let scale_factor = self.scale_factor.get();
+        let (min_width, min_height) = self
+            .min_size
+            .get()
+            .map_or((0, 0), |size| size.to_physical(scale_factor).into());
+        let (max_width, max_height) = self.max_size.get().map_or((u32::MAX, u32::MAX), |size| {
+            size.to_physical(scale_factor).into()
+        });
+        (
+            width.max(min_width).min(max_width),
+            height.max(min_height).min(max_height),
+        )
+    }
+}
+
+impl WindowApi for Window {
+    fn poll_events(&self, callback: &mut dyn FnMut(Event)) {
+        // The queue is taken first, so the callback may inject new events for the next poll.
+        let events = std::mem::take(&mut *self.events.borrow_mut());
+        for event in events {
+            match event {
+                Event::CloseRequested => self.close_requested.set(true),
+                Event::Resized { width, height } => self.size.set((width, height)),
+                Event::ScaleFactorChanged { scale_factor } => self.scale_factor.set(scale_factor),
+                Event::Moved { x, y } => self.position.set((x, y)),
+
+                // Like on native windows, text is only delivered while text input is enabled.
+                Event::ReceivedCharacter(_) | Event::Ime(_) if !self.text_input.get() => continue,
+                Event::HoveredFile { .. }
+                | Event::HoveredFileCancelled
+                | Event::DroppedFile { .. }
+                    if !self.drag_and_drop.get() =>
+                {
+                    continue
+                }
+                _ => {}
+            }
+
+            callback(event);
+        }
+    }
+
+    fn set_title(&self, title: &str) {
+        *self.title.borrow_mut() = title.to_owned();
+    }
+
+    fn scale_factor(&self) -> f64 {
+        self.scale_factor.get()
+    }
+
+    fn inner_size(&self) -> PhysicalSize {
+        self.size.get().into()
+    }
+
+    fn set_inner_size(&self, size: Size) {
+        let size = size.to_physical(self.scale_factor.get());
+        self.size.set(self.clamp_size(size.into()));
+    }
+
+    fn set_position(&self, x: i32, y: i32) {
+        self.position.set((x, y));
+    }
+
+    fn set_min_inner_size(&self, size: Option<Size>) {
+        self.min_size.set(size);
+        self.size.set(self.clamp_size(self.size.get()));
+    }
+
+    fn set_max_inner_size(&self, size: Option<Size>) {
+        self.max_size.set(size);
+        self.size.set(self.clamp_size(self.size.get()));
+    }
+
+    fn set_resizable(&self, resizable: bool) {
+        self.resizable.set(resizable);
+    }
+
+    fn set_decorations(&self, decorations: bool) {
+        self.decorations.set(decorations);
+    }
+
+    fn set_always_on_top(&self, always_on_top: bool) {
+        self.always_on_top.set(always_on_top);
+    }
+
+    fn set_visible(&self, visible: bool) {
+        self.visible.set(visible);
+    }
+
+    fn set_cursor_grab(&self, mode: CursorGrabMode) {
+        self.cursor_grab.set(mode);
+    }
+
+    fn set_cursor_visible(&self, visible: bool) {
+        self.cursor_visible.set(visible);
+    }
+
+    fn set_cursor(&self, cursor: &Cursor) {
+        *self.cursor.borrow_mut() = cursor.clone();
+    }
+
+    fn set_icon(&self, icon: Option<&Icon>) {
+        *self.icon.borrow_mut() = icon.cloned();
+    }
+
+    fn monitors(&self) -> Vec<Monitor> {
+        vec![Self::monitor()]
+    }
+
+    fn current_monitor(&self) -> Option<Monitor> {
+        Some(Self::monitor())
+    }
+
+    fn set_fullscreen(&self, fullscreen: Option<Fullscreen>) {
+        match &fullscreen {
+            Some(Fullscreen::Borderless(monitor)) => {
+                let monitor = monitor.clone().unwrap_or_else(Self::monitor);
+                self.enter_fullscreen(monitor.position, monitor.size);
+            }
+            Some(Fullscreen::Exclusive(monitor, mode)) => {
+                self.enter_fullscreen(monitor.position, mode.size);
+            }
+            None => {
+                if let Some((position, size)) = self.windowed.take() {
+                    self.position.set(position);
+                    self.size.set(size);
+                }
+            }
+        }
+
+        *self.fullscreen.borrow_mut() = fullscreen;
+    }
+
+    fn set_text_input(&self, enabled: bool) {
+        self.text_input.set(enabled);
+    }
+
+    fn set_ime_position(&self, x: i32, y: i32) {
+        self.ime_position.set((x, y));
+    }
+
+    fn set_drag_and_drop(&self, enabled: bool) {
+        self.drag_and_drop.set(enabled);
+    }
+}
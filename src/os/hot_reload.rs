@@ -0,0 +1,231 @@
+//! Reloading game code while the engine keeps running.
+//!
+//! The game code is built as a `cdylib`, which the host loads through [`HotReload`]. The host owns
+//! everything that must survive a reload, like the [`Window`](super::Window) and the rhi objects,
+//! and passes them to the library on every call. Whatever the library needs to keep between reloads lives
+//! in a state it allocates, which the host holds on to as an opaque pointer.
+//!
+//! The library exports the following functions, with `#[no_mangle]`:
+//!
+//! - `extern "C" fn iglo_load(state: *mut c_void) -> *mut c_void` is called after every load with
+//!   the state returned by the previous `iglo_unload`, or null on the first load, and returns the state.
+//! - `extern "C" fn iglo_unload(state: *mut c_void, shutdown: bool) -> *mut c_void` is called before the
+//!   library is unloaded and returns the state to hand over. With `shutdown` set no new version follows,
+//!   so the state should be freed.
+//!
+//! Both are optional. Everything else the library exports is looked up with [`HotReload::symbol()`],
+//! e.g. an update function that takes the state and the objects of the host.
+//!
+//! The state is only valid as long as its layout doesn't change, so a version that changes it should
+//! migrate it in `iglo_load`, or start over. Likewise the library must not keep pointers to its own
+//! statics, functions or trait objects in the state, since those are unloaded with the old version.
+
+use std::{
+    env,
+    ffi::c_void,
+    fs,
+    path::{Path, PathBuf},
+    process, ptr,
+    sync::atomic::{AtomicUsize, Ordering},
+    time::{Duration, SystemTime},
+};
+
+use super::{DynamicLibrary, LibraryError};
+
+/// The name of the function called after a version of the library is loaded.
+pub const LOAD_SYMBOL: &str = "iglo_load";
+
+/// The name of the function called before a version of the library is unloaded.
+pub const UNLOAD_SYMBOL: &str = "iglo_unload";
+
+/// How long the library must be left unchanged before it is reloaded,
+/// so a library that is still being written by the linker isn't loaded.
+const SETTLE_TIME: Duration = Duration::from_millis(300);
+
+/// Numbers the instances, so libraries with the same name get separate directories.
+static NEXT_ID: AtomicUsize = AtomicUsize::new(0);
+
+type LoadFn = unsafe extern "C" fn(*mut c_void) -> *mut c_void;
+type UnloadFn = unsafe extern "C" fn(*mut c_void, bool) -> *mut c_void;
+
+/// A library that is reloaded whenever it is rebuilt, see the [module documentation](self).
+///
+/// The library is never loaded from where it is built. Windows locks loaded libraries, so they couldn't
+/// be rebuilt, and the dynamic linker on Linux returns the library that is already loaded for the same path.
+/// Instead every version is copied to a directory of its own first, which is removed when this is dropped.
+pub struct HotReload {
+    source: PathBuf,
+    directory: PathBuf,
+    library: Option<DynamicLibrary>,
+    state: *mut c_void,
+
+    /// The modification time of the loaded version.
+    modified: SystemTime,
+
+    /// The modification time of a version that failed to load, so it isn't retried until it changes again.
+    failed: Option<SystemTime>,
+    version: u32,
+}
+
+impl HotReload {
+    /// Loads the first version of a library.
+    ///
+    /// # Arguments
+    ///
+    /// - `path` - The path of the library, e.g. `target/debug/libgame.so` or `target/debug/game.dll`.
+    pub fn new(path: impl AsRef<Path>) -> Result<Self, LibraryError> {
+        let source = path.as_ref().to_owned();
+        let modified = Self::modified(&source)?;
+
+        let stem = source.file_stem().unwrap_or_default().to_string_lossy();
+        let id = NEXT_ID.fetch_add(1, Ordering::Relaxed);
+        let directory =
+            env::temp_dir().join(format!("iglo-hot-reload-{}-{}-{}", process::id(), id, stem));
+        fs::create_dir_all(&directory)?;
+
+        let mut reload = Self {
+            source,
+            directory,
+            library: None,
+            state: ptr::null_mut(),
+            modified,
+            failed: None,
+            version: 0,
+        };
+        reload.load(modified)?;
+        Ok(reload)
+    }
+
+    /// Returns the loaded version of the library.
+    pub fn library(&self) -> &DynamicLibrary {
+        self.library.as_ref().unwrap()
+    }
+
+    /// Returns the state of the library, see the [module documentation](self).
+    pub fn state(&self) -> *mut c_void {
+        self.state
+    }
+
+    /// Returns how often the library has been loaded, starting with 1 for the first version.
+    pub fn version(&self) -> u32 {
+        self.version
+    }
+
+    /// Returns an exported symbol of the loaded version, see [`DynamicLibrary::symbol()`].
+    ///
+    /// # Safety
+    ///
+    /// `T` must match the type of the symbol, and the value must not be used after the next reload.
+    ///
+    /// # Arguments
+    ///
+    /// - `name` - The unmangled name of the symbol.
+    pub unsafe fn symbol<T: Copy>(&self, name: &str) -> Result<T, LibraryError> {
+        self.library().symbol(name)
+    }
+
+    /// Reloads the library if it was rebuilt, and returns whether it was.
+    ///
+    /// This only checks the modification time of the file, so it is cheap enough to call every frame.
+    /// If the new version fails to load, the previous one stays loaded and the error is returned once.
+    pub fn reload_if_changed(&mut self) -> Result<bool, LibraryError> {
+        let modified = match Self::modified(&self.source) {
+            Ok(modified) => modified,
+
+            // The file is missing while it is rebuilt.
+            Err(LibraryError::NotFound) => return Ok(false),
+            Err(error) => return Err(error),
+        };
+
+        let settled = SystemTime::now()
+            .duration_since(modified)
+            .is_ok_and(|age| age >= SETTLE_TIME);
+        if modified == self.modified || Some(modified) == self.failed || !settled {
+            return Ok(false);
+        }
+
+        match self.load(modified) {
+            Ok(()) => Ok(true),
+            Err(error) => {
+                self.failed = Some(modified);
+                Err(error)
+            }
+        }
+    }
+
+    /// Unloads the current version and loads the library again, whether or not it changed.
+    ///
+    /// If the new version fails to load, the previous one stays loaded.
+    pub fn reload(&mut self) -> Result<(), LibraryError> {
+        let modified = Self::modified(&self.source)?;
+        self.load(modified)
+    }
+
+    /// Loads a copy of the library, and hands the state over from the previous version.
+    fn load(&mut self, modified: SystemTime) -> Result<(), LibraryError> {
+        let path = self.directory.join(format!(
+            "{}-{}",
+            self.version + 1,
+            self.source
+                .file_name()
+                .unwrap_or_default()
+                .to_string_lossy()
+        ));
+        fs::copy(&self.source, &path)?;
+
+        // The new version is loaded before the previous one is unloaded, so it can be kept if loading fails.
+        let library = match DynamicLibrary::open(&path) {
+            Ok(library) => library,
+            Err(error) => {
+                let _ = fs::remove_file(&path);
+                return Err(error);
+            }
+        };
+
+        if let Some(previous) = self.library.take() {
+            self.unload(previous, false);
+        }
+
+        // SAFETY: The library exports the functions with the documented signatures.
+        if let Ok(load) = unsafe { library.symbol::<LoadFn>(LOAD_SYMBOL) } {
+            self.state = unsafe { load(self.state) };
+        }
+
+        self.library = Some(library);
+        self.modified = modified;
+        self.failed = None;
+        self.version += 1;
+        Ok(())
+    }
+
+    /// Takes the state from a version of the library, unloads it and removes its copy.
+    fn unload(&mut self, library: DynamicLibrary, shutdown: bool) {
+        // SAFETY: The library exports the functions with the documented signatures.
+        if let Ok(unload) = unsafe { library.symbol::<UnloadFn>(UNLOAD_SYMBOL) } {
+            self.state = unsafe { unload(self.state, shutdown) };
+        }
+
+        let path = library.path().to_owned();
+        drop(library);
+        let _ = fs::remove_file(path);
+    }
+
+    fn modified(path: &Path) -> Result<SystemTime, LibraryError> {
+        match fs::metadata(path).and_then(|metadata| metadata.modified()) {
+            Ok(modified) => Ok(modified),
+            Err(error) if error.kind() == std::io::ErrorKind::NotFound => {
+                Err(LibraryError::NotFound)
+            }
+            Err(error) => Err(error.into()),
+        }
+    }
+}
+
+impl Drop for HotReload {
+    fn drop(&mut self) {
+        if let Some(library) = self.library.take() {
+            self.unload(library, true);
+        }
+        let _ = fs::remove_dir_all(&self.directory);
+    }
+}
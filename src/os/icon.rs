@@ -0,0 +1,33 @@
+//! Window icons.
+
+use super::ImageData;
+
+/// The icon of a window, shown in its title bar, the taskbar and the task switcher.
+///
+/// Each of these shows the icon at a different size, so an icon is made of images of several sizes,
+/// e.g. 16x16, 32x32, 48x48 and 256x256. Platforms pick the closest size and scale it.
+#[derive(Debug, Clone, PartialEq, Eq, Hash)]
+pub struct Icon {
+    images: Vec<ImageData>,
+}
+
+impl Icon {
+    /// Creates an icon from images of different sizes.
+    ///
+    /// Returns `None` if there are no images, or one of them is empty or its data doesn't match its size.
+    ///
+    /// # Arguments
+    ///
+    /// - `images` - The images of the icon, in any order.
+    pub fn new(images: Vec<ImageData>) -> Option<Self> {
+        if images.is_empty() || !images.iter().all(ImageData::is_valid) {
+            return None;
+        }
+
+        Some(Self { images })
+    }
+
+    pub fn images(&self) -> &[ImageData] {
+        &self.images
+    }
+}
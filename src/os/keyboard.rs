@@ -0,0 +1,371 @@
+use std::collections::{HashMap, HashSet};
+
+use bitflags::bitflags;
+
+use super::{ElementState, Event};
+
+/// The platform specific code of a physical key, i.e. its position on the keyboard.
+///
+/// Scancodes don't depend on the keyboard layout, which makes them a good fit for bindings like WASD.
+/// They are not portable though: Linux reports evdev codes and Windows reports set 1 scancodes,
+/// with `0xe000` added for extended keys.
+#[derive(Debug, Clone, Copy, PartialEq, Eq, Hash)]
+pub struct ScanCode(pub u32);
+
+/// A key, as labeled by the active keyboard layout.
+///
+/// Keys that have no equivalent here are reported as `None` in [`Event::KeyboardInput`].
+#[derive(Debug, Clone, Copy, PartialEq, Eq, Hash)]
+pub enum KeyCode {
+    A,
+    B,
+    C,
+    D,
+    E,
+    F,
+    G,
+    H,
+    I,
+    J,
+    K,
+    L,
+    M,
+    N,
+    O,
+    P,
+    Q,
+    R,
+    S,
+    T,
+    U,
+    V,
+    W,
+    X,
+    Y,
+    Z,
+
+    Key0,
+    Key1,
+    Key2,
+    Key3,
+    Key4,
+    Key5,
+    Key6,
+    Key7,
+    Key8,
+    Key9,
+
+    F1,
+    F2,
+    F3,
+    F4,
+    F5,
+    F6,
+    F7,
+    F8,
+    F9,
+    F10,
+    F11,
+    F12,
+    F13,
+    F14,
+    F15,
+    F16,
+    F17,
+    F18,
+    F19,
+    F20,
+    F21,
+    F22,
+    F23,
+    F24,
+
+    Escape,
+    Tab,
+    CapsLock,
+    LeftShift,
+    RightShift,
+    LeftControl,
+    RightControl,
+    LeftAlt,
+    RightAlt,
+    LeftSuper,
+    RightSuper,
+    Menu,
+    Space,
+    Enter,
+    Backspace,
+
+    Insert,
+    Delete,
+    Home,
+    End,
+    PageUp,
+    PageDown,
+    Left,
+    Right,
+    Up,
+    Down,
+
+    PrintScreen,
+    ScrollLock,
+    Pause,
+    NumLock,
+
+    /// The <code>`</code> key on a US layout.
+    Grave,
+    Minus,
+    Equals,
+    LeftBracket,
+    RightBracket,
+    Backslash,
+    Semicolon,
+    Apostrophe,
+    Comma,
+    Period,
+    Slash,
+
+    Numpad0,
+    Numpad1,
+    Numpad2,
+    Numpad3,
+    Numpad4,
+    Numpad5,
+    Numpad6,
+    Numpad7,
+    Numpad8,
+    Numpad9,
+    NumpadAdd,
+    NumpadSubtract,
+    NumpadMultiply,
+    NumpadDivide,
+    NumpadDecimal,
+    NumpadEnter,
+}
+
+impl KeyCode {
+    /// All key codes in declaration order, so the index of a key code is `key as usize`.
+    pub(crate) const ALL: [KeyCode; 116] = [
+        KeyCode::A,
+        KeyCode::B,
+        KeyCode::C,
+        KeyCode::D,
+        KeyCode::E,
+        KeyCode::F,
+        KeyCode::G,
+        KeyCode::H,
+        KeyCode::I,
+        KeyCode::J,
+        KeyCode::K,
+        KeyCode::L,
+        KeyCode::M,
+        KeyCode::N,
+        KeyCode::O,
+        KeyCode::P,
+        KeyCode::Q,
+        KeyCode::R,
+        KeyCode::S,
+        KeyCode::T,
+        KeyCode::U,
+        KeyCode::V,
+        KeyCode::W,
+        KeyCode::X,
+        KeyCode::Y,
+        KeyCode::Z,
+        KeyCode::Key0,
+        KeyCode::Key1,
+        KeyCode::Key2,
+        KeyCode::Key3,
+        KeyCode::Key4,
+        KeyCode::Key5,
+        KeyCode::Key6,
+        KeyCode::Key7,
+        KeyCode::Key8,
+        KeyCode::Key9,
+        KeyCode::F1,
+        KeyCode::F2,
+        KeyCode::F3,
+        KeyCode::F4,
+        KeyCode::F5,
+        KeyCode::F6,
+        KeyCode::F7,
+        KeyCode::F8,
+        KeyCode::F9,
+        KeyCode::F10,
+        KeyCode::F11,
+        KeyCode::F12,
+        KeyCode::F13,
+        KeyCode::F14,
+        KeyCode::F15,
+        KeyCode::F16,
+        KeyCode::F17,
+        KeyCode::F18,
+        KeyCode::F19,
+        KeyCode::F20,
+        KeyCode::F21,
+        KeyCode::F22,
+        KeyCode::F23,
+        KeyCode::F24,
+        KeyCode::Escape,
+        KeyCode::Tab,
+        KeyCode::CapsLock,
+        KeyCode::LeftShift,
+        KeyCode::RightShift,
+        KeyCode::LeftControl,
+        KeyCode::RightControl,
+        KeyCode::LeftAlt,
+        KeyCode::RightAlt,
+        KeyCode::LeftSuper,
+        KeyCode::RightSuper,
+        KeyCode::Menu,
+        KeyCode::Space,
+        KeyCode::Enter,
+        KeyCode::Backspace,
+        KeyCode::Insert,
+        KeyCode::Delete,
+        KeyCode::Home,
+        KeyCode::End,
+        KeyCode::PageUp,
+        KeyCode::PageDown,
+        KeyCode::Left,
+        KeyCode::Right,
+        KeyCode::Up,
+        KeyCode::Down,
+        KeyCode::PrintScreen,
+        KeyCode::ScrollLock,
+        KeyCode::Pause,
+        KeyCode::NumLock,
+        KeyCode::Grave,
+        KeyCode::Minus,
+        KeyCode::Equals,
+        KeyCode::LeftBracket,
+        KeyCode::RightBracket,
+        KeyCode::Backslash,
+        KeyCode::Semicolon,
+        KeyCode::Apostrophe,
+        KeyCode::Comma,
+        KeyCode::Period,
+        KeyCode::Slash,
+        KeyCode::Numpad0,
+        KeyCode::Numpad1,
+        KeyCode::Numpad2,
+        KeyCode::Numpad3,
+        KeyCode::Numpad4,
+        KeyCode::Numpad5,
+        KeyCode::Numpad6,
+        KeyCode::Numpad7,
+        KeyCode::Numpad8,
+        KeyCode::Numpad9,
+        KeyCode::NumpadAdd,
+        KeyCode::NumpadSubtract,
+        KeyCode::NumpadMultiply,
+        KeyCode::NumpadDivide,
+        KeyCode::NumpadDecimal,
+        KeyCode::NumpadEnter,
+    ];
+}
+
+// Checks that `ALL` covers every key code in declaration order, so recordings decode to the same keys.
+const _: () = {
+    let mut i = 0;
+    while i < KeyCode::ALL.len() {
+        assert!(KeyCode::ALL[i] as usize == i);
+        i += 1;
+    }
+    assert!(KeyCode::NumpadEnter as usize + 1 == KeyCode::ALL.len());
+};
+
+bitflags! {
+    /// The modifier keys and locks that are active.
+    pub struct Modifiers: u8 {
+        const SHIFT = 0b00000001;
+        const CONTROL = 0b00000010;
+        const ALT = 0b00000100;
+        const SUPER = 0b00001000;
+        const CAPS_LOCK = 0b00010000;
+        const NUM_LOCK = 0b00100000;
+    }
+}
+
+impl Default for Modifiers {
+    fn default() -> Self {
+        Self::empty()
+    }
+}
+
+/// The state of the keyboard as seen by a window, updated by [`Window::poll_events()`](super::Window::poll_events).
+///
+/// "Pressed" and "released" refer to the events delivered by the most recent call to `poll_events`,
+/// so calling it once per frame makes them per-frame queries.
+#[derive(Debug, Default)]
+pub struct KeyboardState {
+    /// The keys that are held down, with the key code they had when they were pressed.
+    down: HashMap<ScanCode, Option<KeyCode>>,
+    pressed: HashSet<KeyCode>,
+    released: HashSet<KeyCode>,
+    modifiers: Modifiers,
+}
+
+impl KeyboardState {
+    /// Returns whether `key` is held down.
+    pub fn is_down(&self, key: KeyCode) -> bool {
+        self.down.values().any(|&down| down == Some(key))
+    }
+
+    /// Returns whether `key` went down during the last poll. Repeats are not counted.
+    pub fn was_pressed(&self, key: KeyCode) -> bool {
+        self.pressed.contains(&key)
+    }
+
+    /// Returns whether `key` went up during the last poll.
+    pub fn was_released(&self, key: KeyCode) -> bool {
+        self.released.contains(&key)
+    }
+
+    /// Returns the modifiers reported by the most recent key event.
+    pub fn modifiers(&self) -> Modifiers {
+        self.modifiers
+    }
+
+    /// Forgets the presses and releases of the previous poll.
+    pub(super) fn begin_poll(&mut self) {
+        self.pressed.clear();
+        self.released.clear();
+    }
+
+    pub(super) fn handle_event(&mut self, event: &Event) {
+        match *event {
+            Event::KeyboardInput {
+                scancode,
+                keycode,
+                state,
+                modifiers,
+                repeat,
+            } => {
+                self.modifiers = modifiers;
+
+                match state {
+                    ElementState::Pressed if !repeat => {
+                        self.down.insert(scancode, keycode);
+                        self.pressed.extend(keycode);
+                    }
+                    ElementState::Pressed => {}
+                    ElementState::Released => {
+                        // The key code may have changed since the press, e.g. because the layout was switched.
+                        if let Some(keycode) = self.down.remove(&scancode).unwrap_or(keycode) {
+                            self.released.insert(keycode);
+                        }
+                    }
+                }
+            }
+
+            // Releases are not delivered to unfocused windows, so everything is released here.
+            Event::Focused(false) => {
+                self.released
+                    .extend(self.down.drain().filter_map(|(_, keycode)| keycode));
+                self.modifiers = Modifiers::empty();
+            }
+            _ => {}
+        }
+    }
+}
@@ -0,0 +1,53 @@
+//! Libraries through the dynamic linker.
+
+use std::{
+    ffi::{c_void, CString},
+    os::unix::ffi::OsStrExt,
+    path::Path,
+    ptr::NonNull,
+};
+
+use super::LibraryError;
+
+pub struct Library {
+    handle: NonNull<c_void>,
+}
+
+impl Library {
+    pub fn open(path: &Path) -> Result<Self, LibraryError> {
+        let name = match CString::new(path.as_os_str().as_bytes()) {
+            Ok(name) => name,
+            _ => return Err(LibraryError::InvalidName),
+        };
+
+        // Symbols are resolved right away, so missing ones fail here instead of when they are called.
+        // They are kept local, so reloaded libraries don't resolve to the symbols of previous versions.
+        let handle = unsafe { libc::dlopen(name.as_ptr(), libc::RTLD_NOW | libc::RTLD_LOCAL) };
+        match NonNull::new(handle) {
+            Some(handle) => Ok(Self { handle }),
+            None if path.components().count() > 1 && !path.exists() => Err(LibraryError::NotFound),
+            None => Err(LibraryError::LoadFailed),
+        }
+    }
+
+    pub fn symbol(&self, name: &str) -> Result<*mut c_void, LibraryError> {
+        let name = match CString::new(name) {
+            Ok(name) => name,
+            _ => return Err(LibraryError::InvalidName),
+        };
+
+        // SAFETY: The handle stays valid until the library is dropped.
+        let address = unsafe { libc::dlsym(self.handle.as_ptr(), name.as_ptr()) };
+        match address.is_null() {
+            true => Err(LibraryError::SymbolNotFound),
+            false => Ok(address),
+        }
+    }
+}
+
+impl Drop for Library {
+    fn drop(&mut self) {
+        // SAFETY: Symbols must not be used after the library is dropped, which callers have promised.
+        unsafe { libc::dlclose(self.handle.as_ptr()) };
+    }
+}
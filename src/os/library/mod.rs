@@ -0,0 +1,102 @@
+//! Loading dynamic libraries at runtime.
+
+#[cfg(target_os = "linux")]
+mod linux;
+
+#[cfg(target_os = "linux")]
+use linux as imp;
+
+#[cfg(target_os = "windows")]
+mod windows;
+
+#[cfg(target_os = "windows")]
+use self::windows as imp;
+
+use std::{
+    ffi::c_void,
+    io,
+    mem::{size_of, transmute_copy},
+    path::{Path, PathBuf},
+};
+
+#[derive(Debug, Clone, Copy, PartialEq, Eq)]
+pub enum LibraryError {
+    /// The library file doesn't exist.
+    NotFound,
+
+    /// The file isn't a library for this platform, or its dependencies couldn't be loaded.
+    LoadFailed,
+
+    /// The library doesn't export the symbol.
+    SymbolNotFound,
+
+    /// A path or symbol name contains a nul character.
+    InvalidName,
+
+    /// Reading or copying the library failed.
+    Io(io::ErrorKind),
+}
+
+impl From<io::Error> for LibraryError {
+    fn from(error: io::Error) -> Self {
+        Self::Io(error.kind())
+    }
+}
+
+/// A dynamic library loaded into the process, a shared object on Linux and a DLL on Windows.
+///
+/// The library is unloaded when this is dropped, after which its symbols must not be used anymore.
+pub struct DynamicLibrary {
+    imp: imp::Library,
+    path: PathBuf,
+}
+
+impl DynamicLibrary {
+    /// Loads a library and resolves all of its symbols.
+    ///
+    /// # Arguments
+    ///
+    /// - `path` - The path of the library file. Paths without a directory are searched for like
+    ///   the platform does for dependencies, e.g. in `LD_LIBRARY_PATH`.
+    pub fn open(path: impl AsRef<Path>) -> Result<Self, LibraryError> {
+        let path = path.as_ref();
+        Ok(Self {
+            imp: imp::Library::open(path)?,
+            path: path.to_owned(),
+        })
+    }
+
+    /// Returns the path the library was loaded from.
+    pub fn path(&self) -> &Path {
+        &self.path
+    }
+
+    /// Returns the address of an exported symbol.
+    ///
+    /// # Arguments
+    ///
+    /// - `name` - The unmangled name of the symbol, i.e. of a `#[no_mangle]` item.
+    pub fn symbol_address(&self, name: &str) -> Result<*mut c_void, LibraryError> {
+        self.imp.symbol(name)
+    }
+
+    /// Returns an exported symbol as `T`, usually an `extern "C"` function pointer.
+    ///
+    /// # Safety
+    ///
+    /// `T` must match the type of the symbol, and the value must not be used after the library is dropped.
+    ///
+    /// # Arguments
+    ///
+    /// - `name` - The unmangled name of the symbol, i.e. of a `#[no_mangle]` item.
+    pub unsafe fn symbol<T: Copy>(&self, name: &str) -> Result<T, LibraryError> {
+        assert_eq!(
+            size_of::<T>(),
+            size_of::<*mut c_void>(),
+            "symbols can only be read as pointers"
+        );
+
+        let address = self.symbol_address(name)?;
+        Ok(transmute_copy(&address))
+    }
+}
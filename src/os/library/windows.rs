@@ -0,0 +1,51 @@
+//! Libraries through the Win32 loader.
+
+use std::{ffi::c_void, ffi::CString, os::windows::ffi::OsStrExt, path::Path};
+
+use windows::Win32::{
+    Foundation::{HINSTANCE, PSTR, PWSTR},
+    System::LibraryLoader::*,
+};
+
+use super::LibraryError;
+
+pub struct Library {
+    handle: HINSTANCE,
+}
+
+impl Library {
+    pub fn open(path: &Path) -> Result<Self, LibraryError> {
+        let mut name: Vec<u16> = path.as_os_str().encode_wide().collect();
+        if name.contains(&0) {
+            return Err(LibraryError::InvalidName);
+        }
+        name.push(0);
+
+        let handle = unsafe { LoadLibraryW(PWSTR(name.as_mut_ptr())) };
+        match handle {
+            0 if path.components().count() > 1 && !path.exists() => Err(LibraryError::NotFound),
+            0 => Err(LibraryError::LoadFailed),
+            handle => Ok(Self { handle }),
+        }
+    }
+
+    pub fn symbol(&self, name: &str) -> Result<*mut c_void, LibraryError> {
+        let name = match CString::new(name) {
+            Ok(name) => name,
+            _ => return Err(LibraryError::InvalidName),
+        };
+
+        // SAFETY: The handle stays valid until the library is dropped.
+        match unsafe { GetProcAddress(self.handle, PSTR(name.as_ptr() as *mut u8)) } {
+            Some(address) => Ok(address as *mut c_void),
+            None => Err(LibraryError::SymbolNotFound),
+        }
+    }
+}
+
+impl Drop for Library {
+    fn drop(&mut self) {
+        // SAFETY: Symbols must not be used after the library is dropped, which callers have promised.
+        unsafe { FreeLibrary(self.handle) };
+    }
+}
@@ -0,0 +1,268 @@
+//! Translation of keysyms and evdev codes, which are shared by X11 and Wayland, to [`KeyCode`]s.
+
+// The keysym constants keep the names of the X11 headers.
+#![allow(non_upper_case_globals)]
+
+use x11_dl::keysym::*;
+
+use crate::os::KeyCode;
+
+const LETTERS: [KeyCode; 26] = [
+    KeyCode::A,
+    KeyCode::B,
+    KeyCode::C,
+    KeyCode::D,
+    KeyCode::E,
+    KeyCode::F,
+    KeyCode::G,
+    KeyCode::H,
+    KeyCode::I,
+    KeyCode::J,
+    KeyCode::K,
+    KeyCode::L,
+    KeyCode::M,
+    KeyCode::N,
+    KeyCode::O,
+    KeyCode::P,
+    KeyCode::Q,
+    KeyCode::R,
+    KeyCode::S,
+    KeyCode::T,
+    KeyCode::U,
+    KeyCode::V,
+    KeyCode::W,
+    KeyCode::X,
+    KeyCode::Y,
+    KeyCode::Z,
+];
+
+const DIGITS: [KeyCode; 10] = [
+    KeyCode::Key0,
+    KeyCode::Key1,
+    KeyCode::Key2,
+    KeyCode::Key3,
+    KeyCode::Key4,
+    KeyCode::Key5,
+    KeyCode::Key6,
+    KeyCode::Key7,
+    KeyCode::Key8,
+    KeyCode::Key9,
+];
+
+const NUMPAD_DIGITS: [KeyCode; 10] = [
+    KeyCode::Numpad0,
+    KeyCode::Numpad1,
+    KeyCode::Numpad2,
+    KeyCode::Numpad3,
+    KeyCode::Numpad4,
+    KeyCode::Numpad5,
+    KeyCode::Numpad6,
+    KeyCode::Numpad7,
+    KeyCode::Numpad8,
+    KeyCode::Numpad9,
+];
+
+const FUNCTION_KEYS: [KeyCode; 24] = [
+    KeyCode::F1,
+    KeyCode::F2,
+    KeyCode::F3,
+    KeyCode::F4,
+    KeyCode::F5,
+    KeyCode::F6,
+    KeyCode::F7,
+    KeyCode::F8,
+    KeyCode::F9,
+    KeyCode::F10,
+    KeyCode::F11,
+    KeyCode::F12,
+    KeyCode::F13,
+    KeyCode::F14,
+    KeyCode::F15,
+    KeyCode::F16,
+    KeyCode::F17,
+    KeyCode::F18,
+    KeyCode::F19,
+    KeyCode::F20,
+    KeyCode::F21,
+    KeyCode::F22,
+    KeyCode::F23,
+    KeyCode::F24,
+];
+
+/// Translates a keysym to the key code it is labeled with.
+///
+/// Both cases of a letter map to the same key code. The keypad keys map to the numpad key codes,
+/// regardless of the state of num lock.
+pub fn keysym_to_keycode(keysym: u32) -> Option<KeyCode> {
+    let keycode = match keysym {
+        XK_a..=XK_z => LETTERS[(keysym - XK_a) as usize],
+        XK_A..=XK_Z => LETTERS[(keysym - XK_A) as usize],
+        XK_0..=XK_9 => DIGITS[(keysym - XK_0) as usize],
+        XK_F1..=XK_F24 => FUNCTION_KEYS[(keysym - XK_F1) as usize],
+        XK_KP_0..=XK_KP_9 => NUMPAD_DIGITS[(keysym - XK_KP_0) as usize],
+
+        XK_Escape => KeyCode::Escape,
+        XK_Tab | XK_ISO_Left_Tab => KeyCode::Tab,
+        XK_Caps_Lock => KeyCode::CapsLock,
+        XK_Shift_L => KeyCode::LeftShift,
+        XK_Shift_R => KeyCode::RightShift,
+        XK_Control_L => KeyCode::LeftControl,
+        XK_Control_R => KeyCode::RightControl,
+        XK_Alt_L | XK_Meta_L => KeyCode::LeftAlt,
+        XK_Alt_R | XK_Meta_R | XK_ISO_Level3_Shift => KeyCode::RightAlt,
+        XK_Super_L => KeyCode::LeftSuper,
+        XK_Super_R => KeyCode::RightSuper,
+        XK_Menu => KeyCode::Menu,
+        XK_space => KeyCode::Space,
+        XK_Return => KeyCode::Enter,
+        XK_BackSpace => KeyCode::Backspace,
+
+        XK_Insert => KeyCode::Insert,
+        XK_Delete => KeyCode::Delete,
+        XK_Home => KeyCode::Home,
+        XK_End => KeyCode::End,
+        XK_Prior => KeyCode::PageUp,
+        XK_Next => KeyCode::PageDown,
+        XK_Left => KeyCode::Left,
+        XK_Right => KeyCode::Right,
+        XK_Up => KeyCode::Up,
+        XK_Down => KeyCode::Down,
+
+        XK_Print => KeyCode::PrintScreen,
+        XK_Scroll_Lock => KeyCode::ScrollLock,
+        XK_Pause => KeyCode::Pause,
+        XK_Num_Lock => KeyCode::NumLock,
+
+        XK_grave => KeyCode::Grave,
+        XK_minus => KeyCode::Minus,
+        XK_equal => KeyCode::Equals,
+        XK_bracketleft => KeyCode::LeftBracket,
+        XK_bracketright => KeyCode::RightBracket,
+        XK_backslash => KeyCode::Backslash,
+        XK_semicolon => KeyCode::Semicolon,
+        XK_apostrophe => KeyCode::Apostrophe,
+        XK_comma => KeyCode::Comma,
+        XK_period => KeyCode::Period,
+        XK_slash => KeyCode::Slash,
+
+        XK_KP_Insert => KeyCode::Numpad0,
+        XK_KP_End => KeyCode::Numpad1,
+        XK_KP_Down => KeyCode::Numpad2,
+        XK_KP_Next => KeyCode::Numpad3,
+        XK_KP_Left => KeyCode::Numpad4,
+        XK_KP_Begin => KeyCode::Numpad5,
+        XK_KP_Right => KeyCode::Numpad6,
+        XK_KP_Home => KeyCode::Numpad7,
+        XK_KP_Up => KeyCode::Numpad8,
+        XK_KP_Prior => KeyCode::Numpad9,
+        XK_KP_Add => KeyCode::NumpadAdd,
+        XK_KP_Subtract => KeyCode::NumpadSubtract,
+        XK_KP_Multiply => KeyCode::NumpadMultiply,
+        XK_KP_Divide => KeyCode::NumpadDivide,
+        XK_KP_Decimal | XK_KP_Delete => KeyCode::NumpadDecimal,
+        XK_KP_Enter => KeyCode::NumpadEnter,
+
+        _ => return None,
+    };
+
+    Some(keycode)
+}
+
+/// Translates an evdev code to the key code it has on a US layout, see linux/input-event-codes.h.
+///
+/// This is the fallback for keys whose keysym has no key code, e.g. digits combined with shift.
+pub fn evdev_to_keycode(code: u32) -> Option<KeyCode> {
+    let keycode = match code {
+        1 => KeyCode::Escape,
+        2..=10 => DIGITS[code as usize - 1],
+        11 => KeyCode::Key0,
+        12 => KeyCode::Minus,
+        13 => KeyCode::Equals,
+        14 => KeyCode::Backspace,
+        15 => KeyCode::Tab,
+        16 => KeyCode::Q,
+        17 => KeyCode::W,
+        18 => KeyCode::E,
+        19 => KeyCode::R,
+        20 => KeyCode::T,
+        21 => KeyCode::Y,
+        22 => KeyCode::U,
+        23 => KeyCode::I,
+        24 => KeyCode::O,
+        25 => KeyCode::P,
+        26 => KeyCode::LeftBracket,
+        27 => KeyCode::RightBracket,
+        28 => KeyCode::Enter,
+        29 => KeyCode::LeftControl,
+        30 => KeyCode::A,
+        31 => KeyCode::S,
+        32 => KeyCode::D,
+        33 => KeyCode::F,
+        34 => KeyCode::G,
+        35 => KeyCode::H,
+        36 => KeyCode::J,
+        37 => KeyCode::K,
+        38 => KeyCode::L,
+        39 => KeyCode::Semicolon,
+        40 => KeyCode::Apostrophe,
+        41 => KeyCode::Grave,
+        42 => KeyCode::LeftShift,
+        43 => KeyCode::Backslash,
+        44 => KeyCode::Z,
+        45 => KeyCode::X,
+        46 => KeyCode::C,
+        47 => KeyCode::V,
+        48 => KeyCode::B,
+        49 => KeyCode::N,
+        50 => KeyCode::M,
+        51 => KeyCode::Comma,
+        52 => KeyCode::Period,
+        53 => KeyCode::Slash,
+        54 => KeyCode::RightShift,
+        55 => KeyCode::NumpadMultiply,
+        56 => KeyCode::LeftAlt,
+        57 => KeyCode::Space,
+        58 => KeyCode::CapsLock,
+        59..=68 => FUNCTION_KEYS[code as usize - 59],
+        69 => KeyCode::NumLock,
+        70 => KeyCode::ScrollLock,
+        71 => KeyCode::Numpad7,
+        72 => KeyCode::Numpad8,
+        73 => KeyCode::Numpad9,
+        74 => KeyCode::NumpadSubtract,
+        75 => KeyCode::Numpad4,
+        76 => KeyCode::Numpad5,
+        77 => KeyCode::Numpad6,
+        78 => KeyCode::NumpadAdd,
+        79 => KeyCode::Numpad1,
+        80 => KeyCode::Numpad2,
+        81 => KeyCode::Numpad3,
+        82 => KeyCode::Numpad0,
+        83 => KeyCode::NumpadDecimal,
+        87 => KeyCode::F11,
+        88 => KeyCode::F12,
+        96 => KeyCode::NumpadEnter,
+        97 => KeyCode::RightControl,
+        98 => KeyCode::NumpadDivide,
+        99 => KeyCode::PrintScreen,
+        100 => KeyCode::RightAlt,
+        102 => KeyCode::Home,
+        103 => KeyCode::Up,
+        104 => KeyCode::PageUp,
+        105 => KeyCode::Left,
+        106 => KeyCode::Right,
+        107 => KeyCode::End,
+        108 => KeyCode::Down,
+        109 => KeyCode::PageDown,
+        110 => KeyCode::Insert,
+        111 => KeyCode::Delete,
+        119 => KeyCode::Pause,
+        125 => KeyCode::LeftSuper,
+        126 => KeyCode::RightSuper,
+        127 => KeyCode::Menu,
+        183..=194 => FUNCTION_KEYS[code as usize - 171],
+        _ => return None,
+    };
+
+    Some(keycode)
+}
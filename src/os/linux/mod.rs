@@ -0,0 +1,232 @@
+use std::{env, ffi::OsString, os::unix::ffi::OsStringExt, path::PathBuf};
+
+use raw_window_handle::{
+    DisplayHandle, HandleError, HasDisplayHandle, HasWindowHandle, WindowHandle,
+};
+
+use super::{
+    Cursor, CursorGrabMode, CursorIcon, Event, Fullscreen, Icon, Monitor, PhysicalSize, Size,
+    WindowApi, WindowDesc, WindowError,
+};
+
+mod keymap;
+mod randr;
+pub mod wayland;
+pub mod x11;
+mod xdnd;
+mod xim;
+
+/// A native Linux window.
+///
+/// The display server is picked at runtime. Wayland is used when `WAYLAND_DISPLAY` is set,
+/// otherwise, or if the compositor can't be connected to while `DISPLAY` is set, the window falls back to X11.
+pub enum Window {
+    X11(Box<x11::Window>),
+    Wayland(Box<wayland::Window>),
+}
+
+impl Window {
+    pub fn new(desc: &WindowDesc) -> Result<Self, WindowError> {
+        if env::var_os("WAYLAND_DISPLAY").is_some() {
+            match wayland::Window::new(desc) {
+                Ok(window) => return Ok(Self::Wayland(Box::new(window))),
+
+                // `WAYLAND_DISPLAY` may be left over from another session, while an X server is still reachable.
+                Err(WindowError::ConnectionFailed) if env::var_os("DISPLAY").is_some() => (),
+                Err(error) => return Err(error),
+            }
+        }
+
+        Ok(Self::X11(Box::new(x11::Window::new(desc)?)))
+    }
+
+    fn api(&self) -> &dyn WindowApi {
+        match self {
+            Self::X11(window) => window.as_ref(),
+            Self::Wayland(window) => window.as_ref(),
+        }
+    }
+}
+
+/// Returns whether windows are created on Wayland rather than X11.
+///
+/// This connects to the compositor, since windows fall back to X11 if it can't be reached.
+pub(crate) fn uses_wayland() -> bool {
+    env::var_os("WAYLAND_DISPLAY").is_some()
+        && (env::var_os("DISPLAY").is_none() || wayland::is_available())
+}
+
+/// Returns whether a Wayland or X11 display server is available.
+pub fn is_display_available() -> bool {
+    env::var_os("WAYLAND_DISPLAY").is_some() || env::var_os("DISPLAY").is_some()
+}
+
+/// Returns the names of a cursor in cursor themes, in order of preference.
+///
+/// Themes name cursors after the X11 cursor font, and newer themes also after the CSS cursors.
+fn cursor_names(icon: CursorIcon) -> &'static [&'static str] {
+    match icon {
+        CursorIcon::Arrow => &["left_ptr", "default"],
+        CursorIcon::Text => &["xterm", "text"],
+        CursorIcon::Hand => &["hand2", "pointer"],
+        CursorIcon::Crosshair => &["crosshair"],
+        CursorIcon::Wait => &["watch", "wait"],
+        CursorIcon::Progress => &["left_ptr_watch", "progress"],
+        CursorIcon::ResizeHorizontal => &["sb_h_double_arrow", "ew-resize"],
+        CursorIcon::ResizeVertical => &["sb_v_double_arrow", "ns-resize"],
+        CursorIcon::ResizeTopLeft => &["bottom_right_corner", "nwse-resize"],
+        CursorIcon::ResizeTopRight => &["bottom_left_corner", "nesw-resize"],
+        CursorIcon::Move => &["fleur", "move"],
+        CursorIcon::NotAllowed => &["crossed_circle", "not-allowed"],
+    }
+}
+
+/// Parses a `text/uri-list`, the format dragged files are offered in by both display servers.
+///
+/// Comments and URIs of other schemes are skipped. The host of `file` URIs is ignored,
+/// since files that can be opened are on the local host.
+fn parse_uri_list(list: &[u8]) -> Vec<PathBuf> {
+    list.split(|&c| c == b'\n')
+        .map(|line| line.strip_suffix(b"\r").unwrap_or(line))
+        .filter(|line| !line.starts_with(b"#"))
+        .filter_map(|uri| uri.strip_prefix(b"file://"))
+        .filter_map(|uri| {
+            let path = &uri[uri.iter().position(|&c| c == b'/')?..];
+
+            // Reserved characters are percent-encoded, e.g. spaces as `%20`.
+            let mut decoded = Vec::with_capacity(path.len());
+            let mut i = 0;
+            while i < path.len() {
+                let escaped = path
+                    .get(i + 1..i + 3)
+                    .and_then(|hex| std::str::from_utf8(hex).ok())
+                    .and_then(|hex| u8::from_str_radix(hex, 16).ok());
+
+                match (path[i], escaped) {
+                    (b'%', Some(c)) => {
+                        decoded.push(c);
+                        i += 3;
+                    }
+                    (c, _) => {
+                        decoded.push(c);
+                        i += 1;
+                    }
+                }
+            }
+
+            Some(PathBuf::from(OsString::from_vec(decoded)))
+        })
+        .collect()
+}
+
+impl HasWindowHandle for Window {
+    fn window_handle(&self) -> Result<WindowHandle<'_>, HandleError> {
+        match self {
+            Window::X11(window) => window.window_handle(),
+            Window::Wayland(window) => window.window_handle(),
+        }
+    }
+}
+
+impl HasDisplayHandle for Window {
+    fn display_handle(&self) -> Result<DisplayHandle<'_>, HandleError> {
+        match self {
+            Window::X11(window) => window.display_handle(),
+            Window::Wayland(window) => window.display_handle(),
+        }
+    }
+}
+
+impl WindowApi for Window {
+    fn show(&self) {
+        self.api().show()
+    }
+
+    fn poll_events(&self, callback: &mut dyn FnMut(Event)) {
+        self.api().poll_events(callback)
+    }
+
+    fn set_title(&self, title: &str) {
+        self.api().set_title(title)
+    }
+
+    fn scale_factor(&self) -> f64 {
+        self.api().scale_factor()
+    }
+
+    fn inner_size(&self) -> PhysicalSize {
+        self.api().inner_size()
+    }
+
+    fn set_inner_size(&self, size: Size) {
+        self.api().set_inner_size(size)
+    }
+
+    fn set_position(&self, x: i32, y: i32) {
+        self.api().set_position(x, y)
+    }
+
+    fn set_min_inner_size(&self, size: Option<Size>) {
+        self.api().set_min_inner_size(size)
+    }
+
+    fn set_max_inner_size(&self, size: Option<Size>) {
+        self.api().set_max_inner_size(size)
+    }
+
+    fn set_resizable(&self, resizable: bool) {
+        self.api().set_resizable(resizable)
+    }
+
+    fn set_decorations(&self, decorations: bool) {
+        self.api().set_decorations(decorations)
+    }
+
+    fn set_always_on_top(&self, always_on_top: bool) {
+        self.api().set_always_on_top(always_on_top)
+    }
+
+    fn set_visible(&self, visible: bool) {
+        self.api().set_visible(visible)
+    }
+
+    fn set_cursor_grab(&self, mode: CursorGrabMode) {
+        self.api().set_cursor_grab(mode)
+    }
+
+    fn set_cursor_visible(&self, visible: bool) {
+        self.api().set_cursor_visible(visible)
+    }
+
+    fn set_cursor(&self, cursor: &Cursor) {
+        self.api().set_cursor(cursor)
+    }
+
+    fn set_icon(&self, icon: Option<&Icon>) {
+        self.api().set_icon(icon)
+    }
+
+    fn monitors(&self) -> Vec<Monitor> {
+        self.api().monitors()
+    }
+
+    fn current_monitor(&self) -> Option<Monitor> {
+        self.api().current_monitor()
+    }
+
+    fn set_fullscreen(&self, fullscreen: Option<Fullscreen>) {
+        self.api().set_fullscreen(fullscreen)
+    }
+
+    fn set_text_input(&self, enabled: bool) {
+        self.api().set_text_input(enabled)
+    }
+
+    fn set_ime_position(&self, x: i32, y: i32) {
+        self.api().set_ime_position(x, y)
+    }
+
+    fn set_drag_and_drop(&self, enabled: bool) {
+        self.api().set_drag_and_drop(enabled)
+    }
+}
@@ -0,0 +1,237 @@
+//! Monitors and video modes through the RandR extension.
+
+use std::{os::raw::c_int, slice};
+
+use x11_dl::{xlib, xrandr};
+
+use super::x11::xft_scale_factor;
+use crate::os::{monitor::sort_video_modes, Monitor, VideoMode};
+
+pub struct Randr {
+    xrandr: Box<xrandr::Xrandr>,
+
+    /// The root window, which identifies the screen whose outputs are queried.
+    root: xlib::Window,
+}
+
+impl Randr {
+    /// Loads Xrandr and checks that the server supports RandR 1.3, which added primary outputs.
+    ///
+    /// Returns `None` otherwise, in which case no monitors are reported.
+    pub unsafe fn new(xlib: &xlib::Xlib, display: *mut xlib::Display) -> Option<Self> {
+        let xrandr = xrandr::Xrandr::open().map(Box::new).ok()?;
+
+        let (mut event_base, mut error_base) = (0, 0);
+        if (xrandr.XRRQueryExtension)(display, &mut event_base, &mut error_base) == xlib::False {
+            return None;
+        }
+
+        let (mut major, mut minor) = (0, 0);
+        if (xrandr.XRRQueryVersion)(display, &mut major, &mut minor) == 0 || (major, minor) < (1, 3)
+        {
+            return None;
+        }
+
+        Some(Self {
+            xrandr,
+            root: (xlib.XDefaultRootWindow)(display),
+        })
+    }
+
+    /// Returns the connected and enabled outputs, with the primary output first.
+    pub fn monitors(&self, xlib: &xlib::Xlib, display: *mut xlib::Display) -> Vec<Monitor> {
+        let mut monitors = Vec::new();
+
+        unsafe {
+            let depth = (xlib.XDefaultDepth)(display, (xlib.XDefaultScreen)(display)) as u16;
+            let scale_factor = xft_scale_factor(xlib, display);
+
+            let resources = (self.xrandr.XRRGetScreenResourcesCurrent)(display, self.root);
+            if resources.is_null() {
+                return monitors;
+            }
+
+            let modes = slice_of((*resources).modes, (*resources).nmode);
+            let video_mode = |id: xrandr::RRMode| {
+                modes
+                    .iter()
+                    .find(|mode| mode.id == id)
+                    .map(|mode| Self::video_mode(mode, depth))
+            };
+
+            for &output in slice_of((*resources).outputs, (*resources).noutput) {
+                let info = (self.xrandr.XRRGetOutputInfo)(display, resources, output);
+                if info.is_null() {
+                    continue;
+                }
+
+                // Outputs without a CRTC are connected but switched off.
+                if (*info).connection == xrandr::RR_Connected as xrandr::Connection
+                    && (*info).crtc != 0
+                {
+                    let crtc = (self.xrandr.XRRGetCrtcInfo)(display, resources, (*info).crtc);
+                    if !crtc.is_null() {
+                        let name = slice_of((*info).name as *const u8, (*info).nameLen);
+
+                        let mut video_modes: Vec<_> = slice_of((*info).modes, (*info).nmode)
+                            .iter()
+                            .filter_map(|&id| video_mode(id))
+                            .collect();
+                        sort_video_modes(&mut video_modes);
+
+                        // The size of the CRTC takes rotation into account, unlike its mode.
+                        let size = ((*crtc).width, (*crtc).height);
+                        monitors.push(Monitor {
+                            name: String::from_utf8_lossy(name).into_owned(),
+                            position: ((*crtc).x, (*crtc).y),
+                            size,
+                            physical_size: ((*info).mm_width as u32, (*info).mm_height as u32),
+                            scale_factor,
+                            video_mode: video_mode((*crtc).mode).unwrap_or(VideoMode {
+                                size,
+                                bit_depth: depth,
+                                refresh_rate_millihertz: 0,
+                            }),
+                            video_modes,
+                            handle: output,
+                        });
+
+                        (self.xrandr.XRRFreeCrtcInfo)(crtc);
+                    }
+                }
+
+                (self.xrandr.XRRFreeOutputInfo)(info);
+            }
+
+            (self.xrandr.XRRFreeScreenResources)(resources);
+
+            let primary = (self.xrandr.XRRGetOutputPrimary)(display, self.root);
+            monitors.sort_by_key(|monitor| monitor.handle != primary);
+        }
+
+        monitors
+    }
+
+    /// Switches an output to the mode matching `mode`.
+    ///
+    /// Returns the previous mode, to be passed to [`Randr::restore_mode()`],
+    /// or `None` if the output already uses the mode, doesn't support it or the switch failed.
+    pub fn set_mode(
+        &self,
+        display: *mut xlib::Display,
+        output: u64,
+        mode: &VideoMode,
+    ) -> Option<xrandr::RRMode> {
+        self.configure_crtc(display, output, |modes, current| {
+            modes
+                .iter()
+                .find(|info| {
+                    let candidate = Self::video_mode(info, mode.bit_depth);
+                    candidate.size == mode.size
+                        && candidate.refresh_rate_millihertz == mode.refresh_rate_millihertz
+                })
+                .map(|info| info.id)
+                .filter(|&id| id != current)
+        })
+    }
+
+    /// Switches an output back to a mode returned by [`Randr::set_mode()`].
+    pub fn restore_mode(&self, display: *mut xlib::Display, output: u64, mode: xrandr::RRMode) {
+        self.configure_crtc(display, output, |_, _| Some(mode));
+    }
+
+    /// Sets the mode of the CRTC driving `output` to the one chosen by `choose`,
+    /// keeping its position, rotation and outputs.
+    ///
+    /// `choose` is given the modes the output supports and the current mode of the CRTC.
+    /// Returns the previous mode if the CRTC was reconfigured.
+    fn configure_crtc(
+        &self,
+        display: *mut xlib::Display,
+        output: u64,
+        choose: impl FnOnce(&[xrandr::XRRModeInfo], xrandr::RRMode) -> Option<xrandr::RRMode>,
+    ) -> Option<xrandr::RRMode> {
+        let mut previous = None;
+
+        unsafe {
+            let resources = (self.xrandr.XRRGetScreenResourcesCurrent)(display, self.root);
+            if resources.is_null() {
+                return None;
+            }
+
+            let info = (self.xrandr.XRRGetOutputInfo)(display, resources, output);
+            if !info.is_null() {
+                let crtc = match (*info).crtc {
+                    0 => std::ptr::null_mut(),
+                    crtc => (self.xrandr.XRRGetCrtcInfo)(display, resources, crtc),
+                };
+
+                if !crtc.is_null() {
+                    // Only the modes the output supports are candidates.
+                    let output_modes = slice_of((*info).modes, (*info).nmode);
+                    let modes: Vec<_> = slice_of((*resources).modes, (*resources).nmode)
+                        .iter()
+                        .filter(|mode| output_modes.contains(&mode.id))
+                        .copied()
+                        .collect();
+
+                    if let Some(mode) = choose(&modes, (*crtc).mode) {
+                        let status = (self.xrandr.XRRSetCrtcConfig)(
+                            display,
+                            resources,
+                            (*info).crtc,
+                            xlib::CurrentTime,
+                            (*crtc).x,
+                            (*crtc).y,
+                            mode,
+                            (*crtc).rotation,
+                            (*crtc).outputs,
+                            (*crtc).noutput,
+                        );
+                        if status == xlib::Success as c_int {
+                            previous = Some((*crtc).mode);
+                        }
+                    }
+
+                    (self.xrandr.XRRFreeCrtcInfo)(crtc);
+                }
+
+                (self.xrandr.XRRFreeOutputInfo)(info);
+            }
+
+            (self.xrandr.XRRFreeScreenResources)(resources);
+        }
+
+        previous
+    }
+
+    fn video_mode(mode: &xrandr::XRRModeInfo, bit_depth: u16) -> VideoMode {
+        // Interlaced modes draw half of the lines per refresh, double scanned ones every line twice.
+        let mut lines = mode.vTotal as u64;
+        if mode.modeFlags & xrandr::RR_Interlace as xrandr::XRRModeFlags != 0 {
+            lines /= 2;
+        }
+        if mode.modeFlags & xrandr::RR_DoubleScan as xrandr::XRRModeFlags != 0 {
+            lines *= 2;
+        }
+
+        let refresh_rate_millihertz = match mode.hTotal as u64 * lines {
+            0 => 0,
+            pixels => (mode.dotClock * 1000 / pixels) as u32,
+        };
+
+        VideoMode {
+            size: (mode.width, mode.height),
+            bit_depth,
+            refresh_rate_millihertz,
+        }
+    }
+}
+
+/// Views an array returned by Xrandr as a slice, which may be null if it is empty.
+unsafe fn slice_of<'a, T>(data: *const T, len: c_int) -> &'a [T] {
+    match data.is_null() || len <= 0 {
+        true => &[],
+        false => slice::from_raw_parts(data, len as usize),
+    }
+}
@@ -0,0 +1,1428 @@
+use std::{
+    cell::{Cell, RefCell},
+    collections::VecDeque,
+    ffi::c_void,
+    io::{ErrorKind, Read},
+    os::unix::io::AsRawFd,
+    path::PathBuf,
+    ptr::NonNull,
+    rc::Rc,
+    time::{Duration, Instant},
+};
+
+use raw_window_handle::{
+    DisplayHandle, HandleError, HasDisplayHandle, HasWindowHandle, WaylandDisplayHandle,
+    WaylandWindowHandle, WindowHandle,
+};
+use smithay_client_toolkit::{
+    data_device::{DataOffer, DndAction, DndEvent, ReadPipe},
+    default_environment, environment, new_default_environment,
+    output::{self, OutputInfo},
+    reexports::{
+        client::{
+            protocol::{wl_keyboard, wl_output, wl_pointer, wl_seat, wl_shm, wl_surface},
+            Attached, Display, EventQueue, Main,
+        },
+        protocols::{
+            unstable::{
+                pointer_constraints::v1::client::{
+                    zwp_confined_pointer_v1, zwp_locked_pointer_v1, zwp_pointer_constraints_v1,
+                },
+                relative_pointer::v1::client::{
+                    zwp_relative_pointer_manager_v1, zwp_relative_pointer_v1,
+                },
+                text_input::v3::client::{zwp_text_input_manager_v3, zwp_text_input_v3},
+            },
+            xdg_shell::client::xdg_toplevel,
+        },
+    },
+    seat::{
+        self, keyboard,
+        pointer::{ThemeManager, ThemeSpec, ThemedPointer},
+        SeatData, SeatListener,
+    },
+    window::{self, Decorations, FallbackFrame},
+};
+
+use super::keymap;
+use crate::os::{
+    monitor::sort_video_modes, Cursor, CursorGrabMode, CursorIcon, CustomCursor, ElementState,
+    Event, Fullscreen, Icon, Ime, KeyCode, Modifiers, Monitor, MouseButton, PhysicalSize, ScanCode,
+    Size, VideoMode, WindowApi, WindowDesc, WindowError,
+};
+
+default_environment!(Environment, desktop);
+
+/// Events collected while the event queue is dispatched.
+///
+/// They are handled once dispatching is done, where the window can be borrowed mutably.
+enum PendingEvent {
+    Window(window::Event),
+    Input(Event),
+}
+
+/// The key that is repeated while it is held down.
+///
+/// Wayland leaves key repeat to clients. The compositor announces the rate, but sctk only forwards it
+/// when it drives the repeat itself through calloop, so common defaults are used instead.
+struct KeyRepeat {
+    scancode: ScanCode,
+    keycode: Option<KeyCode>,
+    modifiers: Modifiers,
+
+    /// The text the key produces, repeated along with the key.
+    text: Option<String>,
+    next: Instant,
+}
+
+const KEY_REPEAT_DELAY: Duration = Duration::from_millis(600);
+const KEY_REPEAT_INTERVAL: Duration = Duration::from_millis(40);
+
+/// The cursor name of the default arrow in cursor themes.
+const DEFAULT_CURSOR: &str = "left_ptr";
+
+/// The mime type dragged files are offered as.
+const URI_LIST: &str = "text/uri-list";
+
+/// How long a drop waits for the source to finish writing the dragged files.
+const DROP_TIMEOUT: Duration = Duration::from_secs(1);
+
+enum PointerConstraint {
+    Locked(Main<zwp_locked_pointer_v1::ZwpLockedPointerV1>),
+    Confined(Main<zwp_confined_pointer_v1::ZwpConfinedPointerV1>),
+}
+
+impl PointerConstraint {
+    fn destroy(self) {
+        match self {
+            Self::Locked(locked) => locked.destroy(),
+            Self::Confined(confined) => confined.destroy(),
+        }
+    }
+}
+
+struct Seat {
+    name: String,
+    keyboard: Option<wl_keyboard::WlKeyboard>,
+    text_input: Option<Main<zwp_text_input_v3::ZwpTextInputV3>>,
+
+    /// Whether the text input focus of the seat is on the surface.
+    text_input_entered: bool,
+    pointer: Option<ThemedPointer>,
+    relative_pointer: Option<Main<zwp_relative_pointer_v1::ZwpRelativePointerV1>>,
+    constraint: Option<PointerConstraint>,
+
+    /// The serial of the event the pointer entered the surface with, needed to change the cursor.
+    enter_serial: Option<u32>,
+}
+
+impl Seat {
+    /// Releases the keyboard and the text input that belongs to it.
+    fn release_keyboard(&mut self) {
+        if let Some(keyboard) = self.keyboard.take() {
+            if let Some(text_input) = self.text_input.take() {
+                text_input.destroy();
+            }
+            self.text_input_entered = false;
+            keyboard.release();
+        }
+    }
+
+    /// Releases the pointer and the objects created for it.
+    fn release_pointer(&mut self) {
+        if let Some(pointer) = self.pointer.take() {
+            if let Some(constraint) = self.constraint.take() {
+                constraint.destroy();
+            }
+            if let Some(relative_pointer) = self.relative_pointer.take() {
+                relative_pointer.destroy();
+            }
+            self.enter_serial = None;
+            pointer.release();
+        }
+    }
+}
+
+/// The cursor shown over the surface.
+enum CursorImage {
+    Icon(CursorIcon),
+
+    /// A cursor drawn from an image, attached to a surface of its own.
+    Custom {
+        surface: wl_surface::WlSurface,
+        hotspot: (i32, i32),
+    },
+}
+
+impl CursorImage {
+    fn destroy(self) {
+        if let Self::Custom { surface, .. } = self {
+            surface.destroy();
+        }
+    }
+}
+
+/// Files dragged over the surface.
+struct Drag {
+    /// The pipe the source writes the dragged files to, `None` once they are read.
+    pipe: Option<ReadPipe>,
+
+    /// What was read from the pipe so far.
+    list: Vec<u8>,
+
+    /// The dragged files, `None` until the source wrote all of them.
+    paths: Option<Vec<PathBuf>>,
+    position: (f64, f64),
+}
+
+/// State shared between the window and the callbacks of the seats.
+///
+/// The callbacks only hold weak references, since the seats holding them are owned by this state.
+struct Shared {
+    display: Display,
+    surface: wl_surface::WlSurface,
+    pending_events: RefCell<VecDeque<PendingEvent>>,
+    key_repeat: RefCell<Option<KeyRepeat>>,
+    seats: RefCell<Vec<Seat>>,
+    theme_manager: ThemeManager,
+
+    // Optional protocols, which not every compositor supports.
+    relative_pointer_manager:
+        Option<Main<zwp_relative_pointer_manager_v1::ZwpRelativePointerManagerV1>>,
+    pointer_constraints: Option<Main<zwp_pointer_constraints_v1::ZwpPointerConstraintsV1>>,
+    text_input_manager: Option<Main<zwp_text_input_manager_v3::ZwpTextInputManagerV3>>,
+
+    cursor_grab: Cell<CursorGrabMode>,
+    cursor_visible: Cell<bool>,
+    cursor: RefCell<CursorImage>,
+    text_input: Cell<bool>,
+    ime_position: Cell<(i32, i32)>,
+
+    /// The buffer scale of the surface. Wayland only has integer scales, surface coordinates are logical.
+    scale_factor: Cell<i32>,
+    drag_and_drop: Cell<bool>,
+
+    /// The files dragged over the surface, `None` while nothing is dragged.
+    drag: RefCell<Option<Drag>>,
+}
+
+impl Shared {
+    fn push_event(&self, event: PendingEvent) {
+        self.pending_events.borrow_mut().push_back(event);
+    }
+
+    /// Handles drags of all seats, only offers of files are accepted.
+    fn handle_dnd(&self, event: DndEvent) {
+        let scale_factor = self.scale_factor.get() as f64;
+        match event {
+            DndEvent::Enter {
+                offer: Some(offer),
+                surface,
+                x,
+                y,
+                ..
+            } if surface == self.surface && self.drag_and_drop.get() => {
+                let offers_files =
+                    offer.with_mime_types(|types| types.iter().any(|t| t == URI_LIST));
+                if !offers_files {
+                    offer.accept(None);
+                    return;
+                }
+
+                offer.accept(Some(URI_LIST.to_owned()));
+                offer.set_actions(DndAction::Copy, DndAction::Copy);
+
+                // The files are reported as hovered once the source wrote them, see `read_drag()`.
+                let pipe = self.receive_paths(offer);
+                *self.drag.borrow_mut() = Some(Drag {
+                    // Without a pipe nothing arrives, so the drag holds no files.
+                    paths: pipe.is_none().then(Vec::new),
+                    pipe,
+                    list: Vec::new(),
+                    position: (x * scale_factor, y * scale_factor),
+                });
+            }
+            DndEvent::Motion { x, y, .. } => {
+                if let Some(drag) = self.drag.borrow_mut().as_mut() {
+                    drag.position = (x * scale_factor, y * scale_factor);
+                    if drag.paths.is_some() {
+                        let (x, y) = drag.position;
+                        self.push_event(PendingEvent::Input(Event::CursorMoved { x, y }));
+                    }
+                }
+            }
+            DndEvent::Leave => {
+                let drag = self.drag.borrow_mut().take();
+                if drag.and_then(|drag| drag.paths).is_some() {
+                    self.push_event(PendingEvent::Input(Event::HoveredFileCancelled));
+                }
+            }
+            DndEvent::Drop { offer } => {
+                // Files dropped before the source finished writing them are waited for, but not forever,
+                // since a source in this process only writes them once its queue is dispatched.
+                self.read_drag(DROP_TIMEOUT);
+
+                let drag = self.drag.borrow_mut().take();
+                if let Some(drag) = drag {
+                    let (x, y) = drag.position;
+                    for path in drag.paths.unwrap_or_default() {
+                        self.push_event(PendingEvent::Input(Event::DroppedFile { path, x, y }));
+                    }
+
+                    if let Some(offer) = offer {
+                        offer.finish();
+                    }
+                }
+            }
+            _ => {}
+        }
+    }
+
+    /// Asks the source for the dragged files, which it writes to the returned pipe.
+    ///
+    /// The pipe doesn't block, it is read as the source writes to it, see `read_drag()`.
+    fn receive_paths(&self, offer: &DataOffer) -> Option<ReadPipe> {
+        let pipe = offer.receive(URI_LIST.to_owned()).ok()?;
+
+        let fd = pipe.as_raw_fd();
+        // SAFETY: The pipe owns the file descriptor, which stays open until it is dropped.
+        unsafe {
+            let flags = libc::fcntl(fd, libc::F_GETFL);
+            if flags == -1 || libc::fcntl(fd, libc::F_SETFL, flags | libc::O_NONBLOCK) == -1 {
+                return None;
+            }
+        }
+
+        // The request has to reach the compositor before the source writes anything.
+        let _ = self.display.flush();
+        Some(pipe)
+    }
+
+    /// Reads what the source wrote to the pipe of the drag, and reports the files as hovered once it is done.
+    ///
+    /// # Arguments
+    ///
+    /// * `timeout` - How long to wait for the source to finish, nothing is waited for if it is zero.
+    fn read_drag(&self, timeout: Duration) {
+        let mut drag = self.drag.borrow_mut();
+        let drag = match drag.as_mut() {
+            Some(drag) => drag,
+            None => return,
+        };
+        let pipe = match drag.pipe.as_mut() {
+            Some(pipe) => pipe,
+            None => return,
+        };
+
+        let deadline = Instant::now() + timeout;
+        let mut buffer = [0; 4096];
+        let done = loop {
+            match pipe.read(&mut buffer) {
+                Ok(0) => break true,
+                Ok(len) => drag.list.extend_from_slice(&buffer[..len]),
+                Err(error) if error.kind() == ErrorKind::Interrupted => {}
+                Err(error) if error.kind() == ErrorKind::WouldBlock => {
+                    let remaining = deadline.saturating_duration_since(Instant::now());
+                    if remaining.is_zero() {
+                        break false;
+                    }
+
+                    let mut fd = libc::pollfd {
+                        fd: pipe.as_raw_fd(),
+                        events: libc::POLLIN,
+                        revents: 0,
+                    };
+                    let timeout = remaining.as_millis().clamp(1, i32::MAX as u128) as i32;
+                    unsafe { libc::poll(&mut fd, 1, timeout) };
+                }
+
+                // The files are lost if the source fails, the drag is still reported with what arrived.
+                Err(_) => break true,
+            }
+        };
+
+        if done {
+            drag.pipe = None;
+            let paths = super::parse_uri_list(&std::mem::take(&mut drag.list));
+
+            let (x, y) = drag.position;
+            for path in &paths {
+                self.push_event(PendingEvent::Input(Event::HoveredFile {
+                    path: path.clone(),
+                    x,
+                    y,
+                }));
+            }
+            drag.paths = Some(paths);
+        }
+    }
+
+    /// Maps or releases the keyboard and pointer of a seat to match its capabilities.
+    fn update_seat(self: &Rc<Self>, seat: &Attached<wl_seat::WlSeat>, data: &SeatData) {
+        let mut seats = self.seats.borrow_mut();
+        let index = match seats.iter().position(|s| s.name == data.name) {
+            Some(index) => index,
+            None => {
+                seats.push(Seat {
+                    name: data.name.clone(),
+                    keyboard: None,
+                    text_input: None,
+                    text_input_entered: false,
+                    pointer: None,
+                    relative_pointer: None,
+                    constraint: None,
+                    enter_serial: None,
+                });
+                seats.len() - 1
+            }
+        };
+
+        let entry = &mut seats[index];
+
+        if data.has_keyboard && !data.defunct {
+            if entry.keyboard.is_none() {
+                entry.keyboard = self.map_keyboard(seat);
+                entry.text_input = self.map_text_input(seat, &data.name);
+            }
+        } else {
+            entry.release_keyboard();
+        }
+
+        if data.has_pointer && !data.defunct {
+            if entry.pointer.is_none() {
+                let pointer = self.map_pointer(seat, &data.name);
+                entry.relative_pointer = self.map_relative_pointer(&pointer);
+                entry.constraint = self.constrain_pointer(&pointer);
+                entry.pointer = Some(pointer);
+            }
+        } else {
+            entry.release_pointer();
+        }
+    }
+
+    fn map_keyboard(
+        self: &Rc<Self>,
+        seat: &Attached<wl_seat::WlSeat>,
+    ) -> Option<wl_keyboard::WlKeyboard> {
+        let shared = Rc::downgrade(self);
+
+        // Keyboards are shared by all surfaces of the client, so only keys pressed while focused are ours.
+        let mut has_focus = false;
+        let mut modifiers = Modifiers::empty();
+        keyboard::map_keyboard(seat, None, move |event, _, _| {
+            let shared = match shared.upgrade() {
+                Some(shared) => shared,
+                None => return,
+            };
+
+            match event {
+                keyboard::Event::Enter {
+                    surface: entered, ..
+                } => has_focus = entered == shared.surface,
+                keyboard::Event::Leave { .. } => {
+                    has_focus = false;
+                    *shared.key_repeat.borrow_mut() = None;
+                }
+                keyboard::Event::Modifiers { modifiers: state } => {
+                    modifiers = Modifiers::empty();
+                    modifiers.set(Modifiers::SHIFT, state.shift);
+                    modifiers.set(Modifiers::CONTROL, state.ctrl);
+                    modifiers.set(Modifiers::ALT, state.alt);
+                    modifiers.set(Modifiers::SUPER, state.logo);
+                    modifiers.set(Modifiers::CAPS_LOCK, state.caps_lock);
+                    modifiers.set(Modifiers::NUM_LOCK, state.num_lock);
+                }
+                keyboard::Event::Key {
+                    rawkey,
+                    keysym,
+                    state,
+                    utf8,
+                    ..
+                } if has_focus => {
+                    let scancode = ScanCode(rawkey);
+
+                    // The keysym has the modifiers applied, e.g. shift turns 1 into !, which has no key code.
+                    let keycode = keymap::keysym_to_keycode(keysym)
+                        .or_else(|| keymap::evdev_to_keycode(rawkey));
+
+                    let state = match state {
+                        keyboard::KeyState::Pressed => {
+                            *shared.key_repeat.borrow_mut() = Some(KeyRepeat {
+                                scancode,
+                                keycode,
+                                modifiers,
+                                text: utf8.clone(),
+                                next: Instant::now() + KEY_REPEAT_DELAY,
+                            });
+                            ElementState::Pressed
+                        }
+                        _ => {
+                            let mut key_repeat = shared.key_repeat.borrow_mut();
+                            if matches!(&*key_repeat, Some(repeat) if repeat.scancode == scancode) {
+                                *key_repeat = None;
+                            }
+                            ElementState::Released
+                        }
+                    };
+
+                    shared.push_event(PendingEvent::Input(Event::KeyboardInput {
+                        scancode,
+                        keycode,
+                        state,
+                        modifiers,
+                        repeat: false,
+                    }));
+                    shared.push_text(utf8.as_deref());
+                }
+                _ => {}
+            }
+        })
+        .ok()
+    }
+
+    /// Queues the characters of the text a key produced, if text input is enabled.
+    fn push_text(&self, text: Option<&str>) {
+        if !self.text_input.get() {
+            return;
+        }
+
+        for c in text.unwrap_or_default().chars().filter(|c| !c.is_control()) {
+            self.push_event(PendingEvent::Input(Event::ReceivedCharacter(c)));
+        }
+    }
+
+    /// Creates the text input of a seat, through which input methods deliver composed text.
+    fn map_text_input(
+        self: &Rc<Self>,
+        seat: &Attached<wl_seat::WlSeat>,
+        name: &str,
+    ) -> Option<Main<zwp_text_input_v3::ZwpTextInputV3>> {
+        let manager = self.text_input_manager.as_ref()?;
+        let shared = Rc::downgrade(self);
+        let name = name.to_owned();
+
+        // Changes are double-buffered until the compositor sends done.
+        let mut pending_preedit = None;
+        let mut pending_commit = None;
+        let mut preedit = (String::new(), None);
+
+        let text_input = manager.get_text_input(seat);
+        text_input.quick_assign(move |text_input, event, _| {
+            let shared = match shared.upgrade() {
+                Some(shared) => shared,
+                None => return,
+            };
+
+            match event {
+                zwp_text_input_v3::Event::Enter { surface } if surface == shared.surface => {
+                    shared.set_text_input_entered(&name, true);
+                    if shared.text_input.get() {
+                        shared.enable_text_input(&text_input);
+                    }
+                }
+                zwp_text_input_v3::Event::Leave { surface } if surface == shared.surface => {
+                    shared.set_text_input_entered(&name, false);
+
+                    // The composition is abandoned when the focus leaves.
+                    if !preedit.0.is_empty() {
+                        preedit = (String::new(), None);
+                        shared.push_ime(Ime::Preedit {
+                            text: String::new(),
+                            cursor: None,
+                        });
+                    }
+                }
+                zwp_text_input_v3::Event::PreeditString {
+                    text, cursor_begin, ..
+                } => {
+                    // A cursor of -1 means hidden.
+                    let cursor = usize::try_from(cursor_begin).ok();
+                    pending_preedit = Some((text.unwrap_or_default(), cursor));
+                }
+                zwp_text_input_v3::Event::CommitString { text } => pending_commit = text,
+                zwp_text_input_v3::Event::Done { .. } => {
+                    let new_preedit = pending_preedit.take().unwrap_or((String::new(), None));
+
+                    if let Some(text) = pending_commit.take() {
+                        shared.push_ime(Ime::Commit(text));
+                    }
+
+                    if new_preedit != preedit {
+                        preedit = new_preedit;
+                        shared.push_ime(Ime::Preedit {
+                            text: preedit.0.clone(),
+                            cursor: preedit.1,
+                        });
+                    }
+                }
+                _ => {}
+            }
+        });
+
+        Some(text_input)
+    }
+
+    fn push_ime(&self, ime: Ime) {
+        if self.text_input.get() {
+            self.push_event(PendingEvent::Input(Event::Ime(ime)));
+        }
+    }
+
+    fn set_text_input_entered(&self, name: &str, entered: bool) {
+        if let Some(seat) = self.seats.borrow_mut().iter_mut().find(|s| s.name == name) {
+            seat.text_input_entered = entered;
+        }
+    }
+
+    /// Enables a text input and tells the input method where to put its candidate window.
+    fn enable_text_input(&self, text_input: &zwp_text_input_v3::ZwpTextInputV3) {
+        let (x, y) = self.ime_position();
+        text_input.enable();
+        text_input.set_cursor_rectangle(x, y, 0, 0);
+        text_input.commit();
+    }
+
+    /// Returns the position for the candidate window in surface coordinates.
+    fn ime_position(&self) -> (i32, i32) {
+        let (x, y) = self.ime_position.get();
+        let scale = self.scale_factor.get();
+        (x / scale, y / scale)
+    }
+
+    fn map_pointer(self: &Rc<Self>, seat: &Attached<wl_seat::WlSeat>, name: &str) -> ThemedPointer {
+        let shared = Rc::downgrade(self);
+        let name = name.to_owned();
+
+        // Pointer events for the frame of the client-side decorations are ignored.
+        let mut has_focus = false;
+        self.theme_manager
+            .theme_pointer_with_impl(seat, move |event, pointer, _| {
+                let shared = match shared.upgrade() {
+                    Some(shared) => shared,
+                    None => return,
+                };
+
+                let event = match event {
+                    wl_pointer::Event::Enter {
+                        serial,
+                        surface: entered,
+                        surface_x,
+                        surface_y,
+                    } if entered == shared.surface => {
+                        has_focus = true;
+                        shared.set_enter_serial(&name, Some(serial));
+                        shared.update_cursor(&pointer, serial);
+                        shared.push_event(PendingEvent::Input(Event::CursorEntered));
+
+                        let scale = shared.scale_factor.get() as f64;
+                        Event::CursorMoved {
+                            x: surface_x * scale,
+                            y: surface_y * scale,
+                        }
+                    }
+                    wl_pointer::Event::Leave { .. } if has_focus => {
+                        has_focus = false;
+                        shared.set_enter_serial(&name, None);
+                        Event::CursorLeft
+                    }
+                    wl_pointer::Event::Motion {
+                        surface_x,
+                        surface_y,
+                        ..
+                    } if has_focus => {
+                        let scale = shared.scale_factor.get() as f64;
+                        Event::CursorMoved {
+                            x: surface_x * scale,
+                            y: surface_y * scale,
+                        }
+                    }
+                    wl_pointer::Event::Button { button, state, .. } if has_focus => {
+                        // Buttons are reported as evdev codes, see linux/input-event-codes.h.
+                        const BTN_LEFT: u32 = 0x110;
+                        const BTN_RIGHT: u32 = 0x111;
+                        const BTN_MIDDLE: u32 = 0x112;
+                        const BTN_SIDE: u32 = 0x113;
+                        const BTN_EXTRA: u32 = 0x114;
+
+                        Event::MouseInput {
+                            button: match button {
+                                BTN_LEFT => MouseButton::Left,
+                                BTN_RIGHT => MouseButton::Right,
+                                BTN_MIDDLE => MouseButton::Middle,
+                                BTN_SIDE => MouseButton::Back,
+                                BTN_EXTRA => MouseButton::Forward,
+                                other => MouseButton::Other(other as u16),
+                            },
+                            state: match state {
+                                wl_pointer::ButtonState::Pressed => ElementState::Pressed,
+                                _ => ElementState::Released,
+                            },
+                        }
+                    }
+                    wl_pointer::Event::Axis { axis, value, .. } if has_focus => {
+                        // Compositors scroll 10 surface units per notch, and positive values scroll down.
+                        let delta = (value / 10.0) as f32;
+                        match axis {
+                            wl_pointer::Axis::HorizontalScroll => Event::MouseWheel {
+                                delta_x: delta,
+                                delta_y: 0.0,
+                            },
+                            _ => Event::MouseWheel {
+                                delta_x: 0.0,
+                                delta_y: -delta,
+                            },
+                        }
+                    }
+                    _ => return,
+                };
+
+                shared.push_event(PendingEvent::Input(event));
+            })
+    }
+
+    fn map_relative_pointer(
+        self: &Rc<Self>,
+        pointer: &wl_pointer::WlPointer,
+    ) -> Option<Main<zwp_relative_pointer_v1::ZwpRelativePointerV1>> {
+        let manager = self.relative_pointer_manager.as_ref()?;
+        let shared = Rc::downgrade(self);
+
+        let relative_pointer = manager.get_relative_pointer(pointer);
+        relative_pointer.quick_assign(move |_, event, _| {
+            if let (
+                Some(shared),
+                zwp_relative_pointer_v1::Event::RelativeMotion {
+                    dx_unaccel,
+                    dy_unaccel,
+                    ..
+                },
+            ) = (shared.upgrade(), event)
+            {
+                shared.push_event(PendingEvent::Input(Event::MouseMotion {
+                    delta_x: dx_unaccel,
+                    delta_y: dy_unaccel,
+                }));
+            }
+        });
+
+        Some(relative_pointer)
+    }
+
+    /// Creates the constraint for the current grab mode.
+    ///
+    /// Persistent constraints are deactivated by the compositor while the surface is unfocused
+    /// and reactivated once it regains focus.
+    fn constrain_pointer(&self, pointer: &wl_pointer::WlPointer) -> Option<PointerConstraint> {
+        let constraints = self.pointer_constraints.as_ref()?;
+        let lifetime = zwp_pointer_constraints_v1::Lifetime::Persistent;
+
+        let constraint = match self.cursor_grab.get() {
+            CursorGrabMode::None => return None,
+            CursorGrabMode::Confined => {
+                let confined = constraints.confine_pointer(&self.surface, pointer, None, lifetime);
+                confined.quick_assign(|_, _, _| {});
+                PointerConstraint::Confined(confined)
+            }
+            CursorGrabMode::Locked => {
+                let locked = constraints.lock_pointer(&self.surface, pointer, None, lifetime);
+                locked.quick_assign(|_, _, _| {});
+                PointerConstraint::Locked(locked)
+            }
+        };
+
+        Some(constraint)
+    }
+
+    fn set_enter_serial(&self, name: &str, serial: Option<u32>) {
+        if let Some(seat) = self.seats.borrow_mut().iter_mut().find(|s| s.name == name) {
+            seat.enter_serial = serial;
+        }
+    }
+
+    /// Shows the cursor of the application or hides it, depending on the visibility of the cursor.
+    fn update_cursor(&self, pointer: &ThemedPointer, serial: u32) {
+        match (self.cursor_visible.get(), &*self.cursor.borrow()) {
+            (true, CursorImage::Icon(icon)) => {
+                // Not every theme has every cursor, the arrow is used for missing ones.
+                let found = super::cursor_names(*icon)
+                    .iter()
+                    .any(|name| pointer.set_cursor(name, Some(serial)).is_ok());
+                if !found {
+                    let _ = pointer.set_cursor(DEFAULT_CURSOR, Some(serial));
+                }
+            }
+            (true, CursorImage::Custom { surface, hotspot }) => {
+                let pointer: &wl_pointer::WlPointer = pointer;
+                pointer.set_cursor(serial, Some(surface), hotspot.0, hotspot.1);
+            }
+            (false, _) => {
+                let pointer: &wl_pointer::WlPointer = pointer;
+                pointer.set_cursor(serial, None, 0, 0);
+            }
+        }
+    }
+
+    /// Applies the cursor to the pointers over the surface.
+    fn update_cursors(&self) {
+        for seat in self.seats.borrow().iter() {
+            if let (Some(pointer), Some(serial)) = (&seat.pointer, seat.enter_serial) {
+                self.update_cursor(pointer, serial);
+            }
+        }
+    }
+}
+
+/// Returns whether the compositor named by `WAYLAND_DISPLAY` can be connected to.
+pub(super) fn is_available() -> bool {
+    Display::connect_to_env().is_ok()
+}
+
+pub struct Window {
+    display: Display,
+    queue: RefCell<EventQueue>,
+
+    /// The toplevel, `None` until the window is shown. Surfaces without a role are never mapped,
+    /// so the buffers presented to a hidden window aren't shown.
+    window: RefCell<Option<window::Window<FallbackFrame>>>,
+    shared: Rc<Shared>,
+
+    /// The size in surface coordinates, i.e. logical pixels.
+    size: Cell<(u32, u32)>,
+    min_size: Cell<Option<Size>>,
+    max_size: Cell<Option<Size>>,
+    focused: Cell<bool>,
+
+    /// Whether the events have been polled before, the first poll reports the initial size.
+    polled: Cell<bool>,
+
+    // The properties of the toplevel, which are applied once it is created.
+    title: RefCell<String>,
+    resizable: Cell<bool>,
+    decorations: Cell<bool>,
+    fullscreen: RefCell<Option<Fullscreen>>,
+
+    // The environment owns the globals used by the window and must outlive it.
+    _seat_listener: SeatListener,
+    env: environment::Environment<Environment>,
+}
+
+impl Window {
+    pub fn new(desc: &WindowDesc) -> Result<Self, WindowError> {
+        let (mut env, display, queue) = match new_default_environment!(Environment, desktop) {
+            Ok(connection) => connection,
+            _ => return Err(WindowError::ConnectionFailed),
+        };
+
+        // The scale is only known once the surface is shown on an output.
+        let size = Self::to_surface_size(desc.size, 1);
+        let surface = env.create_surface().detach();
+
+        let theme_manager = ThemeManager::init(
+            ThemeSpec::System,
+            env.require_global(),
+            env.require_global(),
+        );
+
+        let shared = Rc::new(Shared {
+            display: display.clone(),
+            surface,
+            pending_events: RefCell::new(VecDeque::new()),
+            key_repeat: RefCell::new(None),
+            seats: RefCell::new(Vec::new()),
+            theme_manager,
+            relative_pointer_manager: env.manager.instantiate_range(1, 1).ok(),
+            pointer_constraints: env.manager.instantiate_range(1, 1).ok(),
+            text_input_manager: env.manager.instantiate_range(1, 1).ok(),
+            cursor_grab: Cell::new(CursorGrabMode::None),
+            cursor_visible: Cell::new(true),
+            cursor: RefCell::new(CursorImage::Icon(CursorIcon::default())),
+            text_input: Cell::new(false),
+            ime_position: Cell::new((0, 0)),
+            scale_factor: Cell::new(1),
+            drag_and_drop: Cell::new(desc.drag_and_drop),
+            drag: RefCell::new(None),
+        });
+
+        // Input devices are owned by seats, which may come and go while the window is alive.
+        for seat in env.get_all_seats() {
+            if let Some(data) = seat::with_seat_data(&seat, Clone::clone) {
+                shared.update_seat(&seat, &data);
+            }
+        }
+
+        let seat_listener = {
+            let shared = Rc::downgrade(&shared);
+            env.listen_for_seats(move |seat, data, _| {
+                if let Some(shared) = shared.upgrade() {
+                    shared.update_seat(&seat, data);
+                }
+            })
+        };
+
+        // Compositors without a data device manager don't support drag and drop.
+        {
+            let shared = Rc::downgrade(&shared);
+            let _ = env.set_data_device_callback(move |_, event, _| {
+                if let Some(shared) = shared.upgrade() {
+                    shared.handle_dnd(event);
+                }
+            });
+        }
+
+        let window = Self {
+            display,
+            queue: RefCell::new(queue),
+            window: RefCell::new(None),
+            shared,
+            size: Cell::new(size),
+            min_size: Cell::new(desc.min_size),
+            max_size: Cell::new(desc.max_size),
+            focused: Cell::new(false),
+            polled: Cell::new(false),
+            title: RefCell::new(desc.title.to_owned()),
+            resizable: Cell::new(desc.resizable),
+            decorations: Cell::new(desc.decorations),
+            fullscreen: RefCell::new(None),
+            _seat_listener: seat_listener,
+            env,
+        };
+
+        if desc.visible {
+            window.create_toplevel()?;
+        }
+
+        // Wait for the compositor to process the toplevel, so the first configure is known before returning.
+        window.roundtrip()?;
+        Ok(window)
+    }
+
+    /// Gives the surface the role of a toplevel, after which the compositor maps it once a buffer is attached.
+    fn create_toplevel(&self) -> Result<(), WindowError> {
+        let mut window = {
+            let shared = Rc::downgrade(&self.shared);
+            let surface = self.shared.surface.clone();
+            match self.env.create_window::<FallbackFrame, _>(
+                surface,
+                None,
+                self.size.get(),
+                move |event, _| {
+                    if let Some(shared) = shared.upgrade() {
+                        shared.push_event(PendingEvent::Window(event));
+                    }
+                },
+            ) {
+                Ok(window) => window,
+                _ => return Err(WindowError::CreationFailed),
+            }
+        };
+
+        window.set_title(self.title.borrow().clone());
+        window.set_resizable(self.resizable.get());
+        window.set_decorate(Self::decorate(self.decorations.get()));
+        *self.window.borrow_mut() = Some(window);
+
+        self.update_size_limits();
+        let fullscreen = self.fullscreen.borrow().clone();
+        if fullscreen.is_some() {
+            self.set_fullscreen(fullscreen);
+        }
+        Ok(())
+    }
+
+    /// Blocks until the compositor processed all requests, and queues the events it sent in response.
+    fn roundtrip(&self) -> Result<(), WindowError> {
+        let mut queue = self.queue.borrow_mut();
+        match queue.sync_roundtrip(&mut (), |_, _, _| {}) {
+            Ok(_) => Ok(()),
+            Err(_) => Err(WindowError::ConnectionFailed),
+        }
+    }
+
+    fn decorate(decorations: bool) -> Decorations {
+        match decorations {
+            true => Decorations::FollowServer,
+            false => Decorations::None,
+        }
+    }
+
+    pub fn display(&self) -> &Display {
+        &self.display
+    }
+
+    pub fn surface(&self) -> &wl_surface::WlSurface {
+        &self.shared.surface
+    }
+
+    /// Creates a surface showing the image of a cursor, returns `None` if shared memory isn't available.
+    ///
+    /// The image is attached with a buffer scale of 1, since its size is chosen by the application.
+    fn create_cursor_surface(&self, cursor: &CustomCursor) -> Option<wl_surface::WlSurface> {
+        let image = cursor.image();
+        let (width, height) = (image.width as i32, image.height as i32);
+
+        // The pool can be dropped right away, buffers keep the memory they were created from.
+        let mut pool = self.env.create_simple_pool(|_| {}).ok()?;
+        pool.resize(image.rgba.len()).ok()?;
+        for (bytes, argb) in pool
+            .mmap()
+            .chunks_exact_mut(4)
+            .zip(cursor.premultiplied_argb())
+        {
+            bytes.copy_from_slice(&argb.to_le_bytes());
+        }
+        let buffer = pool.buffer(0, width, height, width * 4, wl_shm::Format::Argb8888);
+
+        let surface = self.env.create_surface().detach();
+        surface.attach(Some(&buffer), 0, 0);
+        surface.damage(0, 0, width, height);
+        surface.commit();
+        Some(surface)
+    }
+
+    /// Converts a size to surface coordinates.
+    fn to_surface_size(size: Size, scale_factor: i32) -> (u32, u32) {
+        let size = size.to_logical(scale_factor as f64);
+        (size.width.round() as u32, size.height.round() as u32)
+    }
+
+    /// Applies the size limits with the current scale factor, which physical sizes depend on.
+    fn update_size_limits(&self) {
+        let scale_factor = self.shared.scale_factor.get();
+        let mut window = self.window.borrow_mut();
+        let window = match window.as_mut() {
+            Some(window) => window,
+            None => return,
+        };
+        window.set_min_size(
+            self.min_size
+                .get()
+                .map(|size| Self::to_surface_size(size, scale_factor)),
+        );
+        window.set_max_size(
+            self.max_size
+                .get()
+                .map(|size| Self::to_surface_size(size, scale_factor)),
+        );
+    }
+
+    /// Checks whether the scale of the outputs the surface is on changed, and if so renders at the new scale.
+    fn update_scale_factor(&self, callback: &mut dyn FnMut(Event)) {
+        let scale_factor = smithay_client_toolkit::get_surface_scale_factor(&self.shared.surface);
+        if scale_factor == self.shared.scale_factor.get() {
+            return;
+        }
+
+        self.shared.scale_factor.set(scale_factor);
+        self.shared.surface.set_buffer_scale(scale_factor);
+        self.update_size_limits();
+
+        // The surface keeps its size, so its size in physical pixels changes.
+        callback(Event::ScaleFactorChanged {
+            scale_factor: scale_factor as f64,
+        });
+        let PhysicalSize { width, height } = self.inner_size();
+        callback(Event::Resized { width, height });
+    }
+
+    /// Queues the repeated presses that are due for the held key.
+    fn repeat_key(&self) {
+        let now = Instant::now();
+        let mut key_repeat = self.shared.key_repeat.borrow_mut();
+        let repeat = match key_repeat.as_mut() {
+            Some(repeat) => repeat,
+            None => return,
+        };
+
+        while repeat.next <= now {
+            repeat.next += KEY_REPEAT_INTERVAL;
+            self.shared
+                .push_event(PendingEvent::Input(Event::KeyboardInput {
+                    scancode: repeat.scancode,
+                    keycode: repeat.keycode,
+                    state: ElementState::Pressed,
+                    modifiers: repeat.modifiers,
+                    repeat: true,
+                }));
+            self.shared.push_text(repeat.text.as_deref());
+        }
+    }
+
+    /// Describes an output, or returns `None` if it has been removed.
+    fn monitor(output: &wl_output::WlOutput) -> Option<Monitor> {
+        output::with_output_info(output, |info: &OutputInfo| {
+            if info.obsolete {
+                return None;
+            }
+
+            // Compositors don't tell clients about the pixel format of outputs.
+            let video_mode = |mode: &output::Mode| VideoMode {
+                size: (mode.dimensions.0 as u32, mode.dimensions.1 as u32),
+                bit_depth: 24,
+                refresh_rate_millihertz: mode.refresh_rate as u32,
+            };
+
+            let mut video_modes: Vec<_> = info.modes.iter().map(video_mode).collect();
+            sort_video_modes(&mut video_modes);
+            let video_mode = info
+                .modes
+                .iter()
+                .find(|mode| mode.is_current)
+                .map(video_mode)?;
+
+            // Modes are given in the orientation of the panel, the transform may rotate it.
+            let size = match info.transform {
+                wl_output::Transform::_90
+                | wl_output::Transform::_270
+                | wl_output::Transform::Flipped90
+                | wl_output::Transform::Flipped270 => (video_mode.size.1, video_mode.size.0),
+                _ => video_mode.size,
+            };
+
+            // The connector name is only known with xdg-output, the model is the next best thing.
+            let name = match info.name.is_empty() {
+                true => info.model.clone(),
+                false => info.name.clone(),
+            };
+
+            Some(Monitor {
+                name,
+                position: info.location,
+                size,
+                physical_size: (
+                    info.physical_size.0.max(0) as u32,
+                    info.physical_size.1.max(0) as u32,
+                ),
+                scale_factor: info.scale_factor as f64,
+                video_mode,
+                video_modes,
+                handle: info.id as u64,
+            })
+        })
+        .flatten()
+    }
+
+    fn handle_window_event(&self, event: window::Event, callback: &mut dyn FnMut(Event)) {
+        match event {
+            window::Event::Configure { new_size, states } => {
+                // The configure has already been acknowledged by the xdg surface at this point,
+                // all that is left is to resize the frame to match and redraw it.
+                if let Some(window) = self.window.borrow_mut().as_mut() {
+                    if let Some((width, height)) = new_size {
+                        window.resize(width, height);
+                    }
+                    window.refresh();
+                }
+
+                if let Some(size) = new_size {
+                    if size != self.size.get() {
+                        self.size.set(size);
+                        let PhysicalSize { width, height } = self.inner_size();
+                        callback(Event::Resized { width, height });
+                    }
+                }
+
+                let focused = states.contains(&xdg_toplevel::State::Activated);
+                if focused != self.focused.get() {
+                    self.focused.set(focused);
+                    callback(Event::Focused(focused));
+                }
+            }
+            window::Event::Refresh => {
+                if let Some(window) = self.window.borrow_mut().as_mut() {
+                    window.refresh();
+                }
+            }
+            window::Event::Close => callback(Event::CloseRequested),
+        }
+    }
+}
+
+impl Drop for Window {
+    fn drop(&mut self) {
+        // The toolkit window destroys the surface when it is dropped, without it the surface is destroyed here.
+        // The objects created for the seats are released here so none of them outlive it.
+        if self.window.get_mut().is_none() {
+            self.shared.surface.destroy();
+        }
+        for seat in self.shared.seats.borrow_mut().iter_mut() {
+            seat.release_keyboard();
+            seat.release_pointer();
+        }
+        self.shared
+            .cursor
+            .replace(CursorImage::Icon(CursorIcon::default()))
+            .destroy();
+        let _ = self.display.flush();
+    }
+}
+
+impl HasWindowHandle for Window {
+    fn window_handle(&self) -> Result<WindowHandle<'_>, HandleError> {
+        let surface = self.shared.surface.as_ref().c_ptr() as *mut c_void;
+        let handle =
+            WaylandWindowHandle::new(NonNull::new(surface).ok_or(HandleError::Unavailable)?);
+
+        // SAFETY: The surface is valid until this object is dropped, which the borrow outlives.
+        Ok(unsafe { WindowHandle::borrow_raw(handle.into()) })
+    }
+}
+
+impl HasDisplayHandle for Window {
+    fn display_handle(&self) -> Result<DisplayHandle<'_>, HandleError> {
+        let display = self.display.get_display_ptr() as *mut c_void;
+        let handle =
+            WaylandDisplayHandle::new(NonNull::new(display).ok_or(HandleError::Unavailable)?);
+
+        // SAFETY: The connection is kept open by this object, which the borrow outlives.
+        Ok(unsafe { DisplayHandle::borrow_raw(handle.into()) })
+    }
+}
+
+impl WindowApi for Window {
+    fn show(&self) {
+        if self.window.borrow().is_none()
+            && self
+                .create_toplevel()
+                .and_then(|_| self.roundtrip())
+                .is_err()
+        {
+            return;
+        }
+
+        // A wayland surface is only mapped once a buffer has been attached to it,
+        // which happens the first time the swapchain presents.
+        if let Some(window) = self.window.borrow_mut().as_mut() {
+            window.refresh();
+        }
+        self.shared.surface.commit();
+        let _ = self.display.flush();
+    }
+
+    fn poll_events(&self, callback: &mut dyn FnMut(Event)) {
+        {
+            let mut queue = self.queue.borrow_mut();
+            let _ = self.display.flush();
+
+            // The socket is read without blocking, so this returns immediately if nothing is available.
+            if let Some(guard) = queue.prepare_read() {
+                let _ = guard.read_events();
+            }
+
+            let _ = queue.dispatch_pending(&mut (), |_, _, _| {});
+        }
+
+        self.shared.read_drag(Duration::ZERO);
+
+        // Configures only report sizes the compositor chose, so the initial size is reported here.
+        if !self.polled.replace(true) {
+            let PhysicalSize { width, height } = self.inner_size();
+            callback(Event::Resized { width, height });
+        }
+
+        self.repeat_key();
+        self.update_scale_factor(callback);
+
+        let events = std::mem::take(&mut *self.shared.pending_events.borrow_mut());
+        for event in events {
+            match event {
+                PendingEvent::Window(event) => self.handle_window_event(event, callback),
+
+                // Relative motion is reported to whichever surface has the pointer, focused or not.
+                PendingEvent::Input(Event::MouseMotion { .. }) if !self.focused.get() => {}
+                PendingEvent::Input(event) => callback(event),
+            }
+        }
+    }
+
+    fn set_title(&self, title: &str) {
+        *self.title.borrow_mut() = title.to_owned();
+        if let Some(window) = self.window.borrow().as_ref() {
+            window.set_title(title.to_owned());
+        }
+    }
+
+    fn scale_factor(&self) -> f64 {
+        self.shared.scale_factor.get() as f64
+    }
+
+    fn inner_size(&self) -> PhysicalSize {
+        let (width, height) = self.size.get();
+        let scale_factor = self.shared.scale_factor.get() as u32;
+        PhysicalSize::new(width * scale_factor, height * scale_factor)
+    }
+
+    fn set_inner_size(&self, size: Size) {
+        let (width, height) = Self::to_surface_size(size, self.shared.scale_factor.get());
+        if let Some(window) = self.window.borrow_mut().as_mut() {
+            window.resize(width, height);
+        }
+        self.size.set((width, height));
+    }
+
+    fn set_position(&self, _x: i32, _y: i32) {}
+
+    fn set_min_inner_size(&self, size: Option<Size>) {
+        self.min_size.set(size);
+        self.update_size_limits();
+    }
+
+    fn set_max_inner_size(&self, size: Option<Size>) {
+        self.max_size.set(size);
+        self.update_size_limits();
+    }
+
+    fn set_resizable(&self, resizable: bool) {
+        self.resizable.set(resizable);
+        if let Some(window) = self.window.borrow().as_ref() {
+            window.set_resizable(resizable);
+        }
+    }
+
+    fn set_decorations(&self, decorations: bool) {
+        self.decorations.set(decorations);
+        if let Some(window) = self.window.borrow_mut().as_mut() {
+            window.set_decorate(Self::decorate(decorations));
+        }
+    }
+
+    fn set_always_on_top(&self, _always_on_top: bool) {}
+
+    fn set_visible(&self, visible: bool) {
+        // Toplevels can't be hidden on Wayland, the closest equivalent is minimizing them.
+        if visible {
+            self.show();
+        } else if let Some(window) = self.window.borrow().as_ref() {
+            window.set_minimized();
+        }
+    }
+
+    fn set_cursor_grab(&self, mode: CursorGrabMode) {
+        self.shared.cursor_grab.set(mode);
+
+        for seat in self.shared.seats.borrow_mut().iter_mut() {
+            if let Some(constraint) = seat.constraint.take() {
+                constraint.destroy();
+            }
+            if let Some(pointer) = &seat.pointer {
+                seat.constraint = self.shared.constrain_pointer(pointer);
+            }
+        }
+
+        let _ = self.display.flush();
+    }
+
+    fn set_cursor_visible(&self, visible: bool) {
+        self.shared.cursor_visible.set(visible);
+        self.shared.update_cursors();
+        let _ = self.display.flush();
+    }
+
+    fn set_cursor(&self, cursor: &Cursor) {
+        let image = match cursor {
+            Cursor::Icon(icon) => CursorImage::Icon(*icon),
+            Cursor::Custom(custom) => match self.create_cursor_surface(custom) {
+                Some(surface) => {
+                    let (x, y) = custom.hotspot();
+                    CursorImage::Custom {
+                        surface,
+                        hotspot: (x as i32, y as i32),
+                    }
+                }
+                None => CursorImage::Icon(CursorIcon::default()),
+            },
+        };
+
+        // The previous surface is only destroyed once the pointers stopped using it.
+        let previous = self.shared.cursor.replace(image);
+        self.shared.update_cursors();
+        previous.destroy();
+        let _ = self.display.flush();
+    }
+
+    fn set_icon(&self, _icon: Option<&Icon>) {}
+
+    fn monitors(&self) -> Vec<Monitor> {
+        self.env
+            .get_all_outputs()
+            .iter()
+            .filter_map(Self::monitor)
+            .collect()
+    }
+
+    fn current_monitor(&self) -> Option<Monitor> {
+        // The compositor reports which outputs the surface is shown on, it may be on none before it is mapped.
+        smithay_client_toolkit::get_surface_outputs(&self.shared.surface)
+            .iter()
+            .find_map(Self::monitor)
+            .or_else(|| self.monitors().into_iter().next())
+    }
+
+    fn set_fullscreen(&self, fullscreen: Option<Fullscreen>) {
+        *self.fullscreen.borrow_mut() = fullscreen.clone();
+        let window = self.window.borrow();
+        let window = match window.as_ref() {
+            Some(window) => window,
+            None => return,
+        };
+
+        let handle = match fullscreen {
+            Some(Fullscreen::Borderless(monitor)) => Some(monitor.map(|monitor| monitor.handle)),
+            Some(Fullscreen::Exclusive(monitor, _)) => Some(Some(monitor.handle)),
+            None => None,
+        };
+
+        match handle {
+            Some(handle) => {
+                let output = handle.and_then(|handle| {
+                    self.env.get_all_outputs().into_iter().find(|output| {
+                        output::with_output_info(output, |info| info.id as u64) == Some(handle)
+                    })
+                });
+
+                // Without an output the compositor picks one, usually the one the window is on.
+                window.set_fullscreen(output.as_ref());
+            }
+            None => window.unset_fullscreen(),
+        }
+        let _ = self.display.flush();
+    }
+
+    fn set_text_input(&self, enabled: bool) {
+        self.shared.text_input.set(enabled);
+
+        // Requests are ignored by the compositor while the text input focus is elsewhere.
+        for seat in self.shared.seats.borrow().iter() {
+            if let (Some(text_input), true) = (&seat.text_input, seat.text_input_entered) {
+                match enabled {
+                    true => self.shared.enable_text_input(text_input),
+                    false => {
+                        text_input.disable();
+                        text_input.commit();
+                    }
+                }
+            }
+        }
+
+        let _ = self.display.flush();
+    }
+
+    fn set_ime_position(&self, x: i32, y: i32) {
+        self.shared.ime_position.set((x, y));
+        let (x, y) = self.shared.ime_position();
+
+        if self.shared.text_input.get() {
+            for seat in self.shared.seats.borrow().iter() {
+                if let (Some(text_input), true) = (&seat.text_input, seat.text_input_entered) {
+                    text_input.set_cursor_rectangle(x, y, 0, 0);
+                    text_input.commit();
+                }
+            }
+        }
+
+        let _ = self.display.flush();
+    }
+
+    fn set_drag_and_drop(&self, enabled: bool) {
+        self.shared.drag_and_drop.set(enabled);
+    }
+}
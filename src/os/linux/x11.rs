@@ -0,0 +1,1209 @@
+use std::{
+    cell::{Cell, RefCell},
+    collections::HashSet,
+    ffi::{CStr, CString},
+    mem::MaybeUninit,
+    os::raw::{c_char, c_int, c_long, c_uchar, c_ulong, c_void},
+    ptr::{self, NonNull},
+    slice,
+};
+
+use raw_window_handle::{
+    DisplayHandle, HandleError, HasDisplayHandle, HasWindowHandle, WindowHandle, XlibDisplayHandle,
+    XlibWindowHandle,
+};
+use x11_dl::{xcursor, xinput2, xlib};
+
+use super::{keymap, randr::Randr, xdnd::Xdnd, xim::Xim};
+use crate::os::{
+    Cursor, CursorGrabMode, CursorIcon, ElementState, Event, Fullscreen, Icon, Ime, Modifiers,
+    Monitor, MouseButton, PhysicalSize, ScanCode, Size, WindowApi, WindowDesc, WindowError,
+};
+
+/// Atoms interned once per window, so they don't have to be looked up on every use.
+struct Atoms {
+    wm_delete_window: xlib::Atom,
+    net_wm_name: xlib::Atom,
+    utf8_string: xlib::Atom,
+    motif_wm_hints: xlib::Atom,
+    net_wm_state: xlib::Atom,
+    net_wm_state_above: xlib::Atom,
+    net_wm_state_hidden: xlib::Atom,
+    net_wm_state_fullscreen: xlib::Atom,
+    net_wm_icon: xlib::Atom,
+}
+
+impl Atoms {
+    unsafe fn new(xlib: &xlib::Xlib, display: *mut xlib::Display) -> Self {
+        let intern = |name: &str| {
+            let name = CString::new(name).unwrap();
+            (xlib.XInternAtom)(display, name.as_ptr(), xlib::False)
+        };
+
+        Self {
+            wm_delete_window: intern("WM_DELETE_WINDOW"),
+            net_wm_name: intern("_NET_WM_NAME"),
+            utf8_string: intern("UTF8_STRING"),
+            motif_wm_hints: intern("_MOTIF_WM_HINTS"),
+            net_wm_state: intern("_NET_WM_STATE"),
+            net_wm_state_above: intern("_NET_WM_STATE_ABOVE"),
+            net_wm_state_hidden: intern("_NET_WM_STATE_HIDDEN"),
+            net_wm_state_fullscreen: intern("_NET_WM_STATE_FULLSCREEN"),
+            net_wm_icon: intern("_NET_WM_ICON"),
+        }
+    }
+}
+
+/// The XInput2 extension, which reports raw mouse motion.
+struct XInput {
+    // libXi registers its event converters with Xlib, so it must stay loaded as long as the display is open.
+    _xinput2: Box<xinput2::XInput2>,
+
+    /// Identifies the events of the extension.
+    opcode: c_int,
+}
+
+pub struct Window {
+    xlib: Box<xlib::Xlib>,
+    xinput: Option<XInput>,
+    display: *mut xlib::Display,
+    window: xlib::Window,
+    atoms: Atoms,
+    scale_factor: f64,
+    size: Cell<(u32, u32)>,
+    position: Cell<Option<(i32, i32)>>,
+    min_size: Cell<Option<Size>>,
+    max_size: Cell<Option<Size>>,
+    resizable: Cell<bool>,
+    mapped: Cell<bool>,
+    last_position: Cell<(i32, i32)>,
+    minimized: Cell<bool>,
+
+    /// The keycodes that are held down, used to detect repeated presses.
+    keys_down: RefCell<HashSet<u32>>,
+
+    focused: Cell<bool>,
+
+    /// Whether the events have been polled before, the first poll reports the initial size.
+    polled: Cell<bool>,
+    cursor_grab: Cell<CursorGrabMode>,
+
+    /// An invisible cursor used to hide the cursor, created on first use.
+    blank_cursor: Cell<xlib::Cursor>,
+
+    /// The cursor set by the application, `0` for the default cursor of the parent window.
+    cursor: Cell<xlib::Cursor>,
+    cursor_visible: Cell<bool>,
+
+    /// Xcursor, which loads cursors from the cursor theme and creates cursors from images.
+    /// Without it only the cursor font is available.
+    xcursor: Option<Box<xcursor::Xcursor>>,
+
+    /// The input method, if one is running.
+    xim: Option<Xim>,
+    text_input: Cell<bool>,
+
+    /// The RandR extension, without which no monitors are reported.
+    randr: Option<Randr>,
+
+    /// The position to return to when leaving fullscreen, `None` while windowed.
+    windowed_position: Cell<Option<(i32, i32)>>,
+
+    /// The output whose video mode was changed for exclusive fullscreen, and its original mode.
+    saved_mode: Cell<Option<(u64, c_ulong)>>,
+    xdnd: Xdnd,
+}
+
+impl Window {
+    pub fn new(desc: &WindowDesc) -> Result<Self, WindowError> {
+        // SAFETY: Since we are loading Xlib dynamically, we assume that it is implemented correctly.
+        let xlib = match xlib::Xlib::open().map(Box::new) {
+            Ok(xlib) => xlib,
+            _ => return Err(WindowError::NotSupported),
+        };
+
+        // SAFETY: Passing null opens the display named by the DISPLAY environment variable.
+        let display = unsafe { (xlib.XOpenDisplay)(ptr::null()) };
+        if display.is_null() {
+            return Err(WindowError::ConnectionFailed);
+        }
+
+        let scale_factor = unsafe { xft_scale_factor(&xlib, display) };
+        let size = desc.size.to_physical(scale_factor).into();
+
+        let window = unsafe { Self::create_window(&xlib, display, desc, size) };
+        if window == 0 {
+            unsafe { (xlib.XCloseDisplay)(display) };
+            return Err(WindowError::CreationFailed);
+        }
+
+        let atoms = unsafe { Atoms::new(&xlib, display) };
+
+        // By default a held key generates pairs of releases and presses,
+        // with detectable auto repeat only the presses are sent, which makes repeats easy to tell apart.
+        unsafe { (xlib.XkbSetDetectableAutoRepeat)(display, xlib::True, ptr::null_mut()) };
+
+        // Without XInput2 there is no raw motion, but the window is still usable.
+        let xinput = unsafe { Self::select_raw_motion(&xlib, display) };
+
+        let xim = unsafe { Xim::new(&xlib, display, window) };
+        let randr = unsafe { Randr::new(&xlib, display) };
+        let xdnd = unsafe { Xdnd::new(&xlib, display, window) };
+
+        // Opt in to WM_DELETE_WINDOW, otherwise the window manager kills the connection when the window is closed.
+        unsafe {
+            let mut protocols = [atoms.wm_delete_window];
+            (xlib.XSetWMProtocols)(display, window, protocols.as_mut_ptr(), 1);
+        }
+
+        let window = Self {
+            xlib,
+            xinput,
+            display,
+            window,
+            atoms,
+            scale_factor,
+            size: Cell::new(size),
+            position: Cell::new(desc.position),
+            min_size: Cell::new(desc.min_size),
+            max_size: Cell::new(desc.max_size),
+            resizable: Cell::new(desc.resizable),
+            mapped: Cell::new(false),
+            last_position: Cell::new(desc.position.unwrap_or((0, 0))),
+            minimized: Cell::new(false),
+            keys_down: RefCell::new(HashSet::new()),
+            focused: Cell::new(false),
+            polled: Cell::new(false),
+            cursor_grab: Cell::new(CursorGrabMode::None),
+            blank_cursor: Cell::new(0),
+            cursor: Cell::new(0),
+            cursor_visible: Cell::new(true),
+            xcursor: xcursor::Xcursor::open().ok().map(Box::new),
+            xim,
+            text_input: Cell::new(false),
+            randr,
+            windowed_position: Cell::new(None),
+            saved_mode: Cell::new(None),
+            xdnd,
+        };
+
+        window.set_title(desc.title);
+        window.update_size_hints();
+        window.set_decorations(desc.decorations);
+        window.set_always_on_top(desc.always_on_top);
+        window.set_drag_and_drop(desc.drag_and_drop);
+        window.set_icon(desc.icon);
+        window.set_visible(desc.visible);
+
+        Ok(window)
+    }
+
+    unsafe fn create_window(
+        xlib: &xlib::Xlib,
+        display: *mut xlib::Display,
+        desc: &WindowDesc,
+        (width, height): (u32, u32),
+    ) -> xlib::Window {
+        let screen = (xlib.XDefaultScreen)(display);
+        let root = (xlib.XRootWindow)(display, screen);
+
+        let mut attributes = MaybeUninit::<xlib::XSetWindowAttributes>::zeroed().assume_init();
+        attributes.event_mask = xlib::ExposureMask
+            | xlib::StructureNotifyMask
+            | xlib::FocusChangeMask
+            | xlib::PropertyChangeMask
+            | xlib::KeyPressMask
+            | xlib::KeyReleaseMask
+            | xlib::ButtonPressMask
+            | xlib::ButtonReleaseMask
+            | xlib::PointerMotionMask
+            | xlib::EnterWindowMask
+            | xlib::LeaveWindowMask;
+        let mut mask = xlib::CWEventMask;
+
+        let mut depth = xlib::CopyFromParent;
+        let mut visual = ptr::null_mut();
+
+        // Transparency requires a 32-bit visual, so the compositor can use the alpha channel.
+        let mut visual_info = MaybeUninit::<xlib::XVisualInfo>::zeroed().assume_init();
+        if desc.transparent
+            && (xlib.XMatchVisualInfo)(display, screen, 32, xlib::TrueColor, &mut visual_info) != 0
+        {
+            depth = visual_info.depth;
+            visual = visual_info.visual;
+
+            // A window with a different visual than its parent must have its own colormap and border.
+            attributes.colormap =
+                (xlib.XCreateColormap)(display, root, visual_info.visual, xlib::AllocNone);
+            attributes.border_pixel = 0;
+            mask |= xlib::CWColormap | xlib::CWBorderPixel;
+        } else {
+            attributes.background_pixel = (xlib.XBlackPixel)(display, screen);
+            mask |= xlib::CWBackPixel;
+        }
+
+        let (x, y) = desc.position.unwrap_or((0, 0));
+        (xlib.XCreateWindow)(
+            display,
+            root,
+            x,
+            y,
+            width,
+            height,
+            0,
+            depth,
+            xlib::InputOutput as u32,
+            visual,
+            mask,
+            &mut attributes,
+        )
+    }
+
+    /// Loads XInput2 and selects raw motion events of all pointers.
+    ///
+    /// Raw events can only be selected on the root window, they are reported regardless of
+    /// which window is focused.
+    unsafe fn select_raw_motion(xlib: &xlib::Xlib, display: *mut xlib::Display) -> Option<XInput> {
+        let xinput2 = xinput2::XInput2::open().map(Box::new).ok()?;
+
+        let name = CString::new("XInputExtension").unwrap();
+        let (mut opcode, mut first_event, mut first_error) = (0, 0, 0);
+        if (xlib.XQueryExtension)(
+            display,
+            name.as_ptr(),
+            &mut opcode,
+            &mut first_event,
+            &mut first_error,
+        ) == xlib::False
+        {
+            return None;
+        }
+
+        let (mut major, mut minor) = (2, 0);
+        if (xinput2.XIQueryVersion)(display, &mut major, &mut minor) != xlib::Success as c_int {
+            return None;
+        }
+
+        let mut mask = [0; 4];
+        xinput2::XISetMask(&mut mask, xinput2::XI_RawMotion);
+        let mut event_mask = xinput2::XIEventMask {
+            deviceid: xinput2::XIAllMasterDevices,
+            mask_len: mask.len() as c_int,
+            mask: mask.as_mut_ptr(),
+        };
+        (xinput2.XISelectEvents)(
+            display,
+            (xlib.XDefaultRootWindow)(display),
+            &mut event_mask,
+            1,
+        );
+
+        Some(XInput {
+            _xinput2: xinput2,
+            opcode,
+        })
+    }
+
+    pub fn display(&self) -> &*mut xlib::Display {
+        &self.display
+    }
+
+    pub fn window(&self) -> &xlib::Window {
+        &self.window
+    }
+
+    fn handle_event(&self, event: &xlib::XEvent, callback: &mut dyn FnMut(Event)) {
+        match event.get_type() {
+            xlib::ClientMessage => {
+                let client = xlib::XClientMessageEvent::from(*event);
+                match client.data.get_long(0) as xlib::Atom == self.atoms.wm_delete_window {
+                    true => callback(Event::CloseRequested),
+                    false => self
+                        .xdnd
+                        .handle_client_message(&self.xlib, &client, callback),
+                }
+            }
+            xlib::SelectionNotify => {
+                let selection = xlib::XSelectionEvent::from(*event);
+                self.xdnd
+                    .handle_selection_notify(&self.xlib, &selection, callback);
+            }
+            xlib::ConfigureNotify => {
+                let configure = xlib::XConfigureEvent::from(*event);
+
+                let size = (configure.width as u32, configure.height as u32);
+                if size != self.size.get() {
+                    self.size.set(size);
+                    callback(Event::Resized {
+                        width: size.0,
+                        height: size.1,
+                    });
+                }
+
+                // The event position is relative to the frame of the window manager, not the screen.
+                let position = self.root_position();
+                if position != self.last_position.get() {
+                    self.last_position.set(position);
+                    callback(Event::Moved {
+                        x: position.0,
+                        y: position.1,
+                    });
+                }
+            }
+            xlib::FocusIn | xlib::FocusOut => {
+                let focus = xlib::XFocusChangeEvent::from(*event);
+                if focus.detail != xlib::NotifyInferior {
+                    let focused = focus.type_ == xlib::FocusIn;
+
+                    // Keys released while unfocused are never reported.
+                    if !focused {
+                        self.keys_down.borrow_mut().clear();
+                    }
+
+                    // The grab is released while unfocused, so the user can interact with other windows.
+                    self.focused.set(focused);
+                    self.apply_cursor_grab();
+                    self.update_ime_focus();
+
+                    callback(Event::Focused(focused));
+                }
+            }
+            xlib::PropertyNotify => {
+                let property = xlib::XPropertyEvent::from(*event);
+                if property.atom == self.atoms.net_wm_state {
+                    let minimized = self.has_net_wm_state(self.atoms.net_wm_state_hidden);
+                    if minimized != self.minimized.get() {
+                        self.minimized.set(minimized);
+                        callback(Event::Minimized(minimized));
+                    }
+                }
+            }
+            xlib::KeyPress | xlib::KeyRelease => {
+                let mut key = xlib::XKeyEvent::from(*event);
+
+                let text = match key.type_ == xlib::KeyPress && self.text_input.get() {
+                    true => self.lookup_string(&mut key),
+                    false => String::new(),
+                };
+
+                // Text committed by the input method arrives as a press without a key.
+                if key.keycode == 0 {
+                    if !text.is_empty() {
+                        callback(Event::Ime(Ime::Commit(text)));
+                    }
+                    return;
+                }
+
+                // X11 keycodes are offset by 8 from the evdev scancodes.
+                let scancode = key.keycode - 8;
+
+                // The keysym without any modifiers applied, so e.g. shift doesn't change the key code.
+                let keysym = unsafe { (self.xlib.XLookupKeysym)(&mut key, 0) } as u32;
+
+                let (state, repeat) = match key.type_ {
+                    xlib::KeyPress => (
+                        ElementState::Pressed,
+                        !self.keys_down.borrow_mut().insert(key.keycode),
+                    ),
+                    _ => {
+                        self.keys_down.borrow_mut().remove(&key.keycode);
+                        (ElementState::Released, false)
+                    }
+                };
+
+                callback(Event::KeyboardInput {
+                    scancode: ScanCode(scancode),
+                    keycode: keymap::keysym_to_keycode(keysym)
+                        .or_else(|| keymap::evdev_to_keycode(scancode)),
+                    state,
+                    modifiers: Self::modifiers(key.state),
+                    repeat,
+                });
+
+                for c in text.chars().filter(|c| !c.is_control()) {
+                    callback(Event::ReceivedCharacter(c));
+                }
+            }
+            xlib::ButtonPress | xlib::ButtonRelease => {
+                let button = xlib::XButtonEvent::from(*event);
+                let state = match button.type_ {
+                    xlib::ButtonPress => ElementState::Pressed,
+                    _ => ElementState::Released,
+                };
+
+                // Buttons 4 to 7 are the scroll wheels, which send a press and a release per notch.
+                // Finer deltas would require the scroll valuators of XInput 2.1.
+                let (delta_x, delta_y) = match button.button {
+                    4 => (0.0, 1.0),
+                    5 => (0.0, -1.0),
+                    6 => (-1.0, 0.0),
+                    7 => (1.0, 0.0),
+                    _ => {
+                        callback(Event::MouseInput {
+                            button: match button.button {
+                                1 => MouseButton::Left,
+                                2 => MouseButton::Middle,
+                                3 => MouseButton::Right,
+                                8 => MouseButton::Back,
+                                9 => MouseButton::Forward,
+                                other => MouseButton::Other(other as u16),
+                            },
+                            state,
+                        });
+                        return;
+                    }
+                };
+
+                if state == ElementState::Pressed {
+                    callback(Event::MouseWheel { delta_x, delta_y });
+                }
+            }
+            xlib::MotionNotify => {
+                let motion = xlib::XMotionEvent::from(*event);
+
+                // A locked cursor is emulated by moving it back to the center after every motion.
+                if self.cursor_grab.get() == CursorGrabMode::Locked {
+                    let center = self.center();
+                    if (motion.x, motion.y) != center {
+                        self.warp_cursor(center);
+                    }
+                    return;
+                }
+
+                callback(Event::CursorMoved {
+                    x: motion.x as f64,
+                    y: motion.y as f64,
+                });
+            }
+            xlib::GenericEvent => self.handle_generic_event(event, callback),
+            xlib::EnterNotify => callback(Event::CursorEntered),
+            xlib::LeaveNotify => callback(Event::CursorLeft),
+            _ => {}
+        }
+    }
+
+    fn handle_generic_event(&self, event: &xlib::XEvent, callback: &mut dyn FnMut(Event)) {
+        let xinput = match &self.xinput {
+            Some(xinput) => xinput,
+            None => return,
+        };
+
+        // SAFETY: Generic events are always cookies.
+        let mut cookie = unsafe { event.generic_event_cookie };
+        if cookie.extension != xinput.opcode || cookie.evtype != xinput2::XI_RawMotion {
+            return;
+        }
+
+        if unsafe { (self.xlib.XGetEventData)(self.display, &mut cookie) } == xlib::False {
+            return;
+        }
+
+        // SAFETY: The data of a raw motion cookie is an XIRawEvent. The raw values only contain
+        // the valuators whose bit is set in the mask, the first two being the x and y axis.
+        let (delta_x, delta_y) = unsafe {
+            let raw = &*(cookie.data as *const xinput2::XIRawEvent);
+            let mask = slice::from_raw_parts(raw.valuators.mask, raw.valuators.mask_len as usize);
+
+            let mut values = raw.raw_values;
+            let mut next_value = |axis| match xinput2::XIMaskIsSet(mask, axis) {
+                true => {
+                    let value = *values;
+                    values = values.add(1);
+                    value
+                }
+                false => 0.0,
+            };
+            let delta_x = next_value(0);
+            let delta_y = next_value(1);
+
+            (self.xlib.XFreeEventData)(self.display, &mut cookie);
+            (delta_x, delta_y)
+        };
+
+        // Raw events are reported for every window, but only the focused one should see them.
+        if self.focused.get() && (delta_x != 0.0 || delta_y != 0.0) {
+            callback(Event::MouseMotion { delta_x, delta_y });
+        }
+    }
+
+    /// Grabs or releases the pointer to match the grab mode and focus.
+    fn apply_cursor_grab(&self) {
+        unsafe {
+            (self.xlib.XUngrabPointer)(self.display, xlib::CurrentTime);
+
+            if self.cursor_grab.get() != CursorGrabMode::None && self.focused.get() {
+                // Confining the pointer to the window itself keeps it inside the window.
+                (self.xlib.XGrabPointer)(
+                    self.display,
+                    self.window,
+                    xlib::True,
+                    (xlib::ButtonPressMask | xlib::ButtonReleaseMask | xlib::PointerMotionMask)
+                        as u32,
+                    xlib::GrabModeAsync,
+                    xlib::GrabModeAsync,
+                    self.window,
+                    0,
+                    xlib::CurrentTime,
+                );
+
+                if self.cursor_grab.get() == CursorGrabMode::Locked {
+                    self.warp_cursor(self.center());
+                }
+            }
+
+            (self.xlib.XFlush)(self.display);
+        }
+    }
+
+    /// Lets the input method intercept keys while text input is enabled and the window is focused.
+    fn update_ime_focus(&self) {
+        if let Some(xim) = &self.xim {
+            xim.set_focus(&self.xlib, self.text_input.get() && self.focused.get());
+        }
+    }
+
+    /// Returns the text a key press produces.
+    fn lookup_string(&self, key: &mut xlib::XKeyEvent) -> String {
+        if let Some(xim) = &self.xim {
+            return xim.lookup_string(&self.xlib, key);
+        }
+
+        // Without an input method only Latin-1 is available.
+        let mut buffer = [0u8; 32];
+        let length = unsafe {
+            (self.xlib.XLookupString)(
+                key,
+                buffer.as_mut_ptr() as *mut c_char,
+                buffer.len() as c_int,
+                ptr::null_mut(),
+                ptr::null_mut(),
+            )
+        };
+        buffer[..length.max(0) as usize]
+            .iter()
+            .map(|&byte| byte as char)
+            .collect()
+    }
+
+    /// Returns the center of the window relative to its top-left corner.
+    fn center(&self) -> (i32, i32) {
+        let (width, height) = self.size.get();
+        (width as i32 / 2, height as i32 / 2)
+    }
+
+    /// Moves the cursor to a position relative to the top-left corner of the window.
+    fn warp_cursor(&self, (x, y): (i32, i32)) {
+        unsafe {
+            (self.xlib.XWarpPointer)(self.display, 0, self.window, 0, 0, 0, 0, x, y);
+            (self.xlib.XFlush)(self.display);
+        }
+    }
+
+    /// Returns a cursor without any visible pixels.
+    fn blank_cursor(&self) -> xlib::Cursor {
+        if self.blank_cursor.get() == 0 {
+            unsafe {
+                let data: [c_char; 1] = [0];
+                let pixmap = (self.xlib.XCreateBitmapFromData)(
+                    self.display,
+                    self.window,
+                    data.as_ptr(),
+                    1,
+                    1,
+                );
+
+                let mut color = MaybeUninit::<xlib::XColor>::zeroed().assume_init();
+                let cursor = (self.xlib.XCreatePixmapCursor)(
+                    self.display,
+                    pixmap,
+                    pixmap,
+                    &mut color,
+                    &mut color,
+                    0,
+                    0,
+                );
+                (self.xlib.XFreePixmap)(self.display, pixmap);
+
+                self.blank_cursor.set(cursor);
+            }
+        }
+
+        self.blank_cursor.get()
+    }
+
+    /// Creates a cursor, returns `0` if it couldn't be created.
+    fn create_cursor(&self, cursor: &Cursor) -> xlib::Cursor {
+        match (cursor, &self.xcursor) {
+            (Cursor::Icon(icon), Some(xcursor)) => {
+                for name in super::cursor_names(*icon) {
+                    let name = CString::new(*name).unwrap();
+                    let cursor =
+                        unsafe { (xcursor.XcursorLibraryLoadCursor)(self.display, name.as_ptr()) };
+                    if cursor != 0 {
+                        return cursor;
+                    }
+                }
+
+                // Not every theme has every cursor, the cursor font does.
+                unsafe { (self.xlib.XCreateFontCursor)(self.display, font_cursor(*icon)) }
+            }
+            (Cursor::Icon(icon), None) => unsafe {
+                (self.xlib.XCreateFontCursor)(self.display, font_cursor(*icon))
+            },
+            (Cursor::Custom(custom), Some(xcursor)) => unsafe {
+                let image = custom.image();
+                let cursor_image =
+                    (xcursor.XcursorImageCreate)(image.width as c_int, image.height as c_int);
+                if cursor_image.is_null() {
+                    return 0;
+                }
+
+                // SAFETY: The image was created with room for `width * height` pixels.
+                let (x, y) = custom.hotspot();
+                (*cursor_image).xhot = x;
+                (*cursor_image).yhot = y;
+                let pixels = std::slice::from_raw_parts_mut(
+                    (*cursor_image).pixels,
+                    image.width as usize * image.height as usize,
+                );
+                for (pixel, argb) in pixels.iter_mut().zip(custom.premultiplied_argb()) {
+                    *pixel = argb;
+                }
+
+                let cursor = (xcursor.XcursorImageLoadCursor)(self.display, cursor_image);
+                (xcursor.XcursorImageDestroy)(cursor_image);
+                cursor
+            },
+            (Cursor::Custom(_), None) => 0,
+        }
+    }
+
+    /// Shows the cursor of the application, or hides it.
+    fn apply_cursor(&self) {
+        unsafe {
+            match (self.cursor_visible.get(), self.cursor.get()) {
+                (false, _) => {
+                    (self.xlib.XDefineCursor)(self.display, self.window, self.blank_cursor())
+                }
+                (true, 0) => (self.xlib.XUndefineCursor)(self.display, self.window),
+                (true, cursor) => (self.xlib.XDefineCursor)(self.display, self.window, cursor),
+            };
+            (self.xlib.XFlush)(self.display);
+        }
+    }
+
+    /// Returns the position of the window relative to the root window.
+    fn root_position(&self) -> (i32, i32) {
+        let (mut x, mut y, mut child) = (0, 0, 0);
+        unsafe {
+            (self.xlib.XTranslateCoordinates)(
+                self.display,
+                self.window,
+                (self.xlib.XDefaultRootWindow)(self.display),
+                0,
+                0,
+                &mut x,
+                &mut y,
+                &mut child,
+            );
+        }
+        (x, y)
+    }
+
+    /// Returns whether the window manager has set the `_NET_WM_STATE` atom on the window.
+    fn has_net_wm_state(&self, state: xlib::Atom) -> bool {
+        self.net_wm_states().contains(&state)
+    }
+
+    /// Returns the atoms of the `_NET_WM_STATE` property of the window.
+    fn net_wm_states(&self) -> Vec<xlib::Atom> {
+        let mut actual_type = 0;
+        let mut actual_format = 0;
+        let mut item_count: c_ulong = 0;
+        let mut bytes_after: c_ulong = 0;
+        let mut data = ptr::null_mut();
+
+        unsafe {
+            let status = (self.xlib.XGetWindowProperty)(
+                self.display,
+                self.window,
+                self.atoms.net_wm_state,
+                0,
+                1024,
+                xlib::False,
+                xlib::XA_ATOM,
+                &mut actual_type,
+                &mut actual_format,
+                &mut item_count,
+                &mut bytes_after,
+                &mut data,
+            );
+
+            if status != xlib::Success as c_int || data.is_null() {
+                return Vec::new();
+            }
+
+            // SAFETY: Properties with a format of 32 are returned as an array of longs.
+            let states =
+                slice::from_raw_parts(data as *const xlib::Atom, item_count as usize).to_vec();
+            (self.xlib.XFree)(data as *mut _);
+            states
+        }
+    }
+
+    /// Translates the modifier mask of an input event.
+    fn modifiers(state: u32) -> Modifiers {
+        let mut modifiers = Modifiers::empty();
+        modifiers.set(Modifiers::SHIFT, state & xlib::ShiftMask != 0);
+        modifiers.set(Modifiers::CONTROL, state & xlib::ControlMask != 0);
+        modifiers.set(Modifiers::ALT, state & xlib::Mod1Mask != 0);
+        modifiers.set(Modifiers::SUPER, state & xlib::Mod4Mask != 0);
+        modifiers.set(Modifiers::CAPS_LOCK, state & xlib::LockMask != 0);
+        modifiers.set(Modifiers::NUM_LOCK, state & xlib::Mod2Mask != 0);
+        modifiers
+    }
+
+    /// Tells the window manager about the size constraints of the window.
+    fn update_size_hints(&self) {
+        let mut hints = unsafe { MaybeUninit::<xlib::XSizeHints>::zeroed().assume_init() };
+
+        if let Some((x, y)) = self.position.get() {
+            hints.flags |= xlib::USPosition;
+            hints.x = x;
+            hints.y = y;
+        }
+
+        let physical =
+            |size: Option<Size>| size.map(|size| size.to_physical(self.scale_factor).into());
+        let (min_size, max_size) = match self.resizable.get() {
+            true => (physical(self.min_size.get()), physical(self.max_size.get())),
+            false => (Some(self.size.get()), Some(self.size.get())),
+        };
+
+        if let Some((width, height)) = min_size {
+            hints.flags |= xlib::PMinSize;
+            hints.min_width = width as c_int;
+            hints.min_height = height as c_int;
+        }
+
+        if let Some((width, height)) = max_size {
+            hints.flags |= xlib::PMaxSize;
+            hints.max_width = width as c_int;
+            hints.max_height = height as c_int;
+        }
+
+        unsafe { (self.xlib.XSetWMNormalHints)(self.display, self.window, &mut hints) };
+    }
+
+    /// Adds or removes a `_NET_WM_STATE` atom.
+    ///
+    /// Before the window is mapped the property is written directly, keeping the other states set so far.
+    /// Afterwards the window manager owns it and has to be asked through a client message.
+    fn set_net_wm_state(&self, state: xlib::Atom, enable: bool) {
+        unsafe {
+            if !self.mapped.get() {
+                let mut states = self.net_wm_states();
+                states.retain(|&other| other != state);
+                if enable {
+                    states.push(state);
+                }
+
+                (self.xlib.XChangeProperty)(
+                    self.display,
+                    self.window,
+                    self.atoms.net_wm_state,
+                    xlib::XA_ATOM,
+                    32,
+                    xlib::PropModeReplace,
+                    states.as_ptr() as *const c_uchar,
+                    states.len() as c_int,
+                );
+                return;
+            }
+
+            const NET_WM_STATE_REMOVE: c_long = 0;
+            const NET_WM_STATE_ADD: c_long = 1;
+
+            let mut message = MaybeUninit::<xlib::XClientMessageEvent>::zeroed().assume_init();
+            message.type_ = xlib::ClientMessage;
+            message.window = self.window;
+            message.message_type = self.atoms.net_wm_state;
+            message.format = 32;
+            message.data.set_long(
+                0,
+                match enable {
+                    true => NET_WM_STATE_ADD,
+                    false => NET_WM_STATE_REMOVE,
+                },
+            );
+            message.data.set_long(1, state as c_long);
+
+            let mut event = xlib::XEvent::from(message);
+            (self.xlib.XSendEvent)(
+                self.display,
+                (self.xlib.XDefaultRootWindow)(self.display),
+                xlib::False,
+                xlib::SubstructureRedirectMask | xlib::SubstructureNotifyMask,
+                &mut event,
+            );
+            (self.xlib.XFlush)(self.display);
+        }
+    }
+}
+
+impl Drop for Window {
+    fn drop(&mut self) {
+        // A changed video mode outlives the connection, so it is restored explicitly.
+        if let (Some((output, mode)), Some(randr)) = (self.saved_mode.take(), &self.randr) {
+            randr.restore_mode(self.display, output, mode);
+        }
+
+        // SAFETY: Nothing uses the window or the display after this,
+        // and the input context is destroyed before the display it belongs to.
+        unsafe {
+            if let Some(xim) = &self.xim {
+                xim.destroy(&self.xlib);
+            }
+            for cursor in [self.blank_cursor.get(), self.cursor.get()] {
+                if cursor != 0 {
+                    (self.xlib.XFreeCursor)(self.display, cursor);
+                }
+            }
+
+            // Closing the display also releases grabs and everything else owned by the connection.
+            (self.xlib.XDestroyWindow)(self.display, self.window);
+            (self.xlib.XCloseDisplay)(self.display);
+        }
+    }
+}
+
+impl HasWindowHandle for Window {
+    fn window_handle(&self) -> Result<WindowHandle<'_>, HandleError> {
+        let handle = XlibWindowHandle::new(self.window);
+
+        // SAFETY: The window is valid until this object is dropped, which the borrow outlives.
+        Ok(unsafe { WindowHandle::borrow_raw(handle.into()) })
+    }
+}
+
+impl HasDisplayHandle for Window {
+    fn display_handle(&self) -> Result<DisplayHandle<'_>, HandleError> {
+        let screen = unsafe { (self.xlib.XDefaultScreen)(self.display) };
+        let handle = XlibDisplayHandle::new(NonNull::new(self.display as *mut c_void), screen);
+
+        // SAFETY: The display is closed when this object is dropped, which the borrow outlives.
+        Ok(unsafe { DisplayHandle::borrow_raw(handle.into()) })
+    }
+}
+
+impl WindowApi for Window {
+    fn poll_events(&self, callback: &mut dyn FnMut(Event)) {
+        // Without a window manager no ConfigureNotify follows the mapping, so the size is reported here.
+        if !self.polled.replace(true) {
+            let (width, height) = self.size.get();
+            callback(Event::Resized { width, height });
+        }
+
+        unsafe {
+            while (self.xlib.XPending)(self.display) > 0 {
+                let mut event = MaybeUninit::<xlib::XEvent>::uninit();
+                (self.xlib.XNextEvent)(self.display, event.as_mut_ptr());
+                let mut event = event.assume_init();
+
+                // The input method communicates through events, and consumes the keys used for composing.
+                let filtered =
+                    self.xim.is_some() && (self.xlib.XFilterEvent)(&mut event, 0) == xlib::True;
+                if !filtered {
+                    self.handle_event(&event, callback);
+                }
+
+                if let Some(xim) = &self.xim {
+                    for ime in xim.take_events() {
+                        if self.text_input.get() {
+                            callback(Event::Ime(ime));
+                        }
+                    }
+                }
+            }
+        }
+    }
+
+    fn set_title(&self, title: &str) {
+        let title = CString::new(title).unwrap_or_default();
+        unsafe {
+            // WM_NAME is Latin-1, so _NET_WM_NAME is set as well for window managers that understand UTF-8.
+            (self.xlib.XStoreName)(self.display, self.window, title.as_ptr());
+            (self.xlib.XChangeProperty)(
+                self.display,
+                self.window,
+                self.atoms.net_wm_name,
+                self.atoms.utf8_string,
+                8,
+                xlib::PropModeReplace,
+                title.as_ptr() as *const c_uchar,
+                title.as_bytes().len() as c_int,
+            );
+            (self.xlib.XFlush)(self.display);
+        }
+    }
+
+    fn scale_factor(&self) -> f64 {
+        self.scale_factor
+    }
+
+    fn inner_size(&self) -> PhysicalSize {
+        self.size.get().into()
+    }
+
+    fn set_inner_size(&self, size: Size) {
+        let PhysicalSize { width, height } = size.to_physical(self.scale_factor);
+        self.size.set((width, height));
+        self.update_size_hints();
+        unsafe {
+            (self.xlib.XResizeWindow)(self.display, self.window, width, height);
+            (self.xlib.XFlush)(self.display);
+        }
+    }
+
+    fn set_position(&self, x: i32, y: i32) {
+        self.position.set(Some((x, y)));
+        self.update_size_hints();
+        unsafe {
+            (self.xlib.XMoveWindow)(self.display, self.window, x, y);
+            (self.xlib.XFlush)(self.display);
+        }
+    }
+
+    fn set_min_inner_size(&self, size: Option<Size>) {
+        self.min_size.set(size);
+        self.update_size_hints();
+    }
+
+    fn set_max_inner_size(&self, size: Option<Size>) {
+        self.max_size.set(size);
+        self.update_size_hints();
+    }
+
+    fn set_resizable(&self, resizable: bool) {
+        self.resizable.set(resizable);
+        self.update_size_hints();
+    }
+
+    fn set_decorations(&self, decorations: bool) {
+        // Layout of the Motif window manager hints, which most window managers still honor.
+        const MWM_HINTS_DECORATIONS: c_long = 1 << 1;
+        let hints: [c_long; 5] = [MWM_HINTS_DECORATIONS, 0, decorations as c_long, 0, 0];
+
+        unsafe {
+            (self.xlib.XChangeProperty)(
+                self.display,
+                self.window,
+                self.atoms.motif_wm_hints,
+                self.atoms.motif_wm_hints,
+                32,
+                xlib::PropModeReplace,
+                hints.as_ptr() as *const c_uchar,
+                hints.len() as c_int,
+            );
+            (self.xlib.XFlush)(self.display);
+        }
+    }
+
+    fn set_always_on_top(&self, always_on_top: bool) {
+        self.set_net_wm_state(self.atoms.net_wm_state_above, always_on_top);
+    }
+
+    fn set_visible(&self, visible: bool) {
+        unsafe {
+            match visible {
+                true => (self.xlib.XMapRaised)(self.display, self.window),
+                false => (self.xlib.XUnmapWindow)(self.display, self.window),
+            };
+            (self.xlib.XFlush)(self.display);
+        }
+        self.mapped.set(visible);
+    }
+
+    fn set_cursor_grab(&self, mode: CursorGrabMode) {
+        self.cursor_grab.set(mode);
+        self.apply_cursor_grab();
+    }
+
+    fn set_cursor_visible(&self, visible: bool) {
+        self.cursor_visible.set(visible);
+        self.apply_cursor();
+    }
+
+    fn set_cursor(&self, cursor: &Cursor) {
+        let previous = self.cursor.replace(self.create_cursor(cursor));
+        self.apply_cursor();
+
+        // The server keeps a cursor alive while a window uses it, so it can be freed right away.
+        if previous != 0 {
+            unsafe { (self.xlib.XFreeCursor)(self.display, previous) };
+        }
+    }
+
+    fn set_icon(&self, icon: Option<&Icon>) {
+        let icon = match icon {
+            Some(icon) => icon,
+            None => {
+                unsafe {
+                    (self.xlib.XDeleteProperty)(self.display, self.window, self.atoms.net_wm_icon);
+                    (self.xlib.XFlush)(self.display);
+                }
+                return;
+            }
+        };
+
+        // The images are concatenated, each as its width and height followed by its pixels in
+        // ARGB with straight alpha. Properties with a format of 32 are passed as longs.
+        let mut data: Vec<c_long> = Vec::new();
+        for image in icon.images() {
+            data.extend([image.width as c_long, image.height as c_long]);
+            data.extend(image.rgba.chunks_exact(4).map(|pixel| {
+                let argb = u32::from_be_bytes([pixel[3], pixel[0], pixel[1], pixel[2]]);
+                argb as c_long
+            }));
+        }
+
+        unsafe {
+            (self.xlib.XChangeProperty)(
+                self.display,
+                self.window,
+                self.atoms.net_wm_icon,
+                xlib::XA_CARDINAL,
+                32,
+                xlib::PropModeReplace,
+                data.as_ptr() as *const c_uchar,
+                data.len() as c_int,
+            );
+            (self.xlib.XFlush)(self.display);
+        }
+    }
+
+    fn monitors(&self) -> Vec<Monitor> {
+        match &self.randr {
+            Some(randr) => randr.monitors(&self.xlib, self.display),
+            None => Vec::new(),
+        }
+    }
+
+    fn current_monitor(&self) -> Option<Monitor> {
+        let (x, y) = self.root_position();
+        let (width, height) = self.size.get();
+        let center = (x + width as i32 / 2, y + height as i32 / 2);
+
+        let monitors = self.monitors();
+        let current = monitors.iter().position(|monitor| monitor.contains(center));
+        monitors.into_iter().nth(current.unwrap_or(0))
+    }
+
+    fn set_fullscreen(&self, fullscreen: Option<Fullscreen>) {
+        let monitor = match &fullscreen {
+            Some(Fullscreen::Borderless(monitor)) => {
+                monitor.clone().or_else(|| self.current_monitor())
+            }
+            Some(Fullscreen::Exclusive(monitor, _)) => Some(monitor.clone()),
+            None => None,
+        };
+
+        // The original video mode is kept when switching between the modes of the same monitor,
+        // otherwise it is restored before anything else changes.
+        let mut saved_mode = self.saved_mode.take();
+        if let (Some((output, mode)), Some(randr)) = (saved_mode, &self.randr) {
+            if !matches!(&fullscreen, Some(Fullscreen::Exclusive(monitor, _)) if monitor.handle == output)
+            {
+                randr.restore_mode(self.display, output, mode);
+                saved_mode = None;
+            }
+        }
+
+        if let (Some(Fullscreen::Exclusive(monitor, mode)), Some(randr)) =
+            (&fullscreen, &self.randr)
+        {
+            if let Some(previous) = randr.set_mode(self.display, monitor.handle, mode) {
+                saved_mode.get_or_insert((monitor.handle, previous));
+            }
+        }
+        self.saved_mode.set(saved_mode);
+
+        // Window managers ignore moves of fullscreen windows, so moving to another monitor
+        // leaves fullscreen first. The window manager picks the monitor the window is on.
+        if self.windowed_position.get().is_some() {
+            self.set_net_wm_state(self.atoms.net_wm_state_fullscreen, false);
+        }
+
+        let position = match monitor {
+            Some(monitor) => {
+                if self.windowed_position.get().is_none() {
+                    self.windowed_position.set(Some(self.root_position()));
+                }
+                Some(monitor.position)
+            }
+            None => self.windowed_position.take(),
+        };
+
+        unsafe {
+            if let Some((x, y)) = position {
+                (self.xlib.XMoveWindow)(self.display, self.window, x, y);
+            }
+            (self.xlib.XFlush)(self.display);
+        }
+
+        if self.windowed_position.get().is_some() {
+            self.set_net_wm_state(self.atoms.net_wm_state_fullscreen, true);
+        }
+    }
+
+    fn set_text_input(&self, enabled: bool) {
+        self.text_input.set(enabled);
+        self.update_ime_focus();
+        unsafe { (self.xlib.XFlush)(self.display) };
+    }
+
+    fn set_ime_position(&self, x: i32, y: i32) {
+        if let Some(xim) = &self.xim {
+            xim.set_spot(&self.xlib, x, y);
+            unsafe { (self.xlib.XFlush)(self.display) };
+        }
+    }
+
+    fn set_drag_and_drop(&self, enabled: bool) {
+        self.xdnd.set_enabled(&self.xlib, enabled);
+    }
+}
+
+/// Returns the scale factor configured through the `Xft.dpi` resource, which desktop
+/// environments set for all monitors alike, relative to the default of 96 DPI.
+pub(super) unsafe fn xft_scale_factor(xlib: &xlib::Xlib, display: *mut xlib::Display) -> f64 {
+    let resources = (xlib.XResourceManagerString)(display);
+    if resources.is_null() {
+        return 1.0;
+    }
+
+    CStr::from_ptr(resources as *const c_char)
+        .to_string_lossy()
+        .lines()
+        .filter_map(|line| line.strip_prefix("Xft.dpi:"))
+        .find_map(|dpi| dpi.trim().parse::<f64>().ok())
+        .filter(|&dpi| dpi > 0.0)
+        .map_or(1.0, |dpi| dpi / 96.0)
+}
+
+/// Returns the glyph of the cursor font closest to a cursor, from `X11/cursorfont.h`.
+fn font_cursor(icon: CursorIcon) -> u32 {
+    match icon {
+        CursorIcon::Arrow => 68,
+        CursorIcon::Text => 152,
+        CursorIcon::Hand => 60,
+        CursorIcon::Crosshair => 34,
+        CursorIcon::Wait | CursorIcon::Progress => 150,
+        CursorIcon::ResizeHorizontal => 108,
+        CursorIcon::ResizeVertical => 116,
+        CursorIcon::ResizeTopLeft => 14,
+        CursorIcon::ResizeTopRight => 12,
+        CursorIcon::Move => 52,
+        CursorIcon::NotAllowed => 0,
+    }
+}
@@ -0,0 +1,353 @@
+//! Receiving dropped files through the XDND protocol.
+//!
+//! The source of a drag talks to the window under the cursor with client messages: it announces the
+//! offered types on enter, and sends the cursor position, which is answered with whether a drop
+//! would be accepted. The data itself is transferred through the `XdndSelection` selection.
+
+use std::{
+    cell::RefCell,
+    mem::MaybeUninit,
+    os::raw::{c_int, c_long, c_uchar},
+    path::PathBuf,
+    ptr, slice,
+};
+
+use x11_dl::xlib;
+
+use crate::os::Event;
+
+/// The version of the protocol implemented, which is also the latest.
+const XDND_VERSION: c_long = 5;
+
+struct Atoms {
+    aware: xlib::Atom,
+    enter: xlib::Atom,
+    position: xlib::Atom,
+    status: xlib::Atom,
+    leave: xlib::Atom,
+    drop: xlib::Atom,
+    finished: xlib::Atom,
+    selection: xlib::Atom,
+    type_list: xlib::Atom,
+    action_copy: xlib::Atom,
+    uri_list: xlib::Atom,
+
+    /// The property of the window that receives the dragged data.
+    property: xlib::Atom,
+}
+
+/// A drag over the window.
+struct Drag {
+    source: xlib::Window,
+
+    /// Whether the source offers files, otherwise the drag is ignored.
+    accepted: bool,
+    position: (f64, f64),
+
+    /// The dragged files, `None` until the source sent them.
+    paths: Option<Vec<PathBuf>>,
+    requested: bool,
+
+    /// Whether the files were dropped before they arrived.
+    dropped: bool,
+}
+
+pub struct Xdnd {
+    display: *mut xlib::Display,
+    window: xlib::Window,
+    atoms: Atoms,
+    drag: RefCell<Option<Drag>>,
+}
+
+impl Xdnd {
+    pub unsafe fn new(
+        xlib: &xlib::Xlib,
+        display: *mut xlib::Display,
+        window: xlib::Window,
+    ) -> Self {
+        let intern =
+            |name: &std::ffi::CStr| (xlib.XInternAtom)(display, name.as_ptr(), xlib::False);
+
+        let atoms = Atoms {
+            aware: intern(c"XdndAware"),
+            enter: intern(c"XdndEnter"),
+            position: intern(c"XdndPosition"),
+            status: intern(c"XdndStatus"),
+            leave: intern(c"XdndLeave"),
+            drop: intern(c"XdndDrop"),
+            finished: intern(c"XdndFinished"),
+            selection: intern(c"XdndSelection"),
+            type_list: intern(c"XdndTypeList"),
+            action_copy: intern(c"XdndActionCopy"),
+            uri_list: intern(c"text/uri-list"),
+            property: intern(c"IGLO_XDND"),
+        };
+
+        Self {
+            display,
+            window,
+            atoms,
+            drag: RefCell::new(None),
+        }
+    }
+
+    /// Advertises whether the window accepts drops, sources ignore windows without `XdndAware`.
+    pub fn set_enabled(&self, xlib: &xlib::Xlib, enabled: bool) {
+        unsafe {
+            match enabled {
+                true => (xlib.XChangeProperty)(
+                    self.display,
+                    self.window,
+                    self.atoms.aware,
+                    xlib::XA_ATOM,
+                    32,
+                    xlib::PropModeReplace,
+                    &XDND_VERSION as *const _ as *const c_uchar,
+                    1,
+                ),
+                false => (xlib.XDeleteProperty)(self.display, self.window, self.atoms.aware),
+            };
+            (xlib.XFlush)(self.display);
+        }
+    }
+
+    pub fn handle_client_message(
+        &self,
+        xlib: &xlib::Xlib,
+        message: &xlib::XClientMessageEvent,
+        callback: &mut dyn FnMut(Event),
+    ) {
+        let data = |index| message.data.get_long(index);
+        let kind = message.message_type;
+
+        // Messages of other drags are ignored, every message carries the source window first.
+        let source = data(0) as xlib::Window;
+        let is_current = matches!(&*self.drag.borrow(), Some(drag) if drag.source == source);
+
+        if kind == self.atoms.enter {
+            // Sources offering more than three types list them in a property instead.
+            let types = match data(1) & 1 {
+                0 => vec![
+                    data(2) as xlib::Atom,
+                    data(3) as xlib::Atom,
+                    data(4) as xlib::Atom,
+                ],
+                _ => unsafe { self.read_property(xlib, source, self.atoms.type_list, false) },
+            };
+
+            *self.drag.borrow_mut() = Some(Drag {
+                source,
+                accepted: types.contains(&self.atoms.uri_list),
+                position: (0.0, 0.0),
+                paths: None,
+                requested: false,
+                dropped: false,
+            });
+        } else if kind == self.atoms.position && is_current {
+            let mut drag = self.drag.borrow_mut();
+            let drag = drag.as_mut().unwrap();
+
+            // The position is in root coordinates, packed into a single value.
+            let (root_x, root_y) = ((data(2) >> 16) as i16 as c_int, data(2) as i16 as c_int);
+            let (mut x, mut y, mut child) = (0, 0, 0);
+            unsafe {
+                (xlib.XTranslateCoordinates)(
+                    self.display,
+                    (xlib.XDefaultRootWindow)(self.display),
+                    self.window,
+                    root_x,
+                    root_y,
+                    &mut x,
+                    &mut y,
+                    &mut child,
+                )
+            };
+            drag.position = (x as f64, y as f64);
+
+            // The files are requested right away, so they can be reported while hovering.
+            if drag.accepted && !drag.requested {
+                drag.requested = true;
+                unsafe {
+                    (xlib.XConvertSelection)(
+                        self.display,
+                        self.atoms.selection,
+                        self.atoms.uri_list,
+                        self.atoms.property,
+                        self.window,
+                        data(3) as xlib::Time,
+                    )
+                };
+            }
+
+            // Setting the second flag asks for positions even while the cursor doesn't move.
+            let (accepted, action) = match drag.accepted {
+                true => (0b11, self.atoms.action_copy as c_long),
+                false => (0b10, 0),
+            };
+            self.send(
+                xlib,
+                source,
+                self.atoms.status,
+                [self.window as c_long, accepted, 0, 0, action],
+            );
+
+            if drag.paths.is_some() {
+                let (x, y) = drag.position;
+                callback(Event::CursorMoved { x, y });
+            }
+        } else if kind == self.atoms.leave && is_current {
+            let drag = self.drag.borrow_mut().take().unwrap();
+            if drag.paths.is_some() {
+                callback(Event::HoveredFileCancelled);
+            }
+        } else if kind == self.atoms.drop && is_current {
+            let mut drag = self.drag.borrow_mut().take().unwrap();
+            match (drag.paths.is_some(), drag.requested) {
+                (true, _) => self.finish_drop(xlib, drag, callback),
+
+                // The files are reported once they arrive.
+                (false, true) => {
+                    drag.dropped = true;
+                    *self.drag.borrow_mut() = Some(drag);
+                }
+                (false, false) => self.send(
+                    xlib,
+                    source,
+                    self.atoms.finished,
+                    [self.window as c_long, 0, 0, 0, 0],
+                ),
+            }
+        }
+    }
+
+    /// Handles the arrival of the dragged files.
+    pub fn handle_selection_notify(
+        &self,
+        xlib: &xlib::Xlib,
+        event: &xlib::XSelectionEvent,
+        callback: &mut dyn FnMut(Event),
+    ) {
+        if event.requestor != self.window || event.selection != self.atoms.selection {
+            return;
+        }
+
+        let mut drag = match self.drag.borrow_mut().take() {
+            Some(drag) => drag,
+            None => return,
+        };
+
+        // A property of `None` means the source failed to convert the selection.
+        let list: Vec<u8> = match event.property {
+            0 => Vec::new(),
+            _ => unsafe { self.read_property(xlib, self.window, self.atoms.property, true) },
+        };
+        let paths = super::parse_uri_list(&list);
+
+        let (x, y) = drag.position;
+        for path in &paths {
+            callback(Event::HoveredFile {
+                path: path.clone(),
+                x,
+                y,
+            });
+        }
+        drag.paths = Some(paths);
+
+        match drag.dropped {
+            true => self.finish_drop(xlib, drag, callback),
+            false => *self.drag.borrow_mut() = Some(drag),
+        }
+    }
+
+    /// Reports the dropped files, and tells the source the drop is done.
+    fn finish_drop(&self, xlib: &xlib::Xlib, drag: Drag, callback: &mut dyn FnMut(Event)) {
+        let (x, y) = drag.position;
+        for path in drag.paths.unwrap_or_default() {
+            callback(Event::DroppedFile { path, x, y });
+        }
+
+        self.send(
+            xlib,
+            drag.source,
+            self.atoms.finished,
+            [
+                self.window as c_long,
+                1,
+                self.atoms.action_copy as c_long,
+                0,
+                0,
+            ],
+        );
+    }
+
+    fn send(&self, xlib: &xlib::Xlib, target: xlib::Window, kind: xlib::Atom, data: [c_long; 5]) {
+        unsafe {
+            let mut message = MaybeUninit::<xlib::XClientMessageEvent>::zeroed().assume_init();
+            message.type_ = xlib::ClientMessage;
+            message.window = target;
+            message.message_type = kind;
+            message.format = 32;
+            for (index, value) in data.into_iter().enumerate() {
+                message.data.set_long(index, value);
+            }
+
+            let mut event = xlib::XEvent::from(message);
+            (xlib.XSendEvent)(
+                self.display,
+                target,
+                xlib::False,
+                xlib::NoEventMask,
+                &mut event,
+            );
+            (xlib.XFlush)(self.display);
+        }
+    }
+
+    /// Reads the items of a property, which must have a format matching the size of `T`.
+    ///
+    /// Properties with a format of 32 are returned as longs, so atoms can be read directly.
+    unsafe fn read_property<T: Copy>(
+        &self,
+        xlib: &xlib::Xlib,
+        window: xlib::Window,
+        property: xlib::Atom,
+        delete: bool,
+    ) -> Vec<T> {
+        let mut actual_type = 0;
+        let mut actual_format = 0;
+        let mut item_count = 0;
+        let mut bytes_after = 0;
+        let mut data = ptr::null_mut();
+
+        let status = (xlib.XGetWindowProperty)(
+            self.display,
+            window,
+            property,
+            0,
+            // Everything, the length is in units of 4 bytes.
+            c_int::MAX as c_long / 4,
+            delete as c_int,
+            xlib::AnyPropertyType as xlib::Atom,
+            &mut actual_type,
+            &mut actual_format,
+            &mut item_count,
+            &mut bytes_after,
+            &mut data,
+        );
+
+        if status != xlib::Success as c_int || data.is_null() {
+            return Vec::new();
+        }
+
+        let expected_format = match std::mem::size_of::<T>() {
+            1 => 8,
+            _ => 32,
+        };
+        let items = match actual_format == expected_format {
+            true => slice::from_raw_parts(data as *const T, item_count as usize).to_vec(),
+            false => Vec::new(),
+        };
+        (xlib.XFree)(data as *mut _);
+        items
+    }
+}
@@ -0,0 +1,339 @@
+//! Text input through the X Input Method protocol.
+
+use std::{
+    cell::RefCell,
+    ffi::CStr,
+    os::raw::{c_char, c_int, c_short, c_ulong, c_ushort, c_void},
+    ptr,
+};
+
+use x11_dl::xlib;
+
+use crate::os::Ime;
+
+/// The supported styles of an input method, which x11-dl doesn't declare.
+#[repr(C)]
+struct XIMStyles {
+    count_styles: c_ushort,
+    supported_styles: *mut c_ulong,
+}
+
+/// The text being composed, maintained by the preedit callbacks.
+#[derive(Default)]
+struct Preedit {
+    text: Vec<char>,
+    caret: usize,
+    events: Vec<Ime>,
+}
+
+impl Preedit {
+    fn push_preedit(&mut self) {
+        let text: String = self.text.iter().collect();
+        let cursor = match self.text.is_empty() {
+            true => None,
+            false => Some(self.text[..self.caret].iter().map(|c| c.len_utf8()).sum()),
+        };
+        self.events.push(Ime::Preedit { text, cursor });
+    }
+}
+
+/// An input context of the window.
+pub struct Xim {
+    ic: xlib::XIC,
+
+    // The preedit callbacks point to this, so it is boxed to keep its address stable.
+    preedit: Box<RefCell<Preedit>>,
+}
+
+impl Xim {
+    /// Opens the input method configured through `XMODIFIERS` and creates an input context for `window`.
+    ///
+    /// Returns `None` if no input method is running, in which case text input falls back to
+    /// the keyboard layout without composition.
+    pub unsafe fn new(
+        xlib: &xlib::Xlib,
+        display: *mut xlib::Display,
+        window: xlib::Window,
+    ) -> Option<Self> {
+        // Xlib only handles multibyte text in the locale of the process, which starts out as C.
+        // Applications that chose a locale themselves are left alone.
+        let current = libc::setlocale(libc::LC_CTYPE, ptr::null());
+        if current.is_null() || CStr::from_ptr(current).to_bytes() == b"C" {
+            libc::setlocale(libc::LC_CTYPE, c"".as_ptr());
+        }
+
+        if (xlib.XSupportsLocale)() == xlib::False {
+            return None;
+        }
+        (xlib.XSetLocaleModifiers)(c"".as_ptr());
+
+        let im = (xlib.XOpenIM)(display, ptr::null_mut(), ptr::null_mut(), ptr::null_mut());
+        if im.is_null() {
+            return None;
+        }
+
+        let mut styles: *mut XIMStyles = ptr::null_mut();
+        (xlib.XGetIMValues)(
+            im,
+            xlib::XNQueryInputStyle_0.as_ptr(),
+            &mut styles,
+            ptr::null_mut::<c_void>(),
+        );
+        if styles.is_null() {
+            (xlib.XCloseIM)(im);
+            return None;
+        }
+
+        let supported =
+            std::slice::from_raw_parts((*styles).supported_styles, (*styles).count_styles as usize);
+
+        // Reporting the composition through callbacks is preferred, so the application can show it.
+        // Otherwise the input method draws it at the spot location, or in a window of its own.
+        let style = [
+            xlib::XIMPreeditCallbacks | xlib::XIMStatusNothing,
+            xlib::XIMPreeditPosition | xlib::XIMStatusNothing,
+            xlib::XIMPreeditNothing | xlib::XIMStatusNothing,
+        ]
+        .into_iter()
+        .map(|style| style as c_ulong)
+        .find(|style| supported.contains(style));
+        (xlib.XFree)(styles as *mut c_void);
+
+        let style = match style {
+            Some(style) => style,
+            None => {
+                (xlib.XCloseIM)(im);
+                return None;
+            }
+        };
+
+        let preedit = Box::new(RefCell::new(Preedit::default()));
+
+        let client_data = &*preedit as *const RefCell<Preedit> as xlib::XPointer;
+        let callback =
+            |callback: unsafe extern "C" fn(xlib::XIC, xlib::XPointer, xlib::XPointer) -> c_int| {
+                xlib::XICCallback {
+                    client_data,
+                    callback: Some(callback),
+                }
+            };
+        let mut start = callback(preedit_start);
+        let mut done = callback(preedit_done);
+        let mut draw = callback(preedit_draw);
+        let mut caret = callback(preedit_caret);
+        let mut spot = xlib::XPoint { x: 0, y: 0 };
+
+        let attributes = match style & xlib::XIMPreeditCallbacks as c_ulong != 0 {
+            true => (xlib.XVaCreateNestedList)(
+                0,
+                xlib::XNPreeditStartCallback_0.as_ptr(),
+                &mut start,
+                xlib::XNPreeditDoneCallback_0.as_ptr(),
+                &mut done,
+                xlib::XNPreeditDrawCallback_0.as_ptr(),
+                &mut draw,
+                xlib::XNPreeditCaretCallback_0.as_ptr(),
+                &mut caret,
+                ptr::null_mut::<c_void>(),
+            ),
+            false => (xlib.XVaCreateNestedList)(
+                0,
+                xlib::XNSpotLocation_0.as_ptr(),
+                &mut spot,
+                ptr::null_mut::<c_void>(),
+            ),
+        };
+
+        let ic = (xlib.XCreateIC)(
+            im,
+            xlib::XNInputStyle_0.as_ptr(),
+            style,
+            xlib::XNClientWindow_0.as_ptr(),
+            window,
+            xlib::XNFocusWindow_0.as_ptr(),
+            window,
+            xlib::XNPreeditAttributes_0.as_ptr(),
+            attributes,
+            ptr::null_mut::<c_void>(),
+        );
+        (xlib.XFree)(attributes);
+
+        if ic.is_null() {
+            (xlib.XCloseIM)(im);
+            return None;
+        }
+
+        // The input context starts out focused, but text input is disabled until it is enabled.
+        (xlib.XUnsetICFocus)(ic);
+
+        Some(Self { ic, preedit })
+    }
+
+    /// Destroys the input context and closes the input method, after which no callbacks are called.
+    ///
+    /// # Safety
+    ///
+    /// The input context must not be used afterwards.
+    pub unsafe fn destroy(&self, xlib: &xlib::Xlib) {
+        let im = (xlib.XIMOfIC)(self.ic);
+        (xlib.XDestroyIC)(self.ic);
+        (xlib.XCloseIM)(im);
+    }
+
+    /// Lets the input method intercept key events of the window, or stops it.
+    pub fn set_focus(&self, xlib: &xlib::Xlib, focused: bool) {
+        unsafe {
+            match focused {
+                true => (xlib.XSetICFocus)(self.ic),
+                false => (xlib.XUnsetICFocus)(self.ic),
+            }
+        }
+    }
+
+    /// Moves the candidate window of the input method.
+    pub fn set_spot(&self, xlib: &xlib::Xlib, x: i32, y: i32) {
+        let mut spot = xlib::XPoint {
+            x: x.clamp(c_short::MIN as i32, c_short::MAX as i32) as c_short,
+            y: y.clamp(c_short::MIN as i32, c_short::MAX as i32) as c_short,
+        };
+
+        unsafe {
+            let attributes = (xlib.XVaCreateNestedList)(
+                0,
+                xlib::XNSpotLocation_0.as_ptr(),
+                &mut spot,
+                ptr::null_mut::<c_void>(),
+            );
+            (xlib.XSetICValues)(
+                self.ic,
+                xlib::XNPreeditAttributes_0.as_ptr(),
+                attributes,
+                ptr::null_mut::<c_void>(),
+            );
+            (xlib.XFree)(attributes);
+        }
+    }
+
+    /// Returns the text a key press produces, including text committed by the input method.
+    pub fn lookup_string(&self, xlib: &xlib::Xlib, key: &mut xlib::XKeyEvent) -> String {
+        let mut buffer = vec![0u8; 64];
+        loop {
+            let mut keysym = 0;
+            let mut status = 0;
+            let length = unsafe {
+                (xlib.Xutf8LookupString)(
+                    self.ic,
+                    key,
+                    buffer.as_mut_ptr() as *mut c_char,
+                    buffer.len() as c_int,
+                    &mut keysym,
+                    &mut status,
+                )
+            };
+
+            // On overflow the length is the size required for the whole text.
+            match status {
+                xlib::XBufferOverflow => buffer.resize(length as usize, 0),
+                xlib::XLookupChars | xlib::XLookupBoth => {
+                    buffer.truncate(length as usize);
+                    return String::from_utf8_lossy(&buffer).into_owned();
+                }
+                _ => return String::new(),
+            }
+        }
+    }
+
+    /// Returns the composition changes reported by the input method since the last call.
+    pub fn take_events(&self) -> Vec<Ime> {
+        std::mem::take(&mut self.preedit.borrow_mut().events)
+    }
+}
+
+/// Returns the preedit state the callbacks of an input context were registered with.
+///
+/// # Safety
+///
+/// `client_data` must be the pointer passed in [`Xim::new()`], whose [`Xim`] is still alive.
+unsafe fn preedit<'a>(client_data: xlib::XPointer) -> &'a RefCell<Preedit> {
+    &*(client_data as *const RefCell<Preedit>)
+}
+
+unsafe extern "C" fn preedit_start(
+    _ic: xlib::XIC,
+    client_data: xlib::XPointer,
+    _call_data: xlib::XPointer,
+) -> c_int {
+    let mut preedit = preedit(client_data).borrow_mut();
+    preedit.text.clear();
+    preedit.caret = 0;
+
+    // The maximum length of the composition, -1 meaning unlimited.
+    -1
+}
+
+unsafe extern "C" fn preedit_done(
+    _ic: xlib::XIC,
+    client_data: xlib::XPointer,
+    _call_data: xlib::XPointer,
+) -> c_int {
+    let mut preedit = preedit(client_data).borrow_mut();
+    preedit.text.clear();
+    preedit.caret = 0;
+    preedit.push_preedit();
+    0
+}
+
+unsafe extern "C" fn preedit_draw(
+    _ic: xlib::XIC,
+    client_data: xlib::XPointer,
+    call_data: xlib::XPointer,
+) -> c_int {
+    let mut preedit = preedit(client_data).borrow_mut();
+    let draw = &*(call_data as *const xlib::XIMPreeditDrawCallbackStruct);
+
+    // The changed range is given in characters, and may be partially out of bounds.
+    let len = preedit.text.len();
+    let first = (draw.chg_first.max(0) as usize).min(len);
+    let last = (first + draw.chg_length.max(0) as usize).min(len);
+
+    // The new text is in the encoding of the locale. Wide characters are not requested, so they are ignored.
+    let mut text = Vec::new();
+    if !draw.text.is_null() {
+        let new = &*draw.text;
+        if new.encoding_is_wchar == xlib::False && !new.string.multi_byte.is_null() {
+            text = CStr::from_ptr(new.string.multi_byte)
+                .to_string_lossy()
+                .chars()
+                .collect();
+        }
+    }
+
+    preedit.text.splice(first..last, text);
+    preedit.caret = (draw.caret.max(0) as usize).min(preedit.text.len());
+    preedit.push_preedit();
+    0
+}
+
+unsafe extern "C" fn preedit_caret(
+    _ic: xlib::XIC,
+    client_data: xlib::XPointer,
+    call_data: xlib::XPointer,
+) -> c_int {
+    let mut preedit = preedit(client_data).borrow_mut();
+    let caret = &mut *(call_data as *mut xlib::XIMPreeditCaretCallbackStruct);
+
+    let len = preedit.text.len();
+    preedit.caret = match caret.direction {
+        xlib::XIMCaretDirection::XIMAbsolutePosition => (caret.position.max(0) as usize).min(len),
+        xlib::XIMCaretDirection::XIMForwardChar => (preedit.caret + 1).min(len),
+        xlib::XIMCaretDirection::XIMBackwardChar => preedit.caret.saturating_sub(1),
+        xlib::XIMCaretDirection::XIMLineStart => 0,
+        xlib::XIMCaretDirection::XIMLineEnd => len,
+        _ => preedit.caret,
+    };
+
+    // The input method reads the resulting position back.
+    caret.position = preedit.caret as c_int;
+    preedit.push_preedit();
+    0
+}
@@ -2,34 +2,513 @@
 pub mod windows;
 
 #[cfg(target_os = "windows")]
-#[path = "windows/mod.rs"]
-mod imp;
+use self::windows as imp;
 
-#[cfg(not(target_os = "windows"))]
+#[cfg(target_os = "linux")]
+pub mod linux;
+
+#[cfg(target_os = "linux")]
+use linux as imp;
+
+#[cfg(not(any(target_os = "windows", target_os = "linux")))]
 std::compile_error!("Unsupported target platform");
 
-pub struct Window(imp::Window);
+pub mod gamepad;
+pub mod headless;
+pub mod hot_reload;
+pub mod time;
+
+pub use clipboard::*;
+pub use dpi::*;
+pub use event::*;
+pub use event_loop::*;
+pub use file_watcher::*;
+pub use icon::*;
+pub use keyboard::*;
+pub use library::*;
+pub use monitor::*;
+pub use mouse::*;
+pub use recording::*;
+
+mod clipboard;
+mod dpi;
+mod event;
+mod event_loop;
+mod file_watcher;
+mod icon;
+mod keyboard;
+mod library;
+mod monitor;
+mod mouse;
+mod recording;
+
+use std::{
+    cell::{Cell, Ref, RefCell},
+    collections::VecDeque,
+    env,
+    rc::Rc,
+};
+
+use raw_window_handle::{
+    DisplayHandle, HandleError, HasDisplayHandle, HasWindowHandle, WindowHandle,
+};
+
+pub struct Window {
+    // Shared with the event loop the window was created with, which only holds a weak reference.
+    inner: Rc<WindowInner>,
+}
+
+struct WindowInner {
+    id: WindowId,
+    imp: WindowImpl,
+    keyboard: RefCell<KeyboardState>,
+    mouse: RefCell<MouseState>,
+    close_requested: Cell<bool>,
+
+    /// Synthetic events of a native window, waiting to be delivered by `poll_events`.
+    injected: RefCell<VecDeque<Event>>,
+}
+
+enum WindowImpl {
+    Native(imp::Window),
+    Headless(Box<headless::Window>),
+}
 
 impl Window {
-    pub fn new() -> Self {
-        Self(imp::Window::new().unwrap())
+    /// Creates a new window.
+    ///
+    /// If no display server is available, or `IGLO_HEADLESS` is set,
+    /// a headless window is created instead. See [`Window::new_headless()`].
+    ///
+    /// The native window is destroyed when the returned object is dropped.
+    /// Windows that are polled together are created with [`EventLoop::create_window()`] instead.
+    ///
+    /// # Arguments
+    ///
+    /// - `desc` - Description of the window.
+    pub fn new(desc: &WindowDesc) -> Result<Self, WindowError> {
+        if env::var_os("IGLO_HEADLESS").is_some() || !imp::is_display_available() {
+            return Ok(Self::new_headless(desc));
+        }
+
+        Ok(Self::from_impl(WindowImpl::Native(imp::Window::new(desc)?)))
+    }
+
+    /// Creates a new headless window, which is never presented on a display.
+    ///
+    /// # Arguments
+    ///
+    /// - `desc` - Description of the window.
+    pub fn new_headless(desc: &WindowDesc) -> Self {
+        Self::from_impl(WindowImpl::Headless(Box::new(headless::Window::new(desc))))
+    }
+
+    fn from_impl(imp: WindowImpl) -> Self {
+        Self {
+            inner: Rc::new(WindowInner {
+                id: WindowId::next(),
+                imp,
+                keyboard: RefCell::new(KeyboardState::default()),
+                mouse: RefCell::new(MouseState::default()),
+                close_requested: Cell::new(false),
+                injected: RefCell::new(VecDeque::new()),
+            }),
+        }
+    }
+
+    /// Returns the identifier of the window, which is unique for the lifetime of the process.
+    pub fn id(&self) -> WindowId {
+        self.inner.id
     }
 
     pub fn show(&self) {
-        self.0.show()
+        self.api().show()
+    }
+
+    /// Processes all pending events and passes them to `callback` in the order they occurred.
+    ///
+    /// This never blocks, so it is meant to be called once per frame.
+    /// The events also update the states returned by [`Window::keyboard()`] and [`Window::mouse()`].
+    pub fn poll_events<F: FnMut(Event)>(&self, mut callback: F) {
+        self.inner.poll_events(&mut callback)
+    }
+
+    /// Queues a synthetic event, which is delivered by the next call to [`Window::poll_events()`]
+    /// after the events of the platform, e.g. to replay recorded input.
+    ///
+    /// The event is handled like a real one by the window, i.e. it updates the input states and
+    /// headless windows apply state changes, see [`headless::Window::inject_event()`].
+    /// Native windows aren't changed by it, an injected [`Event::Resized`] doesn't resize the window.
+    ///
+    /// # Arguments
+    ///
+    /// - `event` - The event to deliver.
+    pub fn inject_event(&self, event: Event) {
+        match &self.inner.imp {
+            WindowImpl::Native(_) => self.inner.injected.borrow_mut().push_back(event),
+            WindowImpl::Headless(window) => window.inject_event(event),
+        }
+    }
+
+    /// Returns whether [`Event::CloseRequested`] has been delivered by [`Window::poll_events()`].
+    ///
+    /// Closing a window only requests it to close, the window stays open until it is dropped.
+    /// This makes it easy to leave the main loop, while the application can still ask for confirmation
+    /// by handling the event itself and calling [`Window::cancel_close_request()`].
+    pub fn is_close_requested(&self) -> bool {
+        self.inner.close_requested.get()
+    }
+
+    /// Forgets a delivered close request, e.g. because the user chose to keep the application open.
+    pub fn cancel_close_request(&self) {
+        self.inner.close_requested.set(false);
+        if let WindowImpl::Headless(window) = &self.inner.imp {
+            window.cancel_close_request();
+        }
+    }
+
+    /// Returns the keyboard state as of the last call to [`Window::poll_events()`].
+    pub fn keyboard(&self) -> Ref<'_, KeyboardState> {
+        self.inner.keyboard.borrow()
+    }
+
+    /// Returns the mouse state as of the last call to [`Window::poll_events()`].
+    pub fn mouse(&self) -> Ref<'_, MouseState> {
+        self.inner.mouse.borrow()
+    }
+
+    pub fn set_title(&self, title: &str) {
+        self.api().set_title(title)
+    }
+
+    /// Returns the factor from logical to physical pixels, e.g. 2.0 on a monitor set to 200% scale.
+    pub fn scale_factor(&self) -> f64 {
+        self.api().scale_factor()
+    }
+
+    /// Returns the inner size of the window in physical pixels, which is what the swapchain should use.
+    pub fn inner_size(&self) -> PhysicalSize {
+        self.api().inner_size()
+    }
+
+    pub fn set_inner_size(&self, size: impl Into<Size>) {
+        self.api().set_inner_size(size.into())
+    }
+
+    pub fn set_position(&self, x: i32, y: i32) {
+        self.api().set_position(x, y)
+    }
+
+    pub fn set_min_inner_size(&self, size: Option<Size>) {
+        self.api().set_min_inner_size(size)
+    }
+
+    pub fn set_max_inner_size(&self, size: Option<Size>) {
+        self.api().set_max_inner_size(size)
+    }
+
+    pub fn set_resizable(&self, resizable: bool) {
+        self.api().set_resizable(resizable)
+    }
+
+    pub fn set_decorations(&self, decorations: bool) {
+        self.api().set_decorations(decorations)
+    }
+
+    pub fn set_always_on_top(&self, always_on_top: bool) {
+        self.api().set_always_on_top(always_on_top)
+    }
+
+    pub fn set_visible(&self, visible: bool) {
+        self.api().set_visible(visible)
+    }
+
+    pub fn set_cursor_grab(&self, mode: CursorGrabMode) {
+        self.api().set_cursor_grab(mode)
+    }
+
+    pub fn set_cursor_visible(&self, visible: bool) {
+        self.api().set_cursor_visible(visible)
+    }
+
+    pub fn set_cursor(&self, cursor: impl Into<Cursor>) {
+        self.api().set_cursor(&cursor.into())
+    }
+
+    pub fn set_icon(&self, icon: Option<&Icon>) {
+        self.api().set_icon(icon)
+    }
+
+    /// Returns all monitors, with the primary monitor first.
+    pub fn monitors(&self) -> Vec<Monitor> {
+        self.api().monitors()
+    }
+
+    /// Returns the primary monitor, or `None` if no monitor is connected.
+    pub fn primary_monitor(&self) -> Option<Monitor> {
+        self.api().monitors().into_iter().next()
+    }
+
+    pub fn current_monitor(&self) -> Option<Monitor> {
+        self.api().current_monitor()
+    }
+
+    pub fn set_fullscreen(&self, fullscreen: Option<Fullscreen>) {
+        self.api().set_fullscreen(fullscreen)
+    }
+
+    pub fn set_text_input(&self, enabled: bool) {
+        self.api().set_text_input(enabled)
     }
 
-    pub fn poll_events(&self) {
-        self.0.poll_events()
+    pub fn set_ime_position(&self, x: i32, y: i32) {
+        self.api().set_ime_position(x, y)
+    }
+
+    pub fn set_drag_and_drop(&self, enabled: bool) {
+        self.api().set_drag_and_drop(enabled)
     }
 
     /// Returns access to the underlying platform specific window.
-    pub fn platform_impl(&self) -> &imp::Window {
-        &self.0
+    ///
+    /// Returns `None` if the window is headless.
+    pub fn platform_impl(&self) -> Option<&imp::Window> {
+        match &self.inner.imp {
+            WindowImpl::Native(window) => Some(window),
+            WindowImpl::Headless(_) => None,
+        }
+    }
+
+    /// Returns access to the underlying headless window.
+    ///
+    /// Returns `None` if the window is not headless.
+    pub fn headless_impl(&self) -> Option<&headless::Window> {
+        match &self.inner.imp {
+            WindowImpl::Headless(window) => Some(window.as_ref()),
+            WindowImpl::Native(_) => None,
+        }
+    }
+
+    fn api(&self) -> &dyn WindowApi {
+        self.inner.api()
+    }
+}
+
+impl WindowInner {
+    fn poll_events(&self, callback: &mut dyn FnMut(Event)) {
+        self.keyboard.borrow_mut().begin_poll();
+        self.mouse.borrow_mut().begin_poll();
+        let mut handle_event = |event: Event| {
+            if matches!(event, Event::CloseRequested) {
+                self.close_requested.set(true);
+            }
+            self.keyboard.borrow_mut().handle_event(&event);
+            self.mouse.borrow_mut().handle_event(&event);
+            callback(event);
+        };
+        self.api().poll_events(&mut handle_event);
+
+        // The queue is taken first, since the callback may inject new events.
+        let injected = std::mem::take(&mut *self.injected.borrow_mut());
+        injected.into_iter().for_each(handle_event);
+    }
+
+    fn api(&self) -> &dyn WindowApi {
+        match &self.imp {
+            WindowImpl::Native(window) => window,
+            WindowImpl::Headless(window) => window.as_ref(),
+        }
     }
 }
 
+/// Headless windows have no native handles, so [`HandleError::Unavailable`] is returned for them.
+impl HasWindowHandle for Window {
+    fn window_handle(&self) -> Result<WindowHandle<'_>, HandleError> {
+        match &self.inner.imp {
+            WindowImpl::Native(window) => window.window_handle(),
+            WindowImpl::Headless(_) => Err(HandleError::Unavailable),
+        }
+    }
+}
+
+impl HasDisplayHandle for Window {
+    fn display_handle(&self) -> Result<DisplayHandle<'_>, HandleError> {
+        match &self.inner.imp {
+            WindowImpl::Native(window) => window.display_handle(),
+            WindowImpl::Headless(_) => Err(HandleError::Unavailable),
+        }
+    }
+}
+
+/// Functionality shared by all window backends.
+///
+/// All sizes are inner sizes, i.e. they exclude the decorations. Sizes and positions reported by the
+/// window are in physical pixels, sizes given to it may be logical and are converted with the current
+/// scale factor.
 pub trait WindowApi {
-    fn show(&self);
-    fn poll_events(&self);
+    /// Makes the window visible.
+    fn show(&self) {
+        self.set_visible(true)
+    }
+
+    fn poll_events(&self, callback: &mut dyn FnMut(Event));
+
+    fn set_title(&self, title: &str);
+
+    /// Returns the factor from logical to physical pixels.
+    ///
+    /// **Note**: X11 has a single scale factor for all monitors, taken from the `Xft.dpi` resource,
+    /// so [`Event::ScaleFactorChanged`] is never emitted there.
+    fn scale_factor(&self) -> f64;
+
+    fn inner_size(&self) -> PhysicalSize;
+
+    fn set_inner_size(&self, size: Size);
+
+    /// Moves the top-left corner of the window to the given position in screen coordinates.
+    ///
+    /// **Note**: Wayland doesn't allow clients to position their windows, so this is ignored.
+    fn set_position(&self, x: i32, y: i32);
+
+    /// Logical sizes are kept logical, i.e. they are converted again when the scale factor changes.
+    fn set_min_inner_size(&self, size: Option<Size>);
+
+    fn set_max_inner_size(&self, size: Option<Size>);
+
+    fn set_resizable(&self, resizable: bool);
+
+    fn set_decorations(&self, decorations: bool);
+
+    /// **Note**: Wayland doesn't allow clients to change their stacking order, so this is ignored.
+    fn set_always_on_top(&self, always_on_top: bool);
+
+    /// **Note**: Wayland doesn't allow clients to hide their windows, so hiding minimizes the window instead.
+    /// Only windows created with [`WindowDesc::visible`] set to `false` stay hidden until they are shown.
+    fn set_visible(&self, visible: bool);
+
+    /// Restricts the cursor to the window. The grab only applies while the window is focused.
+    ///
+    /// **Note**: X11 has no way to lock the cursor, so it is confined and moved back to the center instead.
+    /// On Wayland this requires a compositor that supports the pointer constraints protocol.
+    fn set_cursor_grab(&self, mode: CursorGrabMode);
+
+    /// Shows or hides the cursor while it is over the window.
+    fn set_cursor_visible(&self, visible: bool);
+
+    /// Sets the cursor shown while it is over the window, which is [`CursorIcon::Arrow`] by default.
+    ///
+    /// The cursor is kept while it is hidden, and shown again when it is made visible.
+    ///
+    /// **Note**: Wayland compositors may draw custom cursors at the wrong size on scaled monitors,
+    /// since their images are always attached at a scale of 1.
+    fn set_cursor(&self, cursor: &Cursor);
+
+    /// Sets the icon of the window and its taskbar entry, or restores the platform's default icon.
+    ///
+    /// **Note**: Wayland has no way to set the icon of a window. Compositors show the icon of the
+    /// desktop entry matching the application ID instead.
+    fn set_icon(&self, icon: Option<&Icon>);
+
+    /// Returns all monitors, with the primary monitor first.
+    fn monitors(&self) -> Vec<Monitor>;
+
+    /// Returns the monitor the window is on, or the primary monitor if that is unknown.
+    fn current_monitor(&self) -> Option<Monitor>;
+
+    /// Makes the window fullscreen, or windowed again if `fullscreen` is `None`.
+    ///
+    /// When leaving fullscreen, the window returns to the size and position it had before,
+    /// and the video mode of the monitor is restored.
+    fn set_fullscreen(&self, fullscreen: Option<Fullscreen>);
+
+    /// Enables or disables [`Event::ReceivedCharacter`] and [`Event::Ime`].
+    ///
+    /// Text input is disabled by default. Enable it while a text field has focus,
+    /// since input methods may intercept keys otherwise meant for the application.
+    fn set_text_input(&self, enabled: bool);
+
+    /// Moves the candidate window of the input method next to the given position,
+    /// e.g. the caret of the focused text field, relative to the top-left corner of the window.
+    fn set_ime_position(&self, x: i32, y: i32);
+
+    /// Enables or disables dropping files on the window, reported with [`Event::HoveredFile`],
+    /// [`Event::HoveredFileCancelled`] and [`Event::DroppedFile`].
+    fn set_drag_and_drop(&self, enabled: bool);
+}
+
+/// The reason a window couldn't be created.
+#[derive(Debug, Clone, Copy, PartialEq, Eq)]
+pub enum WindowError {
+    /// The libraries of the display server couldn't be loaded.
+    NotSupported,
+
+    /// The connection to the display server couldn't be established, or was lost during creation.
+    ConnectionFailed,
+
+    /// The display server refused to create the window.
+    CreationFailed,
+
+    /// The platform reported an error that doesn't match any of the other variants.
+    Unknown,
+}
+
+/// Describes how a window is created.
+///
+/// Apart from `transparent`, everything can be changed after creation through the setters on [`Window`].
+#[derive(Debug, Clone, Copy, PartialEq)]
+pub struct WindowDesc<'a> {
+    /// The title of the window.
+    pub title: &'a str,
+
+    /// The inner size of the window, logical sizes are scaled for the monitor the window is created on.
+    pub size: Size,
+
+    /// The position of the window in screen coordinates. If `None` the platform chooses.
+    pub position: Option<(i32, i32)>,
+
+    /// The minimum inner size of the window.
+    pub min_size: Option<Size>,
+
+    /// The maximum inner size of the window.
+    pub max_size: Option<Size>,
+
+    /// Whether the user may resize the window.
+    pub resizable: bool,
+
+    /// Whether the window has a title bar and borders.
+    pub decorations: bool,
+
+    /// Whether the window is kept above all other windows.
+    pub always_on_top: bool,
+
+    /// Whether the alpha channel of the window's contents is composited with what is behind it.
+    pub transparent: bool,
+
+    /// Whether the window is visible after creation.
+    pub visible: bool,
+
+    /// Whether files may be dropped on the window, see [`Window::set_drag_and_drop()`].
+    pub drag_and_drop: bool,
+
+    /// The icon of the window. If `None` the platform's default icon is used.
+    pub icon: Option<&'a Icon>,
+}
+
+impl<'a> Default for WindowDesc<'a> {
+    fn default() -> Self {
+        Self {
+            title: "iglo",
+            size: LogicalSize::new(640.0, 480.0).into(),
+            position: None,
+            min_size: None,
+            max_size: None,
+            resizable: true,
+            decorations: true,
+            always_on_top: false,
+            transparent: false,
+            visible: false,
+            drag_and_drop: false,
+            icon: None,
+        }
+    }
 }
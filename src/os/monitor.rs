@@ -0,0 +1,84 @@
+/// A display mode a monitor supports.
+#[derive(Debug, Clone, Copy, PartialEq, Eq, Hash)]
+pub struct VideoMode {
+    /// The resolution in pixels.
+    pub size: (u32, u32),
+
+    /// The number of bits per pixel, including all color channels.
+    pub bit_depth: u16,
+
+    /// The refresh rate in millihertz, e.g. 59940 for 59.94 Hz.
+    pub refresh_rate_millihertz: u32,
+}
+
+/// A monitor connected to the system, see [`Window::monitors()`](super::Window::monitors).
+///
+/// This is a snapshot, it isn't updated when the monitor changes.
+#[derive(Debug, Clone, PartialEq)]
+pub struct Monitor {
+    /// A human readable name, e.g. the name of the connector like `DP-1`, or the model.
+    pub name: String,
+
+    /// The position of the top-left corner in screen coordinates.
+    ///
+    /// **Note**: Wayland compositors may hide the layout of the monitors and report `(0, 0)` for all of them.
+    pub position: (i32, i32),
+
+    /// The size of the current video mode in pixels.
+    pub size: (u32, u32),
+
+    /// The size of the visible area in millimeters, `(0, 0)` if unknown, e.g. for projectors.
+    pub physical_size: (u32, u32),
+
+    /// The factor by which content should be scaled to appear at its intended size.
+    pub scale_factor: f64,
+
+    /// The current video mode.
+    pub video_mode: VideoMode,
+
+    /// All video modes the monitor supports, with the largest and fastest first.
+    pub video_modes: Vec<VideoMode>,
+
+    /// Identifies the monitor to the platform.
+    pub(super) handle: u64,
+}
+
+impl Monitor {
+    /// Returns whether `point` in screen coordinates lies on the monitor.
+    #[cfg(target_os = "linux")]
+    pub(super) fn contains(&self, (x, y): (i32, i32)) -> bool {
+        let (left, top) = self.position;
+        x >= left && y >= top && x < left + self.size.0 as i32 && y < top + self.size.1 as i32
+    }
+}
+
+/// How a window covers a monitor, see [`Window::set_fullscreen()`](super::Window::set_fullscreen).
+#[derive(Debug, Clone, PartialEq)]
+pub enum Fullscreen {
+    /// The window covers the monitor without changing its video mode.
+    ///
+    /// If no monitor is given, the one the window is on is used.
+    Borderless(Option<Monitor>),
+
+    /// The monitor switches to the video mode while the window is fullscreen.
+    ///
+    /// The video mode must be one of the modes in [`Monitor::video_modes`].
+    ///
+    /// **Note**: Wayland doesn't allow clients to change video modes, so this behaves like
+    /// [`Fullscreen::Borderless`] there.
+    Exclusive(Monitor, VideoMode),
+}
+
+/// Orders video modes the way [`Monitor::video_modes`] promises and removes duplicates.
+pub(super) fn sort_video_modes(modes: &mut Vec<VideoMode>) {
+    let key = |mode: &VideoMode| {
+        (
+            mode.size.0 as u64 * mode.size.1 as u64,
+            mode.size.0,
+            mode.refresh_rate_millihertz,
+            mode.bit_depth,
+        )
+    };
+    modes.sort_by_key(|mode| std::cmp::Reverse(key(mode)));
+    modes.dedup();
+}
@@ -0,0 +1,214 @@
+use std::collections::HashSet;
+
+use super::{ElementState, Event, ImageData, MouseButton};
+
+/// How the cursor is restricted to a window, see [`Window::set_cursor_grab()`](super::Window::set_cursor_grab).
+#[derive(Debug, Default, Clone, Copy, PartialEq, Eq)]
+pub enum CursorGrabMode {
+    /// The cursor moves freely.
+    #[default]
+    None,
+
+    /// The cursor can't leave the window.
+    Confined,
+
+    /// The cursor stays in place, e.g. for FPS-style cameras.
+    ///
+    /// The cursor doesn't move, so [`Event::CursorMoved`] isn't emitted. Use [`Event::MouseMotion`] instead.
+    Locked,
+}
+
+/// A standard cursor shape, drawn with the cursor theme of the system.
+#[derive(Debug, Default, Clone, Copy, PartialEq, Eq, Hash)]
+pub enum CursorIcon {
+    #[default]
+    Arrow,
+
+    /// A text cursor, for selecting and editing text.
+    Text,
+
+    /// A pointing hand, for links and other clickable elements.
+    Hand,
+    Crosshair,
+
+    /// The application is busy and can't be interacted with.
+    Wait,
+
+    /// The application is busy, but can still be interacted with.
+    Progress,
+
+    /// Resizing from the left or right edge.
+    ResizeHorizontal,
+
+    /// Resizing from the top or bottom edge.
+    ResizeVertical,
+
+    /// Resizing from the top-left or bottom-right corner.
+    ResizeTopLeft,
+
+    /// Resizing from the top-right or bottom-left corner.
+    ResizeTopRight,
+
+    /// Moving something in any direction.
+    Move,
+
+    /// The action under the cursor isn't allowed.
+    NotAllowed,
+}
+
+/// A cursor drawn from an image.
+#[derive(Debug, Clone, PartialEq, Eq, Hash)]
+pub struct CustomCursor {
+    image: ImageData,
+    hotspot: (u32, u32),
+}
+
+impl CustomCursor {
+    /// Creates a cursor from an image.
+    ///
+    /// Returns `None` if the image is empty, its data doesn't match its size,
+    /// or the hotspot is outside of it.
+    ///
+    /// # Arguments
+    ///
+    /// - `image` - The image of the cursor, in physical pixels. Large images may be scaled down by the platform.
+    /// - `hotspot` - The pixel of the image at the position of the cursor, relative to its top-left corner.
+    pub fn new(image: ImageData, hotspot: (u32, u32)) -> Option<Self> {
+        if !image.is_valid() || hotspot.0 >= image.width || hotspot.1 >= image.height {
+            return None;
+        }
+
+        Some(Self { image, hotspot })
+    }
+
+    pub fn image(&self) -> &ImageData {
+        &self.image
+    }
+
+    pub fn hotspot(&self) -> (u32, u32) {
+        self.hotspot
+    }
+
+    /// Returns the pixels as 32-bit ARGB with premultiplied alpha, the format of X11 and Wayland cursors.
+    #[cfg(target_os = "linux")]
+    pub(crate) fn premultiplied_argb(&self) -> impl Iterator<Item = u32> + '_ {
+        self.image.rgba.chunks_exact(4).map(|pixel| {
+            let alpha = pixel[3] as u32;
+            let premultiply = |channel: u8| (channel as u32 * alpha + 127) / 255;
+            alpha << 24
+                | premultiply(pixel[0]) << 16
+                | premultiply(pixel[1]) << 8
+                | premultiply(pixel[2])
+        })
+    }
+}
+
+/// The cursor shown while it is over a window, see [`Window::set_cursor()`](super::Window::set_cursor).
+#[derive(Debug, Clone, PartialEq, Eq, Hash)]
+pub enum Cursor {
+    Icon(CursorIcon),
+    Custom(CustomCursor),
+}
+
+impl Default for Cursor {
+    fn default() -> Self {
+        Self::Icon(CursorIcon::default())
+    }
+}
+
+impl From<CursorIcon> for Cursor {
+    fn from(icon: CursorIcon) -> Self {
+        Self::Icon(icon)
+    }
+}
+
+impl From<CustomCursor> for Cursor {
+    fn from(cursor: CustomCursor) -> Self {
+        Self::Custom(cursor)
+    }
+}
+
+/// The state of the mouse as seen by a window, updated by [`Window::poll_events()`](super::Window::poll_events).
+///
+/// Like [`KeyboardState`](super::KeyboardState), "pressed", "released" and the deltas refer to
+/// the events delivered by the most recent call to `poll_events`.
+#[derive(Debug, Default)]
+pub struct MouseState {
+    position: Option<(f64, f64)>,
+    down: HashSet<MouseButton>,
+    pressed: HashSet<MouseButton>,
+    released: HashSet<MouseButton>,
+    wheel_delta: (f32, f32),
+    motion_delta: (f64, f64),
+}
+
+impl MouseState {
+    /// Returns the position of the cursor relative to the top-left corner of the window.
+    ///
+    /// Returns `None` if the cursor is outside of the window.
+    pub fn position(&self) -> Option<(f64, f64)> {
+        self.position
+    }
+
+    /// Returns whether `button` is held down.
+    pub fn is_down(&self, button: MouseButton) -> bool {
+        self.down.contains(&button)
+    }
+
+    /// Returns whether `button` went down during the last poll.
+    pub fn was_pressed(&self, button: MouseButton) -> bool {
+        self.pressed.contains(&button)
+    }
+
+    /// Returns whether `button` went up during the last poll.
+    pub fn was_released(&self, button: MouseButton) -> bool {
+        self.released.contains(&button)
+    }
+
+    /// Returns the sum of all wheel deltas of the last poll, see [`Event::MouseWheel`].
+    pub fn wheel_delta(&self) -> (f32, f32) {
+        self.wheel_delta
+    }
+
+    /// Returns the sum of all raw motion of the last poll, see [`Event::MouseMotion`].
+    pub fn motion_delta(&self) -> (f64, f64) {
+        self.motion_delta
+    }
+
+    /// Forgets the presses, releases and deltas of the previous poll.
+    pub(super) fn begin_poll(&mut self) {
+        self.pressed.clear();
+        self.released.clear();
+        self.wheel_delta = (0.0, 0.0);
+        self.motion_delta = (0.0, 0.0);
+    }
+
+    pub(super) fn handle_event(&mut self, event: &Event) {
+        match *event {
+            Event::CursorMoved { x, y } => self.position = Some((x, y)),
+            Event::CursorLeft => self.position = None,
+            Event::MouseInput { button, state } => match state {
+                ElementState::Pressed => {
+                    self.down.insert(button);
+                    self.pressed.insert(button);
+                }
+                ElementState::Released => {
+                    self.down.remove(&button);
+                    self.released.insert(button);
+                }
+            },
+            Event::MouseWheel { delta_x, delta_y } => {
+                self.wheel_delta.0 += delta_x;
+                self.wheel_delta.1 += delta_y;
+            }
+            Event::MouseMotion { delta_x, delta_y } => {
+                self.motion_delta.0 += delta_x;
+                self.motion_delta.1 += delta_y;
+            }
+
+            // Like keys, buttons released while unfocused are never reported.
+            Event::Focused(false) => self.released.extend(self.down.drain()),
+            _ => {}
+        }
+    }
+}
@@ -0,0 +1,830 @@
+//! Recording the events of windows to a compact binary format, and replaying them.
+//!
+//! A recording starts with a header of the magic bytes `IGLOREC\0` and the [`Version`] of the format.
+//! It is followed by records of a tag byte, the time since the previous record in microseconds and
+//! the data of the event. Integers are stored as LEB128 varints, signed ones zigzag encoded, floats as
+//! little endian bytes and strings as their length followed by UTF-8.
+//!
+//! Paths start with a byte for their encoding. Paths that are valid Unicode are stored as UTF-8, so they
+//! replay on every platform. Others are stored as their raw bytes on Unix and as UTF-16 on Windows, which are
+//! only replayed losslessly on the kind of platform they were recorded on.
+//!
+//! Besides events, the end of every frame, i.e. every poll of the recorded windows, is recorded.
+//! Replaying delivers one recorded frame per frame of the application, which makes the replay independent
+//! of how fast the application runs.
+
+use std::{
+    ffi::OsString,
+    io::{self, Read, Write},
+    path::{Path, PathBuf},
+    time::{Duration, Instant},
+};
+
+use super::{ElementState, Event, Ime, KeyCode, Modifiers, MouseButton, ScanCode, Window};
+use crate::Version;
+
+const MAGIC: [u8; 8] = *b"IGLOREC\0";
+
+/// The version of the format written by [`Recorder`].
+///
+/// Recordings with another major version, or a newer minor version, can't be replayed.
+pub const RECORDING_VERSION: Version = Version::new(1, 0, 0);
+
+const TAG_END_OF_FRAME: u8 = 0xff;
+
+/// The encodings of paths, see the [module documentation](self).
+const PATH_UTF8: u8 = 0;
+const PATH_UNIX: u8 = 1;
+const PATH_WINDOWS: u8 = 2;
+
+#[derive(Debug, Clone, Copy, PartialEq, Eq)]
+pub enum RecordingError {
+    /// Reading or writing the recording failed.
+    Io(io::ErrorKind),
+
+    /// The recording was written in a format version that can't be read, see [`RECORDING_VERSION`].
+    UnsupportedVersion(Version),
+
+    /// The data isn't a recording, or it is corrupt.
+    InvalidFormat,
+}
+
+impl From<io::Error> for RecordingError {
+    fn from(error: io::Error) -> Self {
+        Self::Io(error.kind())
+    }
+}
+
+/// An event of a recording, with the time it was recorded at.
+#[derive(Debug, Clone, PartialEq)]
+pub struct RecordedEvent {
+    /// The time since the recording started.
+    pub time: Duration,
+    pub event: Event,
+}
+
+/// Records events to a writer, e.g. a [`File`](std::fs::File).
+///
+/// Every frame is flushed once it ends, so a recording stays usable when the application crashes.
+/// Wrap unbuffered writers in a [`BufWriter`](std::io::BufWriter), to write every frame at once.
+///
+/// **Note**: A recording doesn't store which window an event was delivered to, because a
+/// [`WindowId`](super::WindowId) is only unique within a run of the application. Record the events of
+/// each window separately to replay applications with multiple windows.
+pub struct Recorder<W: Write> {
+    writer: W,
+    start: Instant,
+
+    /// The time of the last record, relative to `start`.
+    last_time: Duration,
+}
+
+impl<W: Write> Recorder<W> {
+    /// Starts a recording by writing the header.
+    ///
+    /// # Arguments
+    ///
+    /// - `writer` - Where the recording is written to.
+    pub fn new(mut writer: W) -> Result<Self, RecordingError> {
+        writer.write_all(&MAGIC)?;
+        writer.write_all(&[RECORDING_VERSION.major, RECORDING_VERSION.minor])?;
+        writer.write_all(&RECORDING_VERSION.patch.to_le_bytes())?;
+
+        Ok(Self {
+            writer,
+            start: Instant::now(),
+            last_time: Duration::ZERO,
+        })
+    }
+
+    /// Polls the events of a window like [`Window::poll_events()`], records them and ends the frame.
+    ///
+    /// # Arguments
+    ///
+    /// - `window` - The window to poll.
+    /// - `callback` - Receives the events, after they have been recorded.
+    pub fn poll_events<F: FnMut(Event)>(
+        &mut self,
+        window: &Window,
+        mut callback: F,
+    ) -> Result<(), RecordingError> {
+        let mut events = Vec::new();
+        window.poll_events(|event| events.push(event));
+
+        for event in &events {
+            self.record(event)?;
+        }
+        self.end_frame()?;
+
+        events.into_iter().for_each(&mut callback);
+        Ok(())
+    }
+
+    /// Records an event, e.g. one delivered by [`EventLoop::poll_events()`](super::EventLoop::poll_events).
+    ///
+    /// The [`WindowId`](super::WindowId) of the event isn't recorded, see [`Recorder`].
+    ///
+    /// # Arguments
+    ///
+    /// - `event` - The event to record.
+    pub fn record(&mut self, event: &Event) -> Result<(), RecordingError> {
+        let mut record = Vec::new();
+        encode_event(&mut record, event);
+        self.write_record(record[0], &record[1..])
+    }
+
+    /// Records the end of a frame, after all events polled in it were recorded.
+    pub fn end_frame(&mut self) -> Result<(), RecordingError> {
+        self.write_record(TAG_END_OF_FRAME, &[])?;
+        self.writer.flush()?;
+        Ok(())
+    }
+
+    /// Ends the recording and returns the writer.
+    pub fn finish(mut self) -> Result<W, RecordingError> {
+        self.writer.flush()?;
+        Ok(self.writer)
+    }
+
+    fn write_record(&mut self, tag: u8, data: &[u8]) -> Result<(), RecordingError> {
+        let time = self.start.elapsed();
+        let delta = (time - self.last_time).as_micros() as u64;
+
+        // The time is advanced by whole microseconds, so rounding errors don't add up.
+        self.last_time += Duration::from_micros(delta);
+
+        let mut header = vec![tag];
+        write_varint(&mut header, delta);
+        self.writer.write_all(&header)?;
+        self.writer.write_all(data)?;
+        Ok(())
+    }
+}
+
+/// Replays a recording made by [`Recorder`].
+pub struct Replay<R: Read> {
+    reader: R,
+    version: Version,
+    time: Duration,
+}
+
+impl<R: Read> Replay<R> {
+    /// Reads the header of a recording.
+    ///
+    /// Returns [`RecordingError::UnsupportedVersion`] if the format version can't be read.
+    ///
+    /// # Arguments
+    ///
+    /// - `reader` - Where the recording is read from.
+    pub fn new(mut reader: R) -> Result<Self, RecordingError> {
+        let mut header = [0; 12];
+        match reader.read_exact(&mut header) {
+            Ok(()) => (),
+            Err(error) if error.kind() == io::ErrorKind::UnexpectedEof => {
+                return Err(RecordingError::InvalidFormat)
+            }
+            Err(error) => return Err(error.into()),
+        }
+
+        if header[..8] != MAGIC {
+            return Err(RecordingError::InvalidFormat);
+        }
+
+        let version = Version::new(
+            header[8],
+            header[9],
+            u16::from_le_bytes([header[10], header[11]]),
+        );
+        if version.major != RECORDING_VERSION.major || version.minor > RECORDING_VERSION.minor {
+            return Err(RecordingError::UnsupportedVersion(version));
+        }
+
+        Ok(Self {
+            reader,
+            version,
+            time: Duration::ZERO,
+        })
+    }
+
+    /// Returns the format version the recording was written in.
+    pub fn version(&self) -> Version {
+        self.version
+    }
+
+    /// Reads the events of the next recorded frame.
+    ///
+    /// Returns `None` once the recording has ended. Recordings that were cut off, e.g. because the
+    /// application crashed, end with the last complete frame.
+    pub fn next_frame(&mut self) -> Result<Option<Vec<RecordedEvent>>, RecordingError> {
+        match self.read_frame() {
+            Ok(events) => Ok(Some(events)),
+            Err(RecordingError::Io(io::ErrorKind::UnexpectedEof)) => Ok(None),
+            Err(error) => Err(error),
+        }
+    }
+
+    fn read_frame(&mut self) -> Result<Vec<RecordedEvent>, RecordingError> {
+        let mut reader = Reader(&mut self.reader);
+        let mut events = Vec::new();
+
+        loop {
+            let tag = reader.u8()?;
+            self.time += Duration::from_micros(reader.varint()?);
+
+            if tag == TAG_END_OF_FRAME {
+                return Ok(events);
+            }
+
+            let event = decode_event(tag, &mut reader)?;
+            events.push(RecordedEvent {
+                time: self.time,
+                event,
+            });
+        }
+    }
+
+    /// Injects the events of the next recorded frame into a window,
+    /// so they are delivered by its next call to [`Window::poll_events()`].
+    ///
+    /// Returns `false` once the recording has ended.
+    ///
+    /// # Arguments
+    ///
+    /// - `window` - The window to replay the events on. Headless windows only see the recorded events,
+    ///   native windows see them in addition to their real events.
+    pub fn inject_next_frame(&mut self, window: &Window) -> Result<bool, RecordingError> {
+        match self.next_frame()? {
+            Some(events) => {
+                for recorded in events {
+                    window.inject_event(recorded.event);
+                }
+                Ok(true)
+            }
+            None => Ok(false),
+        }
+    }
+}
+
+fn encode_event(out: &mut Vec<u8>, event: &Event) {
+    let bool = |value: bool| value as u8;
+    let state = |state: &ElementState| match state {
+        ElementState::Pressed => 0,
+        ElementState::Released => 1,
+    };
+
+    match event {
+        Event::CloseRequested => out.push(0),
+        Event::Resized { width, height } => {
+            out.push(1);
+            write_varint(out, *width as u64);
+            write_varint(out, *height as u64);
+        }
+        Event::Moved { x, y } => {
+            out.push(2);
+            write_signed(out, *x as i64);
+            write_signed(out, *y as i64);
+        }
+        Event::Focused(focused) => out.extend([3, bool(*focused)]),
+        Event::Minimized(minimized) => out.extend([4, bool(*minimized)]),
+        Event::ScaleFactorChanged { scale_factor } => {
+            out.push(5);
+            out.extend(scale_factor.to_le_bytes());
+        }
+        Event::KeyboardInput {
+            scancode,
+            keycode,
+            state: key_state,
+            modifiers,
+            repeat,
+        } => {
+            out.push(6);
+            write_varint(out, scancode.0 as u64);
+            out.push(keycode.map_or(u8::MAX, |key| key as u8));
+            out.extend([state(key_state), modifiers.bits(), bool(*repeat)]);
+        }
+        Event::ReceivedCharacter(c) => {
+            out.push(7);
+            write_varint(out, *c as u64);
+        }
+        Event::Ime(Ime::Preedit { text, cursor }) => {
+            out.push(8);
+            write_string(out, text);
+            write_varint(out, cursor.map_or(0, |cursor| cursor as u64 + 1));
+        }
+        Event::Ime(Ime::Commit(text)) => {
+            out.push(9);
+            write_string(out, text);
+        }
+        Event::CursorMoved { x, y } => {
+            out.push(10);
+            out.extend(x.to_le_bytes());
+            out.extend(y.to_le_bytes());
+        }
+        Event::CursorEntered => out.push(11),
+        Event::CursorLeft => out.push(12),
+        Event::MouseInput {
+            button,
+            state: button_state,
+        } => {
+            out.push(13);
+            match button {
+                MouseButton::Left => out.push(0),
+                MouseButton::Right => out.push(1),
+                MouseButton::Middle => out.push(2),
+                MouseButton::Back => out.push(3),
+                MouseButton::Forward => out.push(4),
+                MouseButton::Other(button) => {
+                    out.push(5);
+                    write_varint(out, *button as u64);
+                }
+            }
+            out.push(state(button_state));
+        }
+        Event::MouseWheel { delta_x, delta_y } => {
+            out.push(14);
+            out.extend(delta_x.to_le_bytes());
+            out.extend(delta_y.to_le_bytes());
+        }
+        Event::MouseMotion { delta_x, delta_y } => {
+            out.push(15);
+            out.extend(delta_x.to_le_bytes());
+            out.extend(delta_y.to_le_bytes());
+        }
+        Event::HoveredFile { path, x, y } => {
+            out.push(16);
+            write_path(out, path);
+            out.extend(x.to_le_bytes());
+            out.extend(y.to_le_bytes());
+        }
+        Event::HoveredFileCancelled => out.push(17),
+        Event::DroppedFile { path, x, y } => {
+            out.push(18);
+            write_path(out, path);
+            out.extend(x.to_le_bytes());
+            out.extend(y.to_le_bytes());
+        }
+    }
+}
+
+fn decode_event<R: Read>(tag: u8, reader: &mut Reader<R>) -> Result<Event, RecordingError> {
+    let event = match tag {
+        0 => Event::CloseRequested,
+        1 => Event::Resized {
+            width: reader.varint()? as u32,
+            height: reader.varint()? as u32,
+        },
+        2 => Event::Moved {
+            x: reader.signed()? as i32,
+            y: reader.signed()? as i32,
+        },
+        3 => Event::Focused(reader.bool()?),
+        4 => Event::Minimized(reader.bool()?),
+        5 => Event::ScaleFactorChanged {
+            scale_factor: reader.f64()?,
+        },
+        6 => Event::KeyboardInput {
+            scancode: ScanCode(reader.varint()? as u32),
+            keycode: match reader.u8()? {
+                u8::MAX => None,
+                key => Some(
+                    *KeyCode::ALL
+                        .get(key as usize)
+                        .ok_or(RecordingError::InvalidFormat)?,
+                ),
+            },
+            state: reader.state()?,
+            modifiers: Modifiers::from_bits(reader.u8()?).ok_or(RecordingError::InvalidFormat)?,
+            repeat: reader.bool()?,
+        },
+        7 => Event::ReceivedCharacter(
+            u32::try_from(reader.varint()?)
+                .ok()
+                .and_then(char::from_u32)
+                .ok_or(RecordingError::InvalidFormat)?,
+        ),
+        8 => Event::Ime(Ime::Preedit {
+            text: reader.string()?,
+            cursor: reader
+                .varint()?
+                .checked_sub(1)
+                .map(|cursor| cursor as usize),
+        }),
+        9 => Event::Ime(Ime::Commit(reader.string()?)),
+        10 => Event::CursorMoved {
+            x: reader.f64()?,
+            y: reader.f64()?,
+        },
+        11 => Event::CursorEntered,
+        12 => Event::CursorLeft,
+        13 => Event::MouseInput {
+            button: match reader.u8()? {
+                0 => MouseButton::Left,
+                1 => MouseButton::Right,
+                2 => MouseButton::Middle,
+                3 => MouseButton::Back,
+                4 => MouseButton::Forward,
+                5 => MouseButton::Other(reader.varint()? as u16),
+                _ => return Err(RecordingError::InvalidFormat),
+            },
+            state: reader.state()?,
+        },
+        14 => Event::MouseWheel {
+            delta_x: reader.f32()?,
+            delta_y: reader.f32()?,
+        },
+        15 => Event::MouseMotion {
+            delta_x: reader.f64()?,
+            delta_y: reader.f64()?,
+        },
+        16 => Event::HoveredFile {
+            path: reader.path()?,
+            x: reader.f64()?,
+            y: reader.f64()?,
+        },
+        17 => Event::HoveredFileCancelled,
+        18 => Event::DroppedFile {
+            path: reader.path()?,
+            x: reader.f64()?,
+            y: reader.f64()?,
+        },
+        _ => return Err(RecordingError::InvalidFormat),
+    };
+    Ok(event)
+}
+
+fn write_varint(out: &mut Vec<u8>, mut value: u64) {
+    while value >= 0x80 {
+        out.push(value as u8 | 0x80);
+        value >>= 7;
+    }
+    out.push(value as u8);
+}
+
+fn write_signed(out: &mut Vec<u8>, value: i64) {
+    write_varint(out, ((value << 1) ^ (value >> 63)) as u64);
+}
+
+fn write_bytes(out: &mut Vec<u8>, value: &[u8]) {
+    write_varint(out, value.len() as u64);
+    out.extend_from_slice(value);
+}
+
+fn write_string(out: &mut Vec<u8>, value: &str) {
+    write_bytes(out, value.as_bytes());
+}
+
+fn write_path(out: &mut Vec<u8>, path: &Path) {
+    match path.to_str() {
+        Some(path) => {
+            out.push(PATH_UTF8);
+            write_string(out, path);
+        }
+        #[cfg(unix)]
+        None => {
+            use std::os::unix::ffi::OsStrExt;
+
+            out.push(PATH_UNIX);
+            write_bytes(out, path.as_os_str().as_bytes());
+        }
+        #[cfg(windows)]
+        None => {
+            use std::os::windows::ffi::OsStrExt;
+
+            let bytes: Vec<u8> = path
+                .as_os_str()
+                .encode_wide()
+                .flat_map(u16::to_le_bytes)
+                .collect();
+            out.push(PATH_WINDOWS);
+            write_bytes(out, &bytes);
+        }
+        #[cfg(not(any(unix, windows)))]
+        None => {
+            out.push(PATH_UTF8);
+            write_string(out, &path.to_string_lossy());
+        }
+    }
+}
+
+/// Returns a path stored as raw bytes, which only other platforms than Unix decode lossily.
+fn unix_path(bytes: Vec<u8>) -> PathBuf {
+    #[cfg(unix)]
+    let path = <OsString as std::os::unix::ffi::OsStringExt>::from_vec(bytes);
+    #[cfg(not(unix))]
+    let path = OsString::from(String::from_utf8_lossy(&bytes).into_owned());
+    PathBuf::from(path)
+}
+
+/// Returns a path stored as UTF-16, which only other platforms than Windows decode lossily.
+fn windows_path(wide: &[u16]) -> PathBuf {
+    #[cfg(windows)]
+    let path = <OsString as std::os::windows::ffi::OsStringExt>::from_wide(wide);
+    #[cfg(not(windows))]
+    let path = OsString::from(String::from_utf16_lossy(wide));
+    PathBuf::from(path)
+}
+
+/// Reads the values of records. Reading past the end fails with [`io::ErrorKind::UnexpectedEof`].
+struct Reader<'a, R: Read>(&'a mut R);
+
+impl<'a, R: Read> Reader<'a, R> {
+    fn bytes<const N: usize>(&mut self) -> Result<[u8; N], RecordingError> {
+        let mut bytes = [0; N];
+        self.0.read_exact(&mut bytes)?;
+        Ok(bytes)
+    }
+
+    fn u8(&mut self) -> Result<u8, RecordingError> {
+        Ok(self.bytes::<1>()?[0])
+    }
+
+    fn bool(&mut self) -> Result<bool, RecordingError> {
+        match self.u8()? {
+            0 => Ok(false),
+            1 => Ok(true),
+            _ => Err(RecordingError::InvalidFormat),
+        }
+    }
+
+    fn state(&mut self) -> Result<ElementState, RecordingError> {
+        match self.u8()? {
+            0 => Ok(ElementState::Pressed),
+            1 => Ok(ElementState::Released),
+            _ => Err(RecordingError::InvalidFormat),
+        }
+    }
+
+    fn varint(&mut self) -> Result<u64, RecordingError> {
+        let mut value = 0;
+        for shift in (0..64).step_by(7) {
+            let byte = self.u8()?;
+            value |= ((byte & 0x7f) as u64) << shift;
+            if byte & 0x80 == 0 {
+                return Ok(value);
+            }
+        }
+        Err(RecordingError::InvalidFormat)
+    }
+
+    fn signed(&mut self) -> Result<i64, RecordingError> {
+        let value = self.varint()?;
+        Ok((value >> 1) as i64 ^ -((value & 1) as i64))
+    }
+
+    fn f32(&mut self) -> Result<f32, RecordingError> {
+        Ok(f32::from_le_bytes(self.bytes()?))
+    }
+
+    fn f64(&mut self) -> Result<f64, RecordingError> {
+        Ok(f64::from_le_bytes(self.bytes()?))
+    }
+
+    fn byte_vec(&mut self) -> Result<Vec<u8>, RecordingError> {
+        let len = self.varint()?;
+
+        // The length is checked by reading, a corrupt length must not allocate a huge buffer up front.
+        let mut bytes = Vec::new();
+        (&mut *self.0).take(len).read_to_end(&mut bytes)?;
+        if bytes.len() as u64 != len {
+            return Err(RecordingError::Io(io::ErrorKind::UnexpectedEof));
+        }
+        Ok(bytes)
+    }
+
+    fn string(&mut self) -> Result<String, RecordingError> {
+        String::from_utf8(self.byte_vec()?).map_err(|_| RecordingError::InvalidFormat)
+    }
+
+    fn path(&mut self) -> Result<PathBuf, RecordingError> {
+        match self.u8()? {
+            PATH_UTF8 => Ok(PathBuf::from(self.string()?)),
+            PATH_UNIX => Ok(unix_path(self.byte_vec()?)),
+            PATH_WINDOWS => {
+                let bytes = self.byte_vec()?;
+                if bytes.len() % 2 != 0 {
+                    return Err(RecordingError::InvalidFormat);
+                }
+
+                let wide: Vec<u16> = bytes
+                    .chunks_exact(2)
+                    .map(|unit| u16::from_le_bytes([unit[0], unit[1]]))
+                    .collect();
+                Ok(windows_path(&wide))
+            }
+            _ => Err(RecordingError::InvalidFormat),
+        }
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    fn events() -> Vec<Event> {
+        vec![
+            Event::CloseRequested,
+            Event::Resized {
+                width: 1280,
+                height: 720,
+            },
+            Event::Moved { x: -1920, y: 40 },
+            Event::Focused(true),
+            Event::Minimized(false),
+            Event::ScaleFactorChanged { scale_factor: 1.5 },
+            Event::KeyboardInput {
+                scancode: ScanCode(0x1e),
+                keycode: Some(KeyCode::NumpadEnter),
+                state: ElementState::Pressed,
+                modifiers: Modifiers::SHIFT | Modifiers::NUM_LOCK,
+                repeat: true,
+            },
+            Event::KeyboardInput {
+                scancode: ScanCode(300),
+                keycode: None,
+                state: ElementState::Released,
+                modifiers: Modifiers::empty(),
+                repeat: false,
+            },
+            Event::ReceivedCharacter('\u{1f600}'),
+            Event::Ime(Ime::Preedit {
+                text: "にほん".to_string(),
+                cursor: Some(2),
+            }),
+            Event::Ime(Ime::Preedit {
+                text: String::new(),
+                cursor: None,
+            }),
+            Event::Ime(Ime::Commit("日本".to_string())),
+            Event::CursorMoved { x: 10.25, y: -3.5 },
+            Event::CursorEntered,
+            Event::CursorLeft,
+            Event::MouseInput {
+                button: MouseButton::Other(1000),
+                state: ElementState::Pressed,
+            },
+            Event::MouseInput {
+                button: MouseButton::Back,
+                state: ElementState::Released,
+            },
+            Event::MouseWheel {
+                delta_x: 0.0,
+                delta_y: -1.0,
+            },
+            Event::MouseMotion {
+                delta_x: 2.0,
+                delta_y: 0.5,
+            },
+            Event::HoveredFile {
+                path: PathBuf::from("/tmp/image.png"),
+                x: 1.0,
+                y: 2.0,
+            },
+            Event::HoveredFileCancelled,
+            Event::DroppedFile {
+                path: PathBuf::from("/tmp/image.png"),
+                x: 3.0,
+                y: 4.0,
+            },
+        ]
+    }
+
+    fn record(frames: &[Vec<Event>]) -> Vec<u8> {
+        let mut recorder = Recorder::new(Vec::new()).unwrap();
+        for frame in frames {
+            for event in frame {
+                recorder.record(event).unwrap();
+            }
+            recorder.end_frame().unwrap();
+        }
+        recorder.finish().unwrap()
+    }
+
+    fn replay(data: &[u8]) -> Result<Vec<Vec<Event>>, RecordingError> {
+        let mut replay = Replay::new(data)?;
+        let mut frames = Vec::new();
+        while let Some(frame) = replay.next_frame()? {
+            frames.push(frame.into_iter().map(|recorded| recorded.event).collect());
+        }
+        Ok(frames)
+    }
+
+    #[test]
+    fn round_trip() {
+        let frames = vec![events(), Vec::new(), vec![Event::CursorLeft]];
+        let data = record(&frames);
+
+        assert_eq!(Replay::new(&data[..]).unwrap().version(), RECORDING_VERSION);
+        assert_eq!(replay(&data).unwrap(), frames);
+    }
+
+    #[test]
+    fn round_trip_key_codes() {
+        let frame = KeyCode::ALL
+            .iter()
+            .map(|&key| Event::KeyboardInput {
+                scancode: ScanCode(key as u32),
+                keycode: Some(key),
+                state: ElementState::Pressed,
+                modifiers: Modifiers::empty(),
+                repeat: false,
+            })
+            .collect::<Vec<_>>();
+
+        let frames = vec![frame];
+        assert_eq!(replay(&record(&frames)).unwrap(), frames);
+    }
+
+    #[test]
+    #[cfg(unix)]
+    fn round_trip_non_utf8_path() {
+        use std::os::unix::ffi::OsStringExt;
+
+        let path = PathBuf::from(OsString::from_vec(b"/tmp/\xff\xfe.png".to_vec()));
+        let frames = vec![vec![
+            Event::HoveredFile {
+                path: path.clone(),
+                x: 1.0,
+                y: 2.0,
+            },
+            Event::DroppedFile {
+                path,
+                x: 1.0,
+                y: 2.0,
+            },
+        ]];
+        assert_eq!(replay(&record(&frames)).unwrap(), frames);
+    }
+
+    #[test]
+    fn utf16_path() {
+        // A path recorded on Windows, which decodes everywhere as long as it is valid UTF-16.
+        let mut data = vec![PATH_WINDOWS];
+        write_bytes(&mut data, &[b'a', 0, 0x3d, 0xd8, 0x00, 0xde]);
+        assert_eq!(
+            Reader(&mut &data[..]).path().unwrap(),
+            PathBuf::from("a\u{1f600}")
+        );
+
+        // An odd number of bytes can't be UTF-16.
+        let mut data = vec![PATH_WINDOWS];
+        write_bytes(&mut data, &[b'a', 0, b'b']);
+        assert_eq!(
+            Reader(&mut &data[..]).path(),
+            Err(RecordingError::InvalidFormat)
+        );
+    }
+
+    #[test]
+    fn truncated_frame() {
+        let data = record(&[vec![Event::Focused(true)], vec![Event::CursorEntered]]);
+
+        // The second frame is cut off in its end of frame record, only the first one is replayed.
+        let frames = replay(&data[..data.len() - 1]).unwrap();
+        assert_eq!(frames, vec![vec![Event::Focused(true)]]);
+    }
+
+    #[test]
+    fn unsupported_version() {
+        let mut data = record(&[vec![Event::CursorEntered]]);
+
+        data[8] = RECORDING_VERSION.major + 1;
+        assert!(matches!(
+            Replay::new(&data[..]),
+            Err(RecordingError::UnsupportedVersion(version))
+                if version.major == RECORDING_VERSION.major + 1
+        ));
+
+        data[8] = RECORDING_VERSION.major;
+        data[9] = RECORDING_VERSION.minor + 1;
+        assert!(matches!(
+            Replay::new(&data[..]),
+            Err(RecordingError::UnsupportedVersion(_))
+        ));
+
+        // Newer patch versions don't change the format.
+        data[9] = RECORDING_VERSION.minor;
+        data[10] = data[10].wrapping_add(1);
+        assert!(Replay::new(&data[..]).is_ok());
+    }
+
+    #[test]
+    fn invalid_format() {
+        assert!(matches!(
+            Replay::new(&b"NOTAREC\0\x01\0\0\0"[..]),
+            Err(RecordingError::InvalidFormat)
+        ));
+        assert!(matches!(
+            Replay::new(&MAGIC[..4]),
+            Err(RecordingError::InvalidFormat)
+        ));
+
+        // An unknown tag, and a key code past the end of `KeyCode::ALL`.
+        let mut data = record(&[]);
+        data.extend([0xfe, 0]);
+        assert_eq!(replay(&data), Err(RecordingError::InvalidFormat));
+
+        let mut data = record(&[]);
+        data.extend([6, 0, 0, KeyCode::ALL.len() as u8, 0, 0, 0]);
+        assert_eq!(replay(&data), Err(RecordingError::InvalidFormat));
+    }
+}
@@ -0,0 +1,173 @@
+//! Clocks and frame timing.
+//!
+//! All time is measured with [`Instant`], which uses the monotonic high-resolution clock of the platform:
+//! `CLOCK_MONOTONIC` on Linux and `QueryPerformanceCounter` on Windows.
+
+use std::thread;
+
+pub use std::time::{Duration, Instant};
+
+/// How long before a deadline [`sleep_until()`] stops sleeping and spins instead.
+///
+/// Sleeping overshoots by up to the resolution of the timers of the scheduler, which is around 1ms on
+/// Windows with high resolution timers, and tens of microseconds on Linux.
+#[cfg(target_os = "windows")]
+const SPIN_THRESHOLD: Duration = Duration::from_micros(2000);
+#[cfg(not(target_os = "windows"))]
+const SPIN_THRESHOLD: Duration = Duration::from_micros(500);
+
+/// How much the latest frame contributes to the smoothed frame time of a [`FrameTimer`].
+const SMOOTHING: f64 = 0.1;
+
+/// A monotonic clock, measuring the time since it was started.
+#[derive(Debug, Clone, Copy, PartialEq, Eq)]
+pub struct Clock {
+    start: Instant,
+}
+
+impl Clock {
+    /// Starts a new clock.
+    pub fn new() -> Self {
+        Self {
+            start: Instant::now(),
+        }
+    }
+
+    /// Returns the time the clock was started at.
+    pub fn start(&self) -> Instant {
+        self.start
+    }
+
+    /// Returns the time since the clock was started.
+    pub fn elapsed(&self) -> Duration {
+        self.start.elapsed()
+    }
+
+    /// Starts the clock again, and returns the time that had elapsed.
+    pub fn restart(&mut self) -> Duration {
+        let now = Instant::now();
+        let elapsed = now - self.start;
+        self.start = now;
+        elapsed
+    }
+}
+
+impl Default for Clock {
+    fn default() -> Self {
+        Self::new()
+    }
+}
+
+/// Measures the time between frames.
+///
+/// [`FrameTimer::tick()`] is called once per frame, e.g. right before [`Window::poll_events()`](super::Window::poll_events).
+/// Frames are limited by sleeping until the start of the next frame is due, i.e. until
+/// `timer.frame_start() + frame_time` with [`sleep_until()`].
+#[derive(Debug, Clone, Copy)]
+pub struct FrameTimer {
+    clock: Clock,
+    frame_start: Instant,
+    delta: Duration,
+
+    /// The exponential moving average of the frame time in seconds, `None` before the first frame.
+    smoothed: Option<f64>,
+    frame_index: u64,
+}
+
+impl FrameTimer {
+    /// Creates a timer, the first frame is measured from now.
+    pub fn new() -> Self {
+        let clock = Clock::new();
+        Self {
+            clock,
+            frame_start: clock.start(),
+            delta: Duration::ZERO,
+            smoothed: None,
+            frame_index: 0,
+        }
+    }
+
+    /// Starts a new frame, and returns the time since the previous frame started.
+    pub fn tick(&mut self) -> Duration {
+        let now = Instant::now();
+        self.delta = now - self.frame_start;
+        self.frame_start = now;
+        self.frame_index += 1;
+
+        let seconds = self.delta.as_secs_f64();
+        self.smoothed = Some(match self.smoothed {
+            Some(smoothed) => smoothed + (seconds - smoothed) * SMOOTHING,
+            None => seconds,
+        });
+
+        self.delta
+    }
+
+    /// Returns the time between the starts of the last two frames.
+    pub fn delta(&self) -> Duration {
+        self.delta
+    }
+
+    /// Returns the time the current frame started at.
+    pub fn frame_start(&self) -> Instant {
+        self.frame_start
+    }
+
+    /// Returns the number of frames started, i.e. the index of the current frame counting from 1.
+    pub fn frame_index(&self) -> u64 {
+        self.frame_index
+    }
+
+    /// Returns the frame rate, smoothed over the last frames so it can be displayed.
+    ///
+    /// Returns 0.0 before the first frame.
+    pub fn fps(&self) -> f64 {
+        match self.smoothed {
+            Some(smoothed) if smoothed > 0.0 => 1.0 / smoothed,
+            _ => 0.0,
+        }
+    }
+
+    /// Returns the time since the timer was created.
+    pub fn elapsed(&self) -> Duration {
+        self.clock.elapsed()
+    }
+}
+
+impl Default for FrameTimer {
+    fn default() -> Self {
+        Self::new()
+    }
+}
+
+/// Blocks the thread until `deadline`, returns immediately if it has passed.
+///
+/// Sleeping alone is too coarse for frame limiting, so the thread sleeps until shortly before the deadline
+/// and spins for the rest. This costs some CPU time in exchange for precision.
+///
+/// # Arguments
+///
+/// - `deadline` - The time to wake up at.
+pub fn sleep_until(deadline: Instant) {
+    loop {
+        let now = Instant::now();
+        if now >= deadline {
+            return;
+        }
+
+        let remaining = deadline - now;
+        match remaining > SPIN_THRESHOLD {
+            true => thread::sleep(remaining - SPIN_THRESHOLD),
+            false => std::hint::spin_loop(),
+        }
+    }
+}
+
+/// Blocks the thread for `duration`, see [`sleep_until()`].
+///
+/// # Arguments
+///
+/// - `duration` - How long to block.
+pub fn sleep(duration: Duration) {
+    sleep_until(Instant::now() + duration)
+}
@@ -0,0 +1,264 @@
+//! Drag and drop of files through OLE, which reports files while they are dragged over the window.
+//!
+//! `DragAcceptFiles` only reports files once they are dropped, so windows register an `IDropTarget` instead.
+//! The windows crate can't implement COM interfaces without its `implement` macro, so the object and its
+//! vtable are laid out by hand.
+
+use std::{
+    cell::{Cell, RefCell},
+    ffi::OsString,
+    mem::ManuallyDrop,
+    os::windows::ffi::OsStringExt,
+    path::PathBuf,
+    ptr,
+    sync::atomic::{AtomicU32, Ordering},
+};
+
+use windows::{
+    core::{IUnknown, Interface, RawPtr, GUID, HRESULT},
+    Win32::{
+        Foundation::*,
+        Graphics::Gdi::ScreenToClient,
+        System::{Com::*, Ole::*, SystemServices::CF_HDROP},
+        UI::Shell::{DragQueryFileW, HDROP},
+    },
+};
+
+use super::WindowState;
+use crate::os::Event;
+
+#[repr(C)]
+struct DropTarget {
+    /// COM finds the vtable at the address of the object, so this must be the first field.
+    vtable: *const IDropTargetVtbl,
+    refs: AtomicU32,
+    hwnd: HWND,
+    state: *const WindowState,
+
+    /// The files dragged over the window, `None` while nothing that can be dropped is dragged.
+    paths: RefCell<Option<Vec<PathBuf>>>,
+
+    /// The last position reported while dragging, in client coordinates.
+    position: Cell<(f64, f64)>,
+}
+
+static VTABLE: IDropTargetVtbl = IDropTargetVtbl(
+    query_interface,
+    add_ref,
+    release,
+    drag_enter,
+    drag_over,
+    drag_leave,
+    drag_drop,
+);
+
+/// Registers a drop target for the window, which reports files while drag and drop is enabled in the state.
+///
+/// Returns `false` if OLE isn't initialized on the thread, in which case nothing can be dropped.
+///
+/// # Safety
+///
+/// The state must outlive the registration, i.e. [`revoke()`] must be called before it is freed.
+pub unsafe fn register(hwnd: HWND, state: &WindowState) -> bool {
+    let target = Box::into_raw(Box::new(DropTarget {
+        vtable: &VTABLE,
+        refs: AtomicU32::new(1),
+        hwnd,
+        state,
+        paths: RefCell::new(None),
+        position: Cell::new((0.0, 0.0)),
+    }));
+
+    // SAFETY: The object starts with its vtable, so it is a COM object. Its reference is moved into the
+    // interface, which releases it when dropped, while OLE holds a reference of its own until revoked.
+    let target = std::mem::transmute::<*mut DropTarget, IDropTarget>(target);
+    RegisterDragDrop(hwnd, &target).is_ok()
+}
+
+/// Revokes the drop target of the window, after which the state isn't accessed anymore.
+pub unsafe fn revoke(hwnd: HWND) {
+    let _ = RevokeDragDrop(hwnd);
+}
+
+/// Returns the paths of the files in a `HDROP`.
+fn query_files(hdrop: HDROP) -> Vec<PathBuf> {
+    // An index of 0xFFFFFFFF returns the number of files, a null buffer the length of a path.
+    let count = unsafe { DragQueryFileW(hdrop, u32::MAX, PWSTR::default(), 0) };
+    (0..count)
+        .map(|index| {
+            let len = unsafe { DragQueryFileW(hdrop, index, PWSTR::default(), 0) } as usize;
+            let mut buffer = vec![0u16; len + 1];
+            unsafe {
+                DragQueryFileW(
+                    hdrop,
+                    index,
+                    PWSTR(buffer.as_mut_ptr()),
+                    buffer.len() as u32,
+                )
+            };
+            PathBuf::from(OsString::from_wide(&buffer[..len]))
+        })
+        .collect()
+}
+
+/// Returns the files of a data object, which is empty if it holds something else.
+unsafe fn read_files(data: RawPtr) -> Vec<PathBuf> {
+    if data.is_null() {
+        return Vec::new();
+    }
+
+    // SAFETY: The data object is only borrowed for the call, so its reference must not be released.
+    let data = ManuallyDrop::new(std::mem::transmute::<RawPtr, IDataObject>(data));
+    let format = FORMATETC {
+        cfFormat: CF_HDROP as u16,
+        ptd: ptr::null_mut(),
+        dwAspect: DVASPECT_CONTENT as u32,
+        lindex: -1,
+        tymed: TYMED_HGLOBAL as u32,
+    };
+
+    // The medium is freed by ReleaseStgMedium, including the object it holds for its release.
+    let mut medium = match data.GetData(&format) {
+        Ok(medium) => ManuallyDrop::new(medium),
+        Err(_) => return Vec::new(),
+    };
+    let paths = query_files(medium.Anonymous.hGlobal as HDROP);
+    ReleaseStgMedium(&mut *medium);
+    paths
+}
+
+impl DropTarget {
+    /// Converts a position in screen coordinates to client coordinates, and remembers it.
+    fn update_position(&self, point: POINTL) -> (f64, f64) {
+        let mut point = POINT {
+            x: point.x,
+            y: point.y,
+        };
+        unsafe { ScreenToClient(self.hwnd, &mut point) };
+
+        let position = (point.x as f64, point.y as f64);
+        self.position.set(position);
+        position
+    }
+
+    fn effect(&self) -> u32 {
+        match self.paths.borrow().is_some() {
+            true => DROPEFFECT_COPY,
+            false => DROPEFFECT_NONE,
+        }
+    }
+}
+
+unsafe extern "system" fn query_interface(
+    this: RawPtr,
+    iid: &GUID,
+    interface: *mut RawPtr,
+) -> HRESULT {
+    if *iid == IUnknown::IID || *iid == IDropTarget::IID {
+        add_ref(this);
+        *interface = this;
+        S_OK
+    } else {
+        *interface = ptr::null_mut();
+        E_NOINTERFACE
+    }
+}
+
+unsafe extern "system" fn add_ref(this: RawPtr) -> u32 {
+    let target = &*(this as *const DropTarget);
+    target.refs.fetch_add(1, Ordering::Relaxed) + 1
+}
+
+unsafe extern "system" fn release(this: RawPtr) -> u32 {
+    let refs = (*(this as *const DropTarget))
+        .refs
+        .fetch_sub(1, Ordering::AcqRel)
+        - 1;
+    if refs == 0 {
+        drop(Box::from_raw(this as *mut DropTarget));
+    }
+    refs
+}
+
+unsafe extern "system" fn drag_enter(
+    this: RawPtr,
+    data: RawPtr,
+    _key_state: u32,
+    point: POINTL,
+    effect: *mut u32,
+) -> HRESULT {
+    let target = &*(this as *const DropTarget);
+    let state = &*target.state;
+
+    let paths = match state.drag_and_drop.get() {
+        true => read_files(data),
+        false => Vec::new(),
+    };
+
+    if !paths.is_empty() {
+        let (x, y) = target.update_position(point);
+        for path in &paths {
+            state.push_event(Event::HoveredFile {
+                path: path.clone(),
+                x,
+                y,
+            });
+        }
+        *target.paths.borrow_mut() = Some(paths);
+    }
+
+    *effect = target.effect();
+    S_OK
+}
+
+unsafe extern "system" fn drag_over(
+    this: RawPtr,
+    _key_state: u32,
+    point: POINTL,
+    effect: *mut u32,
+) -> HRESULT {
+    let target = &*(this as *const DropTarget);
+
+    // The source captures the mouse while dragging, so the window receives no WM_MOUSEMOVE.
+    // This is also called while the cursor rests, only changes are reported.
+    if target.paths.borrow().is_some() {
+        let previous = target.position.get();
+        let (x, y) = target.update_position(point);
+        if (x, y) != previous {
+            (*target.state).push_event(Event::CursorMoved { x, y });
+        }
+    }
+
+    *effect = target.effect();
+    S_OK
+}
+
+unsafe extern "system" fn drag_leave(this: RawPtr) -> HRESULT {
+    let target = &*(this as *const DropTarget);
+    if target.paths.borrow_mut().take().is_some() {
+        (*target.state).push_event(Event::HoveredFileCancelled);
+    }
+    S_OK
+}
+
+unsafe extern "system" fn drag_drop(
+    this: RawPtr,
+    _data: RawPtr,
+    _key_state: u32,
+    point: POINTL,
+    effect: *mut u32,
+) -> HRESULT {
+    let target = &*(this as *const DropTarget);
+
+    *effect = match target.paths.borrow_mut().take() {
+        Some(paths) => {
+            let (x, y) = target.update_position(point);
+            for path in paths {
+                (*target.state).push_event(Event::DroppedFile { path, x, y });
+            }
+            DROPEFFECT_COPY
+        }
+        None => DROPEFFECT_NONE,
+    };
+    S_OK
+}
@@ -0,0 +1,115 @@
+//! System cursors, and cursors and icons created from images.
+
+use std::{mem::size_of, ptr, slice};
+
+use windows::Win32::{Foundation::*, Graphics::Gdi::*, UI::WindowsAndMessaging::*};
+
+use crate::os::{CursorIcon, Icon, ImageData};
+
+/// Bitmap compression with explicit channel masks, which allows an alpha channel.
+const BI_BITFIELDS: u32 = 3;
+
+/// Returns the system cursor of a shape, which is shared and must not be destroyed.
+pub fn load_cursor(icon: CursorIcon) -> HCURSOR {
+    let name = match icon {
+        CursorIcon::Arrow => IDC_ARROW,
+        CursorIcon::Text => IDC_IBEAM,
+        CursorIcon::Hand => IDC_HAND,
+        CursorIcon::Crosshair => IDC_CROSS,
+        CursorIcon::Wait => IDC_WAIT,
+        CursorIcon::Progress => IDC_APPSTARTING,
+        CursorIcon::ResizeHorizontal => IDC_SIZEWE,
+        CursorIcon::ResizeVertical => IDC_SIZENS,
+        CursorIcon::ResizeTopLeft => IDC_SIZENWSE,
+        CursorIcon::ResizeTopRight => IDC_SIZENESW,
+        CursorIcon::Move => IDC_SIZEALL,
+        CursorIcon::NotAllowed => IDC_NO,
+    };
+
+    unsafe { LoadCursorW(HINSTANCE::default(), name) }
+}
+
+/// Returns the image of an icon that best fits a square of `size` pixels.
+///
+/// This is the smallest image at least as large, since scaling down looks better than scaling up,
+/// or the largest image if all are smaller.
+pub fn best_image(icon: &Icon, size: u32) -> &ImageData {
+    let extent = |image: &&ImageData| image.width.max(image.height);
+
+    icon.images()
+        .iter()
+        .filter(|image| extent(image) >= size)
+        .min_by_key(extent)
+        .or_else(|| icon.images().iter().max_by_key(extent))
+        .unwrap()
+}
+
+/// Creates an icon from an image, or a cursor if a hotspot is given. Returns a null handle on failure.
+///
+/// Icons and cursors are the same kind of object, so the handle can be used as either.
+/// It must be destroyed with `DestroyIcon` or `DestroyCursor`.
+///
+/// # Arguments
+///
+/// - `image` - The image, which must have `width * height` pixels.
+/// - `hotspot` - The hotspot of the cursor, relative to the top-left corner of the image.
+pub fn create_icon(image: &ImageData, hotspot: Option<(u32, u32)>) -> HICON {
+    let (width, height) = (image.width as i32, image.height as i32);
+
+    let header = BITMAPV5HEADER {
+        bV5Size: size_of::<BITMAPV5HEADER>() as u32,
+        bV5Width: width,
+        // A negative height stores the rows top-down, like the image.
+        bV5Height: -height,
+        bV5Planes: 1,
+        bV5BitCount: 32,
+        bV5Compression: BI_BITFIELDS,
+        bV5RedMask: 0x00ff_0000,
+        bV5GreenMask: 0x0000_ff00,
+        bV5BlueMask: 0x0000_00ff,
+        bV5AlphaMask: 0xff00_0000,
+        // SAFETY: The header is plain data, for which all zeroes is valid.
+        ..unsafe { std::mem::zeroed() }
+    };
+
+    unsafe {
+        let mut bits = ptr::null_mut();
+        let color = CreateDIBSection(
+            HDC::default(),
+            &header as *const _ as *const BITMAPINFO,
+            DIB_RGB_COLORS,
+            &mut bits,
+            HANDLE::default(),
+            0,
+        );
+        if color == 0 || bits.is_null() {
+            return HICON::default();
+        }
+
+        // SAFETY: The section holds `width * height` pixels of 4 bytes, stored as BGRA.
+        let pixels = slice::from_raw_parts_mut(bits as *mut u8, image.rgba.len());
+        for (target, pixel) in pixels.chunks_exact_mut(4).zip(image.rgba.chunks_exact(4)) {
+            target.copy_from_slice(&[pixel[2], pixel[1], pixel[0], pixel[3]]);
+        }
+
+        // The mask is ignored when the color bitmap has alpha, but it must be present.
+        // Its rows are aligned to 16 bits.
+        let mask_bits = vec![0u8; (image.width as usize).div_ceil(16) * 2 * image.height as usize];
+        let mask = CreateBitmap(width, height, 1, 1, mask_bits.as_ptr() as *const _);
+
+        let (x, y) = hotspot.unwrap_or_default();
+        let info = ICONINFO {
+            fIcon: hotspot.is_none().into(),
+            xHotspot: x,
+            yHotspot: y,
+            hbmMask: mask,
+            hbmColor: color,
+        };
+        let icon = CreateIconIndirect(&info);
+
+        // The icon has its own copies of the bitmaps.
+        DeleteObject(color);
+        DeleteObject(mask);
+        icon
+    }
+}
@@ -0,0 +1,167 @@
+//! Translation of virtual keys to [`KeyCode`]s.
+
+use windows::Win32::UI::Input::KeyboardAndMouse::*;
+
+use crate::os::KeyCode;
+
+const LETTERS: [KeyCode; 26] = [
+    KeyCode::A,
+    KeyCode::B,
+    KeyCode::C,
+    KeyCode::D,
+    KeyCode::E,
+    KeyCode::F,
+    KeyCode::G,
+    KeyCode::H,
+    KeyCode::I,
+    KeyCode::J,
+    KeyCode::K,
+    KeyCode::L,
+    KeyCode::M,
+    KeyCode::N,
+    KeyCode::O,
+    KeyCode::P,
+    KeyCode::Q,
+    KeyCode::R,
+    KeyCode::S,
+    KeyCode::T,
+    KeyCode::U,
+    KeyCode::V,
+    KeyCode::W,
+    KeyCode::X,
+    KeyCode::Y,
+    KeyCode::Z,
+];
+
+const DIGITS: [KeyCode; 10] = [
+    KeyCode::Key0,
+    KeyCode::Key1,
+    KeyCode::Key2,
+    KeyCode::Key3,
+    KeyCode::Key4,
+    KeyCode::Key5,
+    KeyCode::Key6,
+    KeyCode::Key7,
+    KeyCode::Key8,
+    KeyCode::Key9,
+];
+
+const NUMPAD_DIGITS: [KeyCode; 10] = [
+    KeyCode::Numpad0,
+    KeyCode::Numpad1,
+    KeyCode::Numpad2,
+    KeyCode::Numpad3,
+    KeyCode::Numpad4,
+    KeyCode::Numpad5,
+    KeyCode::Numpad6,
+    KeyCode::Numpad7,
+    KeyCode::Numpad8,
+    KeyCode::Numpad9,
+];
+
+const FUNCTION_KEYS: [KeyCode; 24] = [
+    KeyCode::F1,
+    KeyCode::F2,
+    KeyCode::F3,
+    KeyCode::F4,
+    KeyCode::F5,
+    KeyCode::F6,
+    KeyCode::F7,
+    KeyCode::F8,
+    KeyCode::F9,
+    KeyCode::F10,
+    KeyCode::F11,
+    KeyCode::F12,
+    KeyCode::F13,
+    KeyCode::F14,
+    KeyCode::F15,
+    KeyCode::F16,
+    KeyCode::F17,
+    KeyCode::F18,
+    KeyCode::F19,
+    KeyCode::F20,
+    KeyCode::F21,
+    KeyCode::F22,
+    KeyCode::F23,
+    KeyCode::F24,
+];
+
+/// Translates the virtual key of a key message to a key code.
+///
+/// Key messages only report the generic virtual keys for shift, control, alt and enter,
+/// so the scancode, including the `0xe000` extended key flag, is used to tell left and right apart.
+pub fn vk_to_keycode(vk: VIRTUAL_KEY, scancode: u32) -> Option<KeyCode> {
+    let extended = scancode & 0xe000 != 0;
+
+    let keycode = match vk {
+        // Letters and digits share their virtual keys with their ASCII codes.
+        0x41..=0x5a => LETTERS[(vk - 0x41) as usize],
+        0x30..=0x39 => DIGITS[(vk - 0x30) as usize],
+        VK_F1..=VK_F24 => FUNCTION_KEYS[(vk - VK_F1) as usize],
+        VK_NUMPAD0..=VK_NUMPAD9 => NUMPAD_DIGITS[(vk - VK_NUMPAD0) as usize],
+
+        VK_ESCAPE => KeyCode::Escape,
+        VK_TAB => KeyCode::Tab,
+        VK_CAPITAL => KeyCode::CapsLock,
+        VK_SHIFT | VK_LSHIFT | VK_RSHIFT => match scancode {
+            0x36 => KeyCode::RightShift,
+            _ => KeyCode::LeftShift,
+        },
+        VK_CONTROL | VK_LCONTROL | VK_RCONTROL => match extended {
+            true => KeyCode::RightControl,
+            false => KeyCode::LeftControl,
+        },
+        VK_MENU | VK_LMENU | VK_RMENU => match extended {
+            true => KeyCode::RightAlt,
+            false => KeyCode::LeftAlt,
+        },
+        VK_LWIN => KeyCode::LeftSuper,
+        VK_RWIN => KeyCode::RightSuper,
+        VK_APPS => KeyCode::Menu,
+        VK_SPACE => KeyCode::Space,
+        VK_RETURN => match extended {
+            true => KeyCode::NumpadEnter,
+            false => KeyCode::Enter,
+        },
+        VK_BACK => KeyCode::Backspace,
+
+        VK_INSERT => KeyCode::Insert,
+        VK_DELETE => KeyCode::Delete,
+        VK_HOME => KeyCode::Home,
+        VK_END => KeyCode::End,
+        VK_PRIOR => KeyCode::PageUp,
+        VK_NEXT => KeyCode::PageDown,
+        VK_LEFT => KeyCode::Left,
+        VK_RIGHT => KeyCode::Right,
+        VK_UP => KeyCode::Up,
+        VK_DOWN => KeyCode::Down,
+
+        VK_SNAPSHOT => KeyCode::PrintScreen,
+        VK_SCROLL => KeyCode::ScrollLock,
+        VK_PAUSE => KeyCode::Pause,
+        VK_NUMLOCK => KeyCode::NumLock,
+
+        // The OEM keys are named after the characters they produce on a US layout.
+        VK_OEM_3 => KeyCode::Grave,
+        VK_OEM_MINUS => KeyCode::Minus,
+        VK_OEM_PLUS => KeyCode::Equals,
+        VK_OEM_4 => KeyCode::LeftBracket,
+        VK_OEM_6 => KeyCode::RightBracket,
+        VK_OEM_5 => KeyCode::Backslash,
+        VK_OEM_1 => KeyCode::Semicolon,
+        VK_OEM_7 => KeyCode::Apostrophe,
+        VK_OEM_COMMA => KeyCode::Comma,
+        VK_OEM_PERIOD => KeyCode::Period,
+        VK_OEM_2 => KeyCode::Slash,
+
+        VK_ADD => KeyCode::NumpadAdd,
+        VK_SUBTRACT => KeyCode::NumpadSubtract,
+        VK_MULTIPLY => KeyCode::NumpadMultiply,
+        VK_DIVIDE => KeyCode::NumpadDivide,
+        VK_DECIMAL => KeyCode::NumpadDecimal,
+
+        _ => return None,
+    };
+
+    Some(keycode)
+}
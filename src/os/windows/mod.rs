@@ -1,8 +1,29 @@
+use std::{
+    cell::{Cell, RefCell},
+    collections::VecDeque,
+    ffi::c_void,
+    mem::size_of,
+    num::NonZeroIsize,
+};
+
+use raw_window_handle::{
+    DisplayHandle, HandleError, HasDisplayHandle, HasWindowHandle, Win32WindowHandle, WindowHandle,
+};
 use windows::Win32::{
-    Foundation::*, Graphics::Gdi::*, System::LibraryLoader::*, UI::WindowsAndMessaging::*,
+    Foundation::*, Globalization::HIMC, Graphics::Dwm::*, Graphics::Gdi::*,
+    System::LibraryLoader::*, System::Ole::*, UI::HiDpi::*, UI::Input::Ime::*,
+    UI::Input::KeyboardAndMouse::*, UI::Input::*, UI::WindowsAndMessaging::*,
 };
 
-use super::WindowApi;
+use super::{
+    Cursor, CursorGrabMode, CursorIcon, ElementState, Event, Fullscreen, Icon, Ime, Modifiers,
+    Monitor, MouseButton, PhysicalSize, ScanCode, Size, WindowApi, WindowDesc, WindowError,
+};
+
+mod drop_target;
+mod icon;
+mod keymap;
+mod monitor;
 
 impl IntoPWSTR for &str {
     fn into_pwstr(&self) -> (PWSTR, Vec<u16>) {
@@ -17,38 +38,199 @@ impl IntoPWSTR for String {
     }
 }
 
+/// State shared with the window procedure through `GWLP_USERDATA`.
+struct WindowState {
+    min_size: Cell<Option<Size>>,
+    max_size: Cell<Option<Size>>,
+
+    /// The DPI of the monitor the window is on, divided by 96.
+    scale_factor: Cell<f64>,
+    resizable: Cell<bool>,
+    decorations: Cell<bool>,
+    minimized: Cell<bool>,
+    focused: Cell<bool>,
+
+    /// The size last reported with `Event::Resized`, `None` before the first report.
+    size: Cell<Option<(u32, u32)>>,
+    cursor_inside: Cell<bool>,
+    cursor_grab: Cell<CursorGrabMode>,
+    cursor_visible: Cell<bool>,
+    cursor: Cell<HCURSOR>,
+
+    /// Whether the cursor was created from an image, in which case it is destroyed when replaced.
+    cursor_owned: Cell<bool>,
+    text_input: Cell<bool>,
+    ime_position: Cell<(i32, i32)>,
+
+    /// Whether files can be dropped on the window, checked by the drop target.
+    drag_and_drop: Cell<bool>,
+
+    /// The first half of a character outside the BMP, which WM_CHAR delivers in two messages.
+    high_surrogate: Cell<Option<u16>>,
+
+    /// Events translated by the window procedure, waiting to be delivered by `poll_events`.
+    events: RefCell<VecDeque<Event>>,
+}
+
+impl WindowState {
+    fn push_event(&self, event: Event) {
+        self.events.borrow_mut().push_back(event);
+    }
+}
+
 pub struct Window {
     hinstance: HINSTANCE,
     hwnd: HWND,
+    state: Box<WindowState>,
+
+    /// The style and placement to return to when leaving fullscreen, `None` while windowed.
+    windowed: Cell<Option<(WINDOW_STYLE, WINDOWPLACEMENT)>>,
+
+    /// The monitor whose video mode was changed for exclusive fullscreen.
+    changed_monitor: Cell<Option<u64>>,
+
+    /// The big and small icons set by the application, null while the class icon is used.
+    icons: Cell<(HICON, HICON)>,
+
+    /// Whether OLE was initialized for the drop target, which has to be balanced when the window is dropped.
+    ole_initialized: bool,
 }
 
 impl Window {
-    pub fn new() -> Result<Self, ()> {
+    pub fn new(desc: &WindowDesc) -> Result<Self, WindowError> {
         let hinstance = unsafe { GetModuleHandleW(PWSTR::default()) };
         let class_name = Self::register_class(hinstance)?;
 
+        let mut style = Self::style(desc.decorations, desc.resizable);
+        if desc.visible {
+            style |= WS_VISIBLE;
+        }
+
+        let mut ex_style = WS_EX_APPWINDOW;
+        if desc.always_on_top {
+            ex_style |= WS_EX_TOPMOST;
+        }
+
+        // Without this Windows scales the window as a bitmap, which makes it blurry.
+        // It fails if the awareness was already set, e.g. by the manifest, which is fine.
+        unsafe { SetProcessDpiAwarenessContext(DPI_AWARENESS_CONTEXT_PER_MONITOR_AWARE_V2) };
+
+        // The monitor isn't known before the window exists, so the size is scaled for the primary monitor
+        // and corrected below if the window ends up elsewhere.
+        let dpi = unsafe { GetDpiForSystem() };
+        let size = desc
+            .size
+            .to_physical(dpi as f64 / USER_DEFAULT_SCREEN_DPI as f64);
+        let (width, height) = Self::outer_size(size, style, ex_style, dpi);
+        let (x, y) = desc.position.unwrap_or((CW_USEDEFAULT, CW_USEDEFAULT));
+
+        // The state is attached in WM_NCCREATE, so the messages sent during creation are already handled,
+        // e.g. WM_GETMINMAXINFO for the size limits and WM_SIZE for the initial size.
+        let state = Box::new(WindowState {
+            min_size: Cell::new(desc.min_size),
+            max_size: Cell::new(desc.max_size),
+            scale_factor: Cell::new(dpi as f64 / USER_DEFAULT_SCREEN_DPI as f64),
+            resizable: Cell::new(desc.resizable),
+            decorations: Cell::new(desc.decorations),
+            minimized: Cell::new(false),
+            focused: Cell::new(false),
+            size: Cell::new(None),
+            cursor_inside: Cell::new(false),
+            cursor_grab: Cell::new(CursorGrabMode::None),
+            cursor_visible: Cell::new(true),
+            cursor: Cell::new(icon::load_cursor(CursorIcon::default())),
+            cursor_owned: Cell::new(false),
+            text_input: Cell::new(false),
+            ime_position: Cell::new((0, 0)),
+            drag_and_drop: Cell::new(desc.drag_and_drop),
+            high_surrogate: Cell::new(None),
+            events: RefCell::new(VecDeque::new()),
+        });
+
+        // The buffers have to outlive the call, since the PWSTRs point into them.
+        let (class_name, _class_name) = class_name.into_pwstr();
+        let (title, _title) = desc.title.into_pwstr();
+
         let hwnd = unsafe {
             CreateWindowExW(
-                0,
-                class_name.into_pwstr().0,
-                PWSTR::default(),
-                WS_OVERLAPPEDWINDOW,
-                CW_USEDEFAULT,
-                CW_USEDEFAULT,
-                640,
-                480,
+                ex_style,
+                class_name,
+                title,
+                style,
+                x,
+                y,
+                width,
+                height,
                 HWND::default(),
                 HMENU::default(),
                 hinstance,
-                std::ptr::null(),
+                // SAFETY: The state is boxed, so the pointer stays valid when the window is moved.
+                &*state as *const WindowState as *const c_void,
             )
         };
 
         if hwnd == 0 {
-            return Err(());
+            return Err(WindowError::CreationFailed);
+        }
+
+        if desc.transparent {
+            Self::enable_transparency(hwnd);
+        }
+
+        // Raw input of the mouse is delivered as WM_INPUT, while the window is in the foreground.
+        let device = RAWINPUTDEVICE {
+            usUsagePage: 0x01, // HID_USAGE_PAGE_GENERIC
+            usUsage: 0x02,     // HID_USAGE_GENERIC_MOUSE
+            dwFlags: 0,
+            hwndTarget: hwnd,
+        };
+        unsafe { RegisterRawInputDevices(&device, 1, size_of::<RAWINPUTDEVICE>() as u32) };
+
+        // Text input starts out disabled, so the input method doesn't intercept keys.
+        unsafe { ImmAssociateContextEx(hwnd, 0, 0) };
+
+        // The drop target is always registered, whether files are accepted is checked when they are dragged.
+        // OLE fails to initialize if the thread already uses a multithreaded apartment, files can't be dropped then.
+        let ole_initialized = unsafe { OleInitialize(std::ptr::null_mut()) }.is_ok();
+        if ole_initialized {
+            // SAFETY: The target is revoked before the state is freed, when the window is dropped.
+            unsafe { drop_target::register(hwnd, &state) };
+        }
+
+        let window = Self {
+            hinstance,
+            hwnd,
+            state,
+            windowed: Cell::new(None),
+            changed_monitor: Cell::new(None),
+            icons: Cell::new((HICON::default(), HICON::default())),
+            ole_initialized,
+        };
+
+        if desc.icon.is_some() {
+            window.set_icon(desc.icon);
         }
 
-        Ok(Self { hinstance, hwnd })
+        let window_dpi = unsafe { GetDpiForWindow(hwnd) };
+        if window_dpi != 0 && window_dpi != dpi {
+            window
+                .state
+                .scale_factor
+                .set(window_dpi as f64 / USER_DEFAULT_SCREEN_DPI as f64);
+            window.state.push_event(Event::ScaleFactorChanged {
+                scale_factor: window.state.scale_factor.get(),
+            });
+            window.set_inner_size(desc.size);
+        }
+
+        // Hidden windows only receive WM_SIZE once they are shown, the initial size is reported either way.
+        if window.state.size.get().is_none() {
+            let PhysicalSize { width, height } = window.inner_size();
+            window.state.size.set(Some((width, height)));
+            window.state.push_event(Event::Resized { width, height });
+        }
+
+        Ok(window)
     }
 
     fn is_class_registered(instance: HINSTANCE, class_name: &str) -> bool {
@@ -63,7 +245,7 @@ impl Window {
         .as_bool()
     }
 
-    fn register_class(instance: HINSTANCE) -> Result<&'static str, ()> {
+    fn register_class(instance: HINSTANCE) -> Result<&'static str, WindowError> {
         const CLASS_NAME: &str = "iglo_window";
 
         if !Self::is_class_registered(instance, CLASS_NAME) {
@@ -74,20 +256,16 @@ impl Window {
                 cbClsExtra: 0,
                 cbWndExtra: 0,
                 hInstance: instance,
-                hIcon: HICON::default(),
-                hCursor: HCURSOR::default(),
+                hIcon: unsafe { LoadIconW(HINSTANCE::default(), IDI_APPLICATION) },
+                hCursor: icon::load_cursor(CursorIcon::default()),
                 hbrBackground: HBRUSH::default(),
                 lpszMenuName: PWSTR::default(),
                 lpszClassName: CLASS_NAME.into_pwstr().0,
                 hIconSm: HICON::default(),
             };
 
-            unsafe {
-                if RegisterClassExW(&wcx) == 0 {
-                    println!("Failed to register class!");
-                    println!("{:?}", GetLastError());
-                    return Err(());
-                }
+            if unsafe { RegisterClassExW(&wcx) } == 0 {
+                return Err(WindowError::CreationFailed);
             }
         }
 
@@ -95,8 +273,540 @@ impl Window {
     }
 
     extern "system" fn wndproc(hwnd: HWND, msg: u32, wparam: WPARAM, lparam: LPARAM) -> LRESULT {
+        if msg == WM_NCCREATE {
+            // SAFETY: For WM_NCCREATE lparam always points to a CREATESTRUCTW,
+            // whose parameter is the state passed to CreateWindowExW.
+            let create = unsafe { &*(lparam as *const CREATESTRUCTW) };
+            unsafe { SetWindowLongPtrW(hwnd, GWLP_USERDATA, create.lpCreateParams as isize) };
+        }
+
+        // SAFETY: The user data is either null or points to the state owned by the window.
+        let state =
+            unsafe { (GetWindowLongPtrW(hwnd, GWLP_USERDATA) as *const WindowState).as_ref() };
+
+        if let Some(state) = state {
+            if let Some(result) = Self::handle_message(state, hwnd, msg, wparam, lparam) {
+                return result;
+            }
+        }
+
+        unsafe { DefWindowProcW(hwnd, msg, wparam, lparam) }
+    }
+
+    /// Translates a message into events.
+    ///
+    /// Returns `None` if the message should also be passed on to `DefWindowProcW`.
+    fn handle_message(
+        state: &WindowState,
+        hwnd: HWND,
+        msg: u32,
+        wparam: WPARAM,
+        lparam: LPARAM,
+    ) -> Option<LRESULT> {
+        // Not part of the features we import from the windows crate.
+        const WM_MOUSELEAVE: u32 = 0x02A3;
+        const MOUSE_MOVE_ABSOLUTE: u16 = 0x01;
+
+        let loword = |value: isize| (value & 0xffff) as u16;
+        let hiword = |value: isize| ((value >> 16) & 0xffff) as u16;
+
         match msg {
-            _ => unsafe { DefWindowProcW(hwnd, msg, wparam, lparam) },
+            WM_GETMINMAXINFO => {
+                let (style, ex_style) = Self::current_style(hwnd);
+
+                // SAFETY: For WM_GETMINMAXINFO lparam always points to a MINMAXINFO.
+                let info = unsafe { &mut *(lparam as *mut MINMAXINFO) };
+
+                let scale_factor = state.scale_factor.get();
+                let dpi = (scale_factor * USER_DEFAULT_SCREEN_DPI as f64) as u32;
+
+                if let Some(size) = state.min_size.get() {
+                    let size = size.to_physical(scale_factor);
+                    let (width, height) = Self::outer_size(size, style, ex_style, dpi);
+                    info.ptMinTrackSize = POINT {
+                        x: width,
+                        y: height,
+                    };
+                }
+
+                if let Some(size) = state.max_size.get() {
+                    let size = size.to_physical(scale_factor);
+                    let (width, height) = Self::outer_size(size, style, ex_style, dpi);
+                    info.ptMaxTrackSize = POINT {
+                        x: width,
+                        y: height,
+                    };
+                }
+
+                Some(0)
+            }
+            WM_CLOSE => {
+                // The window is not destroyed, the application decides what to do about the request.
+                state.push_event(Event::CloseRequested);
+                Some(0)
+            }
+            WM_SIZE => {
+                let minimized = wparam as u32 == SIZE_MINIMIZED;
+                if minimized != state.minimized.get() {
+                    state.minimized.set(minimized);
+                    state.push_event(Event::Minimized(minimized));
+                }
+
+                // A minimized window reports a size of 0x0, which is not interesting to the application.
+                let size = (loword(lparam) as u32, hiword(lparam) as u32);
+                if !minimized && state.size.get() != Some(size) {
+                    state.size.set(Some(size));
+                    state.push_event(Event::Resized {
+                        width: size.0,
+                        height: size.1,
+                    });
+                }
+
+                Self::apply_cursor_grab(hwnd, state);
+                Some(0)
+            }
+            WM_MOVE => {
+                state.push_event(Event::Moved {
+                    x: loword(lparam) as i16 as i32,
+                    y: hiword(lparam) as i16 as i32,
+                });
+
+                Self::apply_cursor_grab(hwnd, state);
+                Some(0)
+            }
+            WM_SETFOCUS | WM_KILLFOCUS => {
+                let focused = msg == WM_SETFOCUS;
+                state.focused.set(focused);
+                state.push_event(Event::Focused(focused));
+
+                // The clip rectangle is global, so it must not outlive the focus.
+                Self::apply_cursor_grab(hwnd, state);
+                Some(0)
+            }
+            WM_SETCURSOR if loword(lparam) as u32 == HTCLIENT => {
+                let cursor = match state.cursor_visible.get() {
+                    true => state.cursor.get(),
+                    false => HCURSOR::default(),
+                };
+                unsafe { SetCursor(cursor) };
+                Some(1)
+            }
+            WM_INPUT => {
+                let mut input = unsafe { std::mem::zeroed::<RAWINPUT>() };
+                let mut size = size_of::<RAWINPUT>() as u32;
+                let read = unsafe {
+                    GetRawInputData(
+                        lparam as HRAWINPUT,
+                        RID_INPUT,
+                        &mut input as *mut _ as *mut c_void,
+                        &mut size,
+                        size_of::<RAWINPUTHEADER>() as u32,
+                    )
+                };
+
+                if read != u32::MAX && input.header.dwType == RIM_TYPEMOUSE {
+                    // SAFETY: The header says the input is from a mouse.
+                    let mouse = unsafe { input.data.mouse };
+
+                    // Absolute motion comes from tablets and remote desktops, which have no relative motion.
+                    if mouse.usFlags & MOUSE_MOVE_ABSOLUTE == 0
+                        && (mouse.lLastX != 0 || mouse.lLastY != 0)
+                    {
+                        state.push_event(Event::MouseMotion {
+                            delta_x: mouse.lLastX as f64,
+                            delta_y: mouse.lLastY as f64,
+                        });
+                    }
+                }
+
+                // DefWindowProcW has to clean up after the raw input.
+                None
+            }
+            WM_DPICHANGED => {
+                let scale_factor = hiword(wparam as isize) as f64 / USER_DEFAULT_SCREEN_DPI as f64;
+                state.scale_factor.set(scale_factor);
+                state.push_event(Event::ScaleFactorChanged { scale_factor });
+
+                // SAFETY: For WM_DPICHANGED lparam always points to the suggested window rectangle,
+                // which keeps the logical size of the window. Applying it sends WM_SIZE.
+                let rect = unsafe { *(lparam as *const RECT) };
+                unsafe {
+                    SetWindowPos(
+                        hwnd,
+                        HWND::default(),
+                        rect.left,
+                        rect.top,
+                        rect.right - rect.left,
+                        rect.bottom - rect.top,
+                        SWP_NOZORDER | SWP_NOACTIVATE,
+                    )
+                };
+                Some(0)
+            }
+            WM_KEYDOWN | WM_SYSKEYDOWN | WM_KEYUP | WM_SYSKEYUP => {
+                // Bits 16-23 hold the scancode and bit 24 whether it is an extended key.
+                let mut scancode = ((lparam >> 16) & 0xff) as u32;
+                if lparam & (1 << 24) != 0 {
+                    scancode |= 0xe000;
+                }
+
+                let pressed = matches!(msg, WM_KEYDOWN | WM_SYSKEYDOWN);
+
+                state.push_event(Event::KeyboardInput {
+                    scancode: ScanCode(scancode),
+                    keycode: keymap::vk_to_keycode(wparam as VIRTUAL_KEY, scancode),
+                    state: match pressed {
+                        true => ElementState::Pressed,
+                        false => ElementState::Released,
+                    },
+                    modifiers: Self::modifiers(),
+                    // Bit 30 is set if the key was already down before the message.
+                    repeat: pressed && lparam & (1 << 30) != 0,
+                });
+
+                // System keys still need the default handling, e.g. for Alt+F4.
+                None
+            }
+            WM_CHAR if state.text_input.get() => {
+                let unit = wparam as u16;
+                let c = match (state.high_surrogate.take(), unit) {
+                    (_, 0xd800..=0xdbff) => {
+                        state.high_surrogate.set(Some(unit));
+                        return Some(0);
+                    }
+                    (Some(high), 0xdc00..=0xdfff) => char::decode_utf16([high, unit]).next(),
+                    (_, unit) => char::decode_utf16([unit]).next(),
+                };
+
+                if let Some(Ok(c)) = c {
+                    if !c.is_control() {
+                        state.push_event(Event::ReceivedCharacter(c));
+                    }
+                }
+                Some(0)
+            }
+            WM_IME_SETCONTEXT => {
+                // The application shows the composition itself, so the system's composition window is hidden.
+                let lparam = lparam & !(ISC_SHOWUICOMPOSITIONWINDOW as isize);
+                Some(unsafe { DefWindowProcW(hwnd, msg, wparam, lparam) })
+            }
+            WM_IME_STARTCOMPOSITION => {
+                Self::update_ime_position(hwnd, state);
+                Some(0)
+            }
+            WM_IME_COMPOSITION => {
+                let himc = unsafe { ImmGetContext(hwnd) };
+                if himc == 0 {
+                    return Some(0);
+                }
+
+                // Handling the result here keeps DefWindowProcW from sending it again as WM_CHAR.
+                if lparam as u32 & GCS_RESULTSTR != 0 {
+                    let text = Self::composition_string(himc, GCS_RESULTSTR);
+                    state.push_event(Event::Ime(Ime::Commit(text)));
+                }
+
+                if lparam as u32 & GCS_COMPSTR != 0 {
+                    let text = Self::composition_string(himc, GCS_COMPSTR);
+
+                    // The cursor is given in UTF-16 units.
+                    let cursor = unsafe {
+                        ImmGetCompositionStringW(himc, GCS_CURSORPOS, std::ptr::null_mut(), 0)
+                    };
+                    let cursor = usize::try_from(cursor).ok().map(|cursor| {
+                        text.chars()
+                            .scan(0, |units, c| {
+                                *units += c.len_utf16();
+                                Some((*units, c.len_utf8()))
+                            })
+                            .take_while(|&(units, _)| units <= cursor)
+                            .map(|(_, len)| len)
+                            .sum()
+                    });
+
+                    state.push_event(Event::Ime(Ime::Preedit { text, cursor }));
+                }
+
+                unsafe { ImmReleaseContext(hwnd, himc) };
+                Some(0)
+            }
+            WM_IME_ENDCOMPOSITION => {
+                state.push_event(Event::Ime(Ime::Preedit {
+                    text: String::new(),
+                    cursor: None,
+                }));
+                Some(0)
+            }
+            WM_MOUSEMOVE => {
+                if !state.cursor_inside.get() {
+                    state.cursor_inside.set(true);
+                    state.push_event(Event::CursorEntered);
+
+                    // Ask for a WM_MOUSELEAVE, once the cursor leaves the window.
+                    let mut track = TRACKMOUSEEVENT {
+                        cbSize: std::mem::size_of::<TRACKMOUSEEVENT>() as u32,
+                        dwFlags: TME_LEAVE,
+                        hwndTrack: hwnd,
+                        dwHoverTime: 0,
+                    };
+                    unsafe { TrackMouseEvent(&mut track) };
+                }
+
+                // A locked cursor doesn't move, even though it is clipped to a single pixel.
+                if state.cursor_grab.get() != CursorGrabMode::Locked {
+                    state.push_event(Event::CursorMoved {
+                        x: loword(lparam) as i16 as f64,
+                        y: hiword(lparam) as i16 as f64,
+                    });
+                }
+                Some(0)
+            }
+            WM_MOUSELEAVE => {
+                state.cursor_inside.set(false);
+                state.push_event(Event::CursorLeft);
+                Some(0)
+            }
+            WM_LBUTTONDOWN | WM_LBUTTONUP | WM_RBUTTONDOWN | WM_RBUTTONUP | WM_MBUTTONDOWN
+            | WM_MBUTTONUP | WM_XBUTTONDOWN | WM_XBUTTONUP => {
+                let button = match msg {
+                    WM_LBUTTONDOWN | WM_LBUTTONUP => MouseButton::Left,
+                    WM_RBUTTONDOWN | WM_RBUTTONUP => MouseButton::Right,
+                    WM_MBUTTONDOWN | WM_MBUTTONUP => MouseButton::Middle,
+                    _ => match hiword(wparam as isize) {
+                        1 => MouseButton::Back,
+                        2 => MouseButton::Forward,
+                        other => MouseButton::Other(other),
+                    },
+                };
+
+                let element_state = match msg {
+                    WM_LBUTTONDOWN | WM_RBUTTONDOWN | WM_MBUTTONDOWN | WM_XBUTTONDOWN => {
+                        ElementState::Pressed
+                    }
+                    _ => ElementState::Released,
+                };
+
+                state.push_event(Event::MouseInput {
+                    button,
+                    state: element_state,
+                });
+
+                // WM_XBUTTON* must return TRUE, the others 0.
+                Some((msg == WM_XBUTTONDOWN || msg == WM_XBUTTONUP) as isize)
+            }
+            WM_MOUSEWHEEL | WM_MOUSEHWHEEL => {
+                let delta = hiword(wparam as isize) as i16 as f32 / WHEEL_DELTA as f32;
+                state.push_event(match msg {
+                    WM_MOUSEWHEEL => Event::MouseWheel {
+                        delta_x: 0.0,
+                        delta_y: delta,
+                    },
+                    _ => Event::MouseWheel {
+                        delta_x: delta,
+                        delta_y: 0.0,
+                    },
+                });
+                Some(0)
+            }
+            _ => None,
+        }
+    }
+
+    /// Clips the cursor to match the grab mode and focus.
+    ///
+    /// The clip rectangle is in screen coordinates, so it has to be updated when the window moves or resizes.
+    fn apply_cursor_grab(hwnd: HWND, state: &WindowState) {
+        let mode = state.cursor_grab.get();
+        if mode == CursorGrabMode::None || !state.focused.get() {
+            unsafe { ClipCursor(std::ptr::null()) };
+            return;
+        }
+
+        let mut rect = RECT::default();
+        unsafe {
+            GetClientRect(hwnd, &mut rect);
+
+            let mut top_left = POINT {
+                x: rect.left,
+                y: rect.top,
+            };
+            let mut bottom_right = POINT {
+                x: rect.right,
+                y: rect.bottom,
+            };
+            ClientToScreen(hwnd, &mut top_left);
+            ClientToScreen(hwnd, &mut bottom_right);
+
+            rect = RECT {
+                left: top_left.x,
+                top: top_left.y,
+                right: bottom_right.x,
+                bottom: bottom_right.y,
+            };
+        }
+
+        // Windows can't lock the cursor, but clipping it to a single pixel in the center has the same effect.
+        if mode == CursorGrabMode::Locked {
+            let x = (rect.left + rect.right) / 2;
+            let y = (rect.top + rect.bottom) / 2;
+            rect = RECT {
+                left: x,
+                top: y,
+                right: x + 1,
+                bottom: y + 1,
+            };
+        }
+
+        unsafe { ClipCursor(&rect) };
+    }
+
+    /// Returns the composition string or result of an input context.
+    fn composition_string(himc: HIMC, kind: u32) -> String {
+        // The length is in bytes, not characters.
+        let len = unsafe { ImmGetCompositionStringW(himc, kind, std::ptr::null_mut(), 0) };
+        if len <= 0 {
+            return String::new();
+        }
+
+        let mut buffer = vec![0u16; len as usize / 2];
+        unsafe {
+            ImmGetCompositionStringW(himc, kind, buffer.as_mut_ptr() as *mut c_void, len as u32)
+        };
+        String::from_utf16_lossy(&buffer)
+    }
+
+    /// Moves the composition and candidate windows of the input method to the IME position.
+    fn update_ime_position(hwnd: HWND, state: &WindowState) {
+        let himc = unsafe { ImmGetContext(hwnd) };
+        if himc == 0 {
+            return;
+        }
+
+        let (x, y) = state.ime_position.get();
+        let position = POINT { x, y };
+
+        unsafe {
+            let composition = COMPOSITIONFORM {
+                dwStyle: CFS_POINT,
+                ptCurrentPos: position,
+                rcArea: RECT::default(),
+            };
+            ImmSetCompositionWindow(himc, &composition);
+
+            let candidate = CANDIDATEFORM {
+                dwIndex: 0,
+                dwStyle: CFS_CANDIDATEPOS,
+                ptCurrentPos: position,
+                rcArea: RECT::default(),
+            };
+            ImmSetCandidateWindow(himc, &candidate);
+
+            ImmReleaseContext(hwnd, himc);
+        }
+    }
+
+    fn modifiers() -> Modifiers {
+        // SAFETY: GetKeyState has no preconditions. The high bit reports whether a key is down,
+        // the low bit whether it is toggled on.
+        let down = |vk: VIRTUAL_KEY| unsafe { GetKeyState(vk as i32) } < 0;
+        let toggled = |vk: VIRTUAL_KEY| unsafe { GetKeyState(vk as i32) } & 1 != 0;
+
+        let mut modifiers = Modifiers::empty();
+        modifiers.set(Modifiers::SHIFT, down(VK_SHIFT));
+        modifiers.set(Modifiers::CONTROL, down(VK_CONTROL));
+        modifiers.set(Modifiers::ALT, down(VK_MENU));
+        modifiers.set(Modifiers::SUPER, down(VK_LWIN) || down(VK_RWIN));
+        modifiers.set(Modifiers::CAPS_LOCK, toggled(VK_CAPITAL));
+        modifiers.set(Modifiers::NUM_LOCK, toggled(VK_NUMLOCK));
+        modifiers
+    }
+
+    fn style(decorations: bool, resizable: bool) -> WINDOW_STYLE {
+        let mut style = WS_CLIPSIBLINGS | WS_CLIPCHILDREN | WS_SYSMENU | WS_MINIMIZEBOX;
+
+        style |= match decorations {
+            true => WS_CAPTION | WS_BORDER,
+            false => WS_POPUP,
+        };
+
+        if resizable {
+            style |= WS_THICKFRAME | WS_MAXIMIZEBOX;
+        }
+
+        style
+    }
+
+    fn current_style(hwnd: HWND) -> (WINDOW_STYLE, WINDOW_EX_STYLE) {
+        unsafe {
+            (
+                GetWindowLongPtrW(hwnd, GWL_STYLE) as WINDOW_STYLE,
+                GetWindowLongPtrW(hwnd, GWL_EXSTYLE) as WINDOW_EX_STYLE,
+            )
+        }
+    }
+
+    /// Converts an inner size to the size of the window including its decorations,
+    /// which are sized for the DPI of the monitor.
+    fn outer_size(
+        PhysicalSize { width, height }: PhysicalSize,
+        style: WINDOW_STYLE,
+        ex_style: WINDOW_EX_STYLE,
+        dpi: u32,
+    ) -> (i32, i32) {
+        let mut rect = RECT {
+            left: 0,
+            top: 0,
+            right: width as i32,
+            bottom: height as i32,
+        };
+
+        unsafe { AdjustWindowRectExForDpi(&mut rect, style, false, ex_style, dpi) };
+        (rect.right - rect.left, rect.bottom - rect.top)
+    }
+
+    fn enable_transparency(hwnd: HWND) {
+        unsafe {
+            // An empty blur region makes DWM composite the window using its alpha channel without blurring.
+            let region = CreateRectRgn(0, 0, -1, -1);
+            let blur_behind = DWM_BLURBEHIND {
+                dwFlags: DWM_BB_ENABLE | DWM_BB_BLURREGION,
+                fEnable: true.into(),
+                hRgnBlur: region,
+                fTransitionOnMaximized: false.into(),
+            };
+
+            let _ = DwmEnableBlurBehindWindow(hwnd, &blur_behind);
+            DeleteObject(region);
+        }
+    }
+
+    /// Applies the style derived from the current state, keeping the visibility of the window.
+    fn update_style(&self) {
+        let mut style = Self::style(self.state.decorations.get(), self.state.resizable.get());
+
+        // While fullscreen the style is applied when the window returns to windowed mode.
+        if let Some((_, placement)) = self.windowed.get() {
+            self.windowed.set(Some((style, placement)));
+            return;
+        }
+
+        unsafe {
+            if IsWindowVisible(self.hwnd).as_bool() {
+                style |= WS_VISIBLE;
+            }
+
+            SetWindowLongPtrW(self.hwnd, GWL_STYLE, style as isize);
+
+            // The frame has to be recalculated for the new style to take effect.
+            SetWindowPos(
+                self.hwnd,
+                HWND::default(),
+                0,
+                0,
+                0,
+                0,
+                SWP_FRAMECHANGED | SWP_NOMOVE | SWP_NOSIZE | SWP_NOZORDER | SWP_NOACTIVATE,
+            );
         }
     }
 
@@ -109,21 +819,389 @@ impl Window {
     }
 }
 
-impl WindowApi for Window {
-    fn show(&self) {
+impl Drop for Window {
+    fn drop(&mut self) {
+        // A changed video mode outlives the window, so it is restored explicitly.
+        if let Some(handle) = self.changed_monitor.take() {
+            monitor::restore_video_mode(handle);
+        }
+
         unsafe {
-            ShowWindow(self.hwnd, SW_SHOW);
+            // Messages are still sent during destruction,
+            // they must not reach the state, which is freed with this object.
+            SetWindowLongPtrW(self.hwnd, GWLP_USERDATA, 0);
+
+            // The cursor clip is global, so it would outlive the window.
+            if self.state.cursor_grab.get() != CursorGrabMode::None && self.state.focused.get() {
+                ClipCursor(std::ptr::null());
+            }
+
+            if self.ole_initialized {
+                drop_target::revoke(self.hwnd);
+            }
+            DestroyWindow(self.hwnd);
+
+            let (big, small) = self.icons.get();
+            for icon in [big, small] {
+                if icon != 0 {
+                    DestroyIcon(icon);
+                }
+            }
+
+            if self.state.cursor_owned.get() {
+                DestroyCursor(self.state.cursor.get());
+            }
+
+            if self.ole_initialized {
+                OleUninitialize();
+            }
         }
     }
+}
+
+impl HasWindowHandle for Window {
+    fn window_handle(&self) -> Result<WindowHandle<'_>, HandleError> {
+        let mut handle =
+            Win32WindowHandle::new(NonZeroIsize::new(self.hwnd).ok_or(HandleError::Unavailable)?);
+        handle.hinstance = NonZeroIsize::new(self.hinstance);
+
+        // SAFETY: The window is destroyed when this object is dropped, which the borrow outlives.
+        Ok(unsafe { WindowHandle::borrow_raw(handle.into()) })
+    }
+}
+
+impl HasDisplayHandle for Window {
+    fn display_handle(&self) -> Result<DisplayHandle<'_>, HandleError> {
+        Ok(DisplayHandle::windows())
+    }
+}
+
+/// Returns whether a display is available. A desktop session is always present on Windows.
+pub fn is_display_available() -> bool {
+    true
+}
 
-    fn poll_events(&self) {
+impl WindowApi for Window {
+    fn poll_events(&self, callback: &mut dyn FnMut(Event)) {
+        // All messages of the thread are pumped, not only those of this window. Filtering by window
+        // would leave thread messages in the queue forever, and the messages of other windows
+        // are dispatched to their own window procedures, which queue the events until those windows are polled.
         unsafe {
             let mut msg: MSG = MSG::default();
-            while PeekMessageW(std::ptr::addr_of_mut!(msg), self.hwnd, 0, 0, PM_REMOVE).0 != 0 {
+            while PeekMessageW(
+                std::ptr::addr_of_mut!(msg),
+                HWND::default(),
+                0,
+                0,
+                PM_REMOVE,
+            )
+            .0 != 0
+            {
                 TranslateMessage(&msg);
                 DispatchMessageW(&msg);
             }
         }
+
+        // The queue is taken first, since the callback may change the window, which sends new messages.
+        let events = std::mem::take(&mut *self.state.events.borrow_mut());
+        for event in events {
+            callback(event);
+        }
+    }
+
+    fn set_title(&self, title: &str) {
+        let (title, _title) = title.into_pwstr();
+        unsafe { SetWindowTextW(self.hwnd, title) };
+    }
+
+    fn scale_factor(&self) -> f64 {
+        self.state.scale_factor.get()
+    }
+
+    fn inner_size(&self) -> PhysicalSize {
+        let mut rect = RECT::default();
+        unsafe { GetClientRect(self.hwnd, &mut rect) };
+        PhysicalSize::new(
+            (rect.right - rect.left) as u32,
+            (rect.bottom - rect.top) as u32,
+        )
+    }
+
+    fn set_inner_size(&self, size: Size) {
+        let scale_factor = self.state.scale_factor.get();
+        let dpi = (scale_factor * USER_DEFAULT_SCREEN_DPI as f64) as u32;
+
+        let (style, ex_style) = Self::current_style(self.hwnd);
+        let (width, height) =
+            Self::outer_size(size.to_physical(scale_factor), style, ex_style, dpi);
+
+        unsafe {
+            SetWindowPos(
+                self.hwnd,
+                HWND::default(),
+                0,
+                0,
+                width,
+                height,
+                SWP_NOMOVE | SWP_NOZORDER | SWP_NOACTIVATE,
+            )
+        };
+    }
+
+    fn set_position(&self, x: i32, y: i32) {
+        unsafe {
+            SetWindowPos(
+                self.hwnd,
+                HWND::default(),
+                x,
+                y,
+                0,
+                0,
+                SWP_NOSIZE | SWP_NOZORDER | SWP_NOACTIVATE,
+            )
+        };
+    }
+
+    fn set_min_inner_size(&self, size: Option<Size>) {
+        self.state.min_size.set(size);
+    }
+
+    fn set_max_inner_size(&self, size: Option<Size>) {
+        self.state.max_size.set(size);
+    }
+
+    fn set_resizable(&self, resizable: bool) {
+        self.state.resizable.set(resizable);
+        self.update_style();
+    }
+
+    fn set_decorations(&self, decorations: bool) {
+        self.state.decorations.set(decorations);
+        self.update_style();
+    }
+
+    fn set_always_on_top(&self, always_on_top: bool) {
+        let insert_after = match always_on_top {
+            true => HWND_TOPMOST,
+            false => HWND_NOTOPMOST,
+        };
+
+        unsafe {
+            SetWindowPos(
+                self.hwnd,
+                insert_after,
+                0,
+                0,
+                0,
+                0,
+                SWP_NOMOVE | SWP_NOSIZE | SWP_NOACTIVATE,
+            )
+        };
+    }
+
+    fn set_visible(&self, visible: bool) {
+        let cmd = match visible {
+            true => SW_SHOW,
+            false => SW_HIDE,
+        };
+
+        unsafe {
+            ShowWindow(self.hwnd, cmd);
+        }
+    }
+
+    fn set_cursor_grab(&self, mode: CursorGrabMode) {
+        self.state.cursor_grab.set(mode);
+        Self::apply_cursor_grab(self.hwnd, &self.state);
+    }
+
+    fn set_cursor_visible(&self, visible: bool) {
+        self.state.cursor_visible.set(visible);
+
+        // The cursor is only updated on WM_SETCURSOR, which is sent the next time the cursor moves.
+        if self.state.cursor_inside.get() {
+            let cursor = match visible {
+                true => self.state.cursor.get(),
+                false => HCURSOR::default(),
+            };
+            unsafe { SetCursor(cursor) };
+        }
+    }
+
+    fn set_icon(&self, icon: Option<&Icon>) {
+        // The small icon is shown in the title bar, the big one in the taskbar and the task switcher.
+        let icons = match icon {
+            Some(icon) => unsafe {
+                let big = icon::best_image(icon, GetSystemMetrics(SM_CXICON) as u32);
+                let small = icon::best_image(icon, GetSystemMetrics(SM_CXSMICON) as u32);
+                (icon::create_icon(big, None), icon::create_icon(small, None))
+            },
+            None => (HICON::default(), HICON::default()),
+        };
+
+        // Setting a null icon falls back to the icon of the window class.
+        unsafe {
+            SendMessageW(self.hwnd, WM_SETICON, ICON_BIG as usize, icons.0);
+            SendMessageW(self.hwnd, WM_SETICON, ICON_SMALL as usize, icons.1);
+        }
+
+        // The window no longer uses the previous icons.
+        let (big, small) = self.icons.replace(icons);
+        for icon in [big, small] {
+            if icon != 0 {
+                unsafe { DestroyIcon(icon) };
+            }
+        }
+    }
+
+    fn set_cursor(&self, cursor: &Cursor) {
+        let (handle, owned) = match cursor {
+            Cursor::Icon(shape) => (icon::load_cursor(*shape), false),
+            Cursor::Custom(custom) => {
+                match icon::create_icon(custom.image(), Some(custom.hotspot())) {
+                    0 => (icon::load_cursor(CursorIcon::default()), false),
+                    handle => (handle, true),
+                }
+            }
+        };
+
+        let previous = self.state.cursor.replace(handle);
+        let previous_owned = self.state.cursor_owned.replace(owned);
+
+        if self.state.cursor_inside.get() && self.state.cursor_visible.get() {
+            unsafe { SetCursor(handle) };
+        }
+
+        // The previous cursor is no longer shown, since it was replaced above.
+        if previous_owned {
+            unsafe { DestroyCursor(previous) };
+        }
+    }
+
+    fn monitors(&self) -> Vec<Monitor> {
+        monitor::monitors()
+    }
+
+    fn current_monitor(&self) -> Option<Monitor> {
+        monitor::monitor_from_window(self.hwnd)
+    }
+
+    fn set_fullscreen(&self, fullscreen: Option<Fullscreen>) {
+        let target = match &fullscreen {
+            Some(Fullscreen::Borderless(monitor)) => {
+                monitor.clone().or_else(|| self.current_monitor())
+            }
+            Some(Fullscreen::Exclusive(monitor, _)) => Some(monitor.clone()),
+            None => None,
+        };
+
+        // The video mode is restored before anything else changes,
+        // unless the same monitor switches to another mode.
+        let mut changed_monitor = self.changed_monitor.take();
+        if let Some(handle) = changed_monitor {
+            if !matches!(&fullscreen, Some(Fullscreen::Exclusive(monitor, _)) if monitor.handle == handle)
+            {
+                monitor::restore_video_mode(handle);
+                changed_monitor = None;
+            }
+        }
+
+        if let Some(Fullscreen::Exclusive(monitor, mode)) = &fullscreen {
+            if monitor::set_video_mode(monitor.handle, mode) {
+                changed_monitor = Some(monitor.handle);
+            }
+        }
+        self.changed_monitor.set(changed_monitor);
+
+        let visible = unsafe { IsWindowVisible(self.hwnd) }.as_bool();
+
+        match target {
+            Some(target) => {
+                if self.windowed.get().is_none() {
+                    let mut placement = WINDOWPLACEMENT {
+                        length: size_of::<WINDOWPLACEMENT>() as u32,
+                        ..Default::default()
+                    };
+                    unsafe { GetWindowPlacement(self.hwnd, &mut placement) };
+
+                    let (style, _) = Self::current_style(self.hwnd);
+                    self.windowed.set(Some((style & !WS_VISIBLE, placement)));
+                }
+
+                // The area of the monitor changes with its video mode, so it is queried again.
+                let rect = monitor::monitor_rect(target.handle).unwrap_or(RECT {
+                    left: target.position.0,
+                    top: target.position.1,
+                    right: target.position.0 + target.size.0 as i32,
+                    bottom: target.position.1 + target.size.1 as i32,
+                });
+
+                let mut style = WS_POPUP | WS_CLIPSIBLINGS | WS_CLIPCHILDREN | WS_SYSMENU;
+                if visible {
+                    style |= WS_VISIBLE;
+                }
+
+                unsafe {
+                    SetWindowLongPtrW(self.hwnd, GWL_STYLE, style as isize);
+                    SetWindowPos(
+                        self.hwnd,
+                        HWND_TOP,
+                        rect.left,
+                        rect.top,
+                        rect.right - rect.left,
+                        rect.bottom - rect.top,
+                        SWP_FRAMECHANGED | SWP_NOACTIVATE,
+                    );
+                }
+            }
+            None => {
+                if let Some((mut style, placement)) = self.windowed.take() {
+                    if visible {
+                        style |= WS_VISIBLE;
+                    }
+
+                    unsafe {
+                        SetWindowLongPtrW(self.hwnd, GWL_STYLE, style as isize);
+                        SetWindowPlacement(self.hwnd, &placement);
+                        SetWindowPos(
+                            self.hwnd,
+                            HWND::default(),
+                            0,
+                            0,
+                            0,
+                            0,
+                            SWP_FRAMECHANGED
+                                | SWP_NOMOVE
+                                | SWP_NOSIZE
+                                | SWP_NOZORDER
+                                | SWP_NOACTIVATE,
+                        );
+                    }
+                }
+            }
+        }
+    }
+
+    fn set_text_input(&self, enabled: bool) {
+        self.state.text_input.set(enabled);
+
+        // Without an input context the input method is disabled for the window.
+        let flags = match enabled {
+            true => IACE_DEFAULT,
+            false => 0,
+        };
+        unsafe { ImmAssociateContextEx(self.hwnd, 0, flags) };
+    }
+
+    fn set_ime_position(&self, x: i32, y: i32) {
+        self.state.ime_position.set((x, y));
+        if self.state.text_input.get() {
+            Self::update_ime_position(self.hwnd, &self.state);
+        }
+    }
+
+    fn set_drag_and_drop(&self, enabled: bool) {
+        self.state.drag_and_drop.set(enabled);
     }
 }
 
@@ -0,0 +1,178 @@
+//! Monitors and video modes through GDI.
+
+use std::mem::size_of;
+
+use windows::Win32::{
+    Foundation::*,
+    Graphics::Gdi::*,
+    UI::HiDpi::*,
+    UI::WindowsAndMessaging::{MONITORINFOF_PRIMARY, USER_DEFAULT_SCREEN_DPI},
+};
+
+use crate::os::{monitor::sort_video_modes, Monitor, VideoMode};
+
+/// Returns all monitors, with the primary monitor first.
+pub fn monitors() -> Vec<Monitor> {
+    unsafe extern "system" fn push(
+        monitor: HMONITOR,
+        _hdc: HDC,
+        _rect: *mut RECT,
+        data: LPARAM,
+    ) -> BOOL {
+        // SAFETY: `data` is the vector passed to EnumDisplayMonitors below.
+        (*(data as *mut Vec<HMONITOR>)).push(monitor);
+        true.into()
+    }
+
+    let mut handles: Vec<HMONITOR> = Vec::new();
+    unsafe {
+        EnumDisplayMonitors(
+            HDC::default(),
+            std::ptr::null(),
+            Some(push),
+            &mut handles as *mut _ as isize,
+        )
+    };
+
+    let mut monitors: Vec<_> = handles.into_iter().filter_map(monitor).collect();
+    monitors.sort_by_key(|(_, primary)| !primary);
+    monitors.into_iter().map(|(monitor, _)| monitor).collect()
+}
+
+/// Returns the monitor the window is mostly on, or the nearest one if it is on none.
+pub fn monitor_from_window(hwnd: HWND) -> Option<Monitor> {
+    let handle = unsafe { MonitorFromWindow(hwnd, MONITOR_DEFAULTTONEAREST) };
+    monitor(handle).map(|(monitor, _)| monitor)
+}
+
+/// Returns the area of a monitor in screen coordinates, which changes with its video mode.
+pub fn monitor_rect(handle: u64) -> Option<RECT> {
+    monitor_info(handle as HMONITOR).map(|info| info.monitorInfo.rcMonitor)
+}
+
+/// Switches the monitor to a video mode until [`restore_video_mode()`] is called,
+/// or the process exits.
+///
+/// Returns whether the switch succeeded.
+pub fn set_video_mode(handle: u64, mode: &VideoMode) -> bool {
+    let mut info = match monitor_info(handle as HMONITOR) {
+        Some(info) => info,
+        None => return false,
+    };
+
+    let devmode = DEVMODEW {
+        dmSize: size_of::<DEVMODEW>() as u16,
+        dmFields: (DM_PELSWIDTH | DM_PELSHEIGHT | DM_BITSPERPEL | DM_DISPLAYFREQUENCY) as u32,
+        dmPelsWidth: mode.size.0,
+        dmPelsHeight: mode.size.1,
+        dmBitsPerPel: mode.bit_depth as u32,
+        dmDisplayFrequency: (mode.refresh_rate_millihertz + 500) / 1000,
+        ..Default::default()
+    };
+
+    // CDS_FULLSCREEN makes the change temporary, it isn't written to the registry.
+    let result = unsafe {
+        ChangeDisplaySettingsExW(
+            PWSTR(info.szDevice.as_mut_ptr()),
+            &devmode,
+            HWND::default(),
+            CDS_FULLSCREEN,
+            std::ptr::null(),
+        )
+    };
+    result == DISP_CHANGE_SUCCESSFUL
+}
+
+/// Switches the monitor back to the video mode stored in the registry.
+pub fn restore_video_mode(handle: u64) {
+    if let Some(mut info) = monitor_info(handle as HMONITOR) {
+        unsafe {
+            ChangeDisplaySettingsExW(
+                PWSTR(info.szDevice.as_mut_ptr()),
+                std::ptr::null(),
+                HWND::default(),
+                0,
+                std::ptr::null(),
+            )
+        };
+    }
+}
+
+fn monitor_info(handle: HMONITOR) -> Option<MONITORINFOEXW> {
+    let mut info = MONITORINFOEXW::default();
+    info.monitorInfo.cbSize = size_of::<MONITORINFOEXW>() as u32;
+
+    match unsafe { GetMonitorInfoW(handle, &mut info as *mut _ as *mut MONITORINFO) }.as_bool() {
+        true => Some(info),
+        false => None,
+    }
+}
+
+/// Describes a monitor, and returns whether it is the primary monitor.
+fn monitor(handle: HMONITOR) -> Option<(Monitor, bool)> {
+    let mut info = monitor_info(handle)?;
+    let device = PWSTR(info.szDevice.as_mut_ptr());
+
+    let video_mode = |index: u32| {
+        let mut devmode = DEVMODEW {
+            dmSize: size_of::<DEVMODEW>() as u16,
+            ..Default::default()
+        };
+
+        match unsafe { EnumDisplaySettingsW(device, index, &mut devmode) }.as_bool() {
+            true => Some(VideoMode {
+                size: (devmode.dmPelsWidth, devmode.dmPelsHeight),
+                bit_depth: devmode.dmBitsPerPel as u16,
+                refresh_rate_millihertz: devmode.dmDisplayFrequency * 1000,
+            }),
+            false => None,
+        }
+    };
+
+    let current = video_mode(ENUM_CURRENT_SETTINGS)?;
+    let mut video_modes: Vec<_> = (0..).map_while(video_mode).collect();
+    sort_video_modes(&mut video_modes);
+
+    // The physical size is only known to the device context of the display.
+    let physical_size = unsafe {
+        let hdc = CreateDCW(device, device, PWSTR::default(), std::ptr::null());
+        let size = (
+            GetDeviceCaps(hdc, HORZSIZE).max(0) as u32,
+            GetDeviceCaps(hdc, VERTSIZE).max(0) as u32,
+        );
+        DeleteDC(hdc);
+        size
+    };
+
+    // Without per-monitor DPI awareness this reports the system DPI, which is still the right scale.
+    let (mut dpi_x, mut dpi_y) = (USER_DEFAULT_SCREEN_DPI, USER_DEFAULT_SCREEN_DPI);
+    let _ = unsafe { GetDpiForMonitor(handle, MDT_EFFECTIVE_DPI, &mut dpi_x, &mut dpi_y) };
+
+    let rect = info.monitorInfo.rcMonitor;
+    let name_len = info
+        .szDevice
+        .iter()
+        .position(|&c| c == 0)
+        .unwrap_or(info.szDevice.len());
+    let name = String::from_utf16_lossy(&info.szDevice[..name_len]);
+
+    let monitor = Monitor {
+        // Device names look like `\\.\DISPLAY1`.
+        name: name.trim_start_matches(r"\\.\").to_owned(),
+        position: (rect.left, rect.top),
+        size: (
+            (rect.right - rect.left) as u32,
+            (rect.bottom - rect.top) as u32,
+        ),
+        physical_size,
+        scale_factor: dpi_x as f64 / USER_DEFAULT_SCREEN_DPI as f64,
+        video_mode: current,
+        video_modes,
+        handle: handle as u64,
+    };
+
+    Some((
+        monitor,
+        info.monitorInfo.dwFlags & MONITORINFOF_PRIMARY != 0,
+    ))
+}
@@ -1,5 +1,11 @@
 use enum_dispatch::enum_dispatch;
 
+use crate::Version;
+
+#[cfg(feature = "vulkan")]
+use super::vk::VkAdapter;
+#[cfg(not(feature = "vulkan"))]
+use super::NoBackend;
 use super::{Surface, SurfaceError};
 
 #[enum_dispatch]
@@ -16,9 +22,79 @@ pub trait AdapterApi: Send {
     fn is_surface_supported(&self, surface: &Surface) -> Result<bool, SurfaceError>;
 }
 
+/// The kind of device behind an adapter.
 #[derive(Debug, Clone, Copy, PartialEq, Eq)]
-pub struct AdapterInfo<'a> {
-    name: &'a str,
+pub enum DeviceType {
+    /// A separate GPU, which is usually the fastest.
+    Discrete,
+
+    /// A GPU that is part of the CPU.
+    Integrated,
+
+    /// A GPU in a virtualized environment.
+    Virtual,
+
+    /// A software implementation running on the CPU, e.g. lavapipe or WARP.
+    Cpu,
+
+    /// A device that doesn't match any of the other kinds.
+    Other,
 }
 
-pub enum Adapter {}
+/// A memory heap of an adapter, which all its allocations are made from.
+#[derive(Debug, Clone, Copy, PartialEq, Eq)]
+pub struct MemoryHeap {
+    /// The size of the heap in bytes.
+    pub size: u64,
+
+    /// Whether the heap is local to the device, i.e. the video memory of a discrete GPU.
+    pub device_local: bool,
+}
+
+#[derive(Debug, Clone, PartialEq, Eq)]
+pub struct AdapterInfo {
+    /// The name of the device, as reported by the driver.
+    pub name: String,
+
+    /// The PCI vendor ID of the device.
+    pub vendor_id: u32,
+
+    /// The vendor specific ID of the device.
+    pub device_id: u32,
+
+    /// The kind of the device.
+    pub device_type: DeviceType,
+
+    /// The version of the driver, whose encoding is vendor specific.
+    pub driver_version: u32,
+
+    /// The version of the graphics API the device supports.
+    pub api_version: Version,
+
+    /// The memory heaps of the device.
+    pub memory_heaps: Vec<MemoryHeap>,
+}
+
+/// Opaque owned object to an adapter, which is a physical device that can be used for rendering.
+///
+/// Adapters are returned by [`InstanceApi::enumerate_adapters()`](super::InstanceApi::enumerate_adapters)
+/// and keep the instance alive.
+#[enum_dispatch(AdapterApi)]
+#[derive(Clone)]
+pub enum Adapter {
+    #[cfg(feature = "vulkan")]
+    Vk(VkAdapter),
+    #[cfg(not(feature = "vulkan"))]
+    None(NoBackend),
+}
+
+#[cfg(not(feature = "vulkan"))]
+impl AdapterApi for NoBackend {
+    fn info(&self) -> AdapterInfo {
+        match *self {}
+    }
+
+    fn is_surface_supported(&self, _surface: &Surface) -> Result<bool, SurfaceError> {
+        match *self {}
+    }
+}
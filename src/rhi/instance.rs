@@ -1,19 +1,34 @@
 use enum_dispatch::enum_dispatch;
 
-use crate::{os::Window, Version};
+use raw_window_handle::{HasDisplayHandle, HasWindowHandle};
 
-use super::{vk::VkInstance, Adapter, Surface, SurfaceError};
+use crate::Version;
+
+#[cfg(feature = "vulkan")]
+use super::vk::VkInstance;
+#[cfg(not(feature = "vulkan"))]
+use super::NoBackend;
+use super::{Adapter, Surface, SurfaceError};
 
 #[enum_dispatch]
 pub trait InstanceApi: Send + Sync {
     /// Returns the currently used backend.
     fn backend(&self) -> Backend;
 
-    /// Creates a new surface.
-    fn new_surface<'a>(&self, window: &'a Window) -> Result<Surface<'a>, SurfaceError>;
-
-    /// Returns all the adapters that supports the minimum required limits.
-    fn enumerate_adapters<T: ExactSizeIterator<Item = Adapter>>(&self) -> T;
+    /// Creates a new surface for a window, which can be any window that exposes its native handles.
+    ///
+    /// Returns [`SurfaceError::NotSupported`] for windows without native handles, e.g. headless windows,
+    /// and for display servers the implementation has no surface extension for.
+    fn new_surface<'a, W: HasWindowHandle + HasDisplayHandle>(
+        &self,
+        window: &'a W,
+    ) -> Result<Surface<'a>, SurfaceError>;
+
+    /// Returns an adapter for every physical device of the instance.
+    ///
+    /// The list is never empty, because instantiation fails with [`InstanceError::NotSupported`]
+    /// if no adapter is found.
+    fn enumerate_adapters(&self) -> Vec<Adapter>;
 }
 
 // /// An object created from an instance.
@@ -32,17 +47,76 @@ pub trait InstanceApi: Send + Sync {
 /// Opaque owned object to an instance.
 #[enum_dispatch(InstanceApi)]
 pub enum Instance {
+    #[cfg(feature = "vulkan")]
     Vk(VkInstance),
+    #[cfg(not(feature = "vulkan"))]
+    None(NoBackend),
 }
 
 impl Instance {
+    /// The enabled backends, in the order [`Instance::new()`] tries them.
+    pub const BACKENDS: &'static [Backend] = &[
+        #[cfg(feature = "vulkan")]
+        Backend::Vulkan,
+    ];
+
+    /// Creates a new instance with the first backend in [`Instance::BACKENDS`] that is supported.
+    ///
+    /// Returns the error of the last backend if none could be instantiated.
+    ///
+    /// # Arguments
+    ///
+    /// - `info` - Info about the instance that is passed to the implementation.
     pub fn new(info: &InstanceInfo) -> Result<Instance, InstanceError> {
-        Ok(Self::Vk(VkInstance::new(info)?))
+        let mut result = Err(InstanceError::NotSupported);
+        for &backend in Self::BACKENDS {
+            result = Self::with_backend(backend, info);
+            if result.is_ok() {
+                break;
+            }
+        }
+        result
+    }
+
+    /// Creates a new instance with the supplied backend.
+    ///
+    /// Unlike [`Instance::new()`] this function only tries to instantiate with the passed in backend.
+    ///
+    /// # Arguments
+    ///
+    /// - `backend` - The backend to use for instantiation.
+    /// - `info` - Info about the instance that is passed to the implementation.
+    #[cfg_attr(not(feature = "vulkan"), allow(unused_variables))]
+    pub fn with_backend(backend: Backend, info: &InstanceInfo) -> Result<Instance, InstanceError> {
+        match backend {
+            #[cfg(feature = "vulkan")]
+            Backend::Vulkan => Ok(Self::Vk(VkInstance::new(info)?)),
+        }
+    }
+}
+
+#[cfg(not(feature = "vulkan"))]
+impl InstanceApi for NoBackend {
+    fn backend(&self) -> Backend {
+        match *self {}
+    }
+
+    fn new_surface<'a, W: HasWindowHandle + HasDisplayHandle>(
+        &self,
+        _window: &'a W,
+    ) -> Result<Surface<'a>, SurfaceError> {
+        match *self {}
+    }
+
+    fn enumerate_adapters(&self) -> Vec<Adapter> {
+        match *self {}
     }
 }
 
+/// The graphics APIs, of which only the ones enabled with cargo features are available.
 #[derive(Debug, Clone, Copy, PartialEq, Eq)]
 pub enum Backend {
+    #[cfg(feature = "vulkan")]
     Vulkan,
 }
 
@@ -1,6 +1,9 @@
 use enum_dispatch::enum_dispatch;
 
+#[cfg(feature = "vulkan")]
 use super::vk::VkSurface;
+#[cfg(not(feature = "vulkan"))]
+use super::NoBackend;
 
 // TODO(Bech): TextureFormat.
 
@@ -18,5 +21,12 @@ pub enum SurfaceError {
 
 #[enum_dispatch(SurfaceApi)]
 pub enum Surface<'a> {
+    #[cfg(feature = "vulkan")]
     Vk(VkSurface<'a>),
+    // A reference, so the lifetime is used without any backend.
+    #[cfg(not(feature = "vulkan"))]
+    None(&'a NoBackend),
 }
+
+#[cfg(not(feature = "vulkan"))]
+impl SurfaceApi for &NoBackend {}
@@ -1,20 +1,93 @@
+use std::{ffi::CStr, sync::Arc};
+
 use ash::vk;
 
-use crate::rhi::{AdapterApi, AdapterInfo, Surface, SurfaceError};
+use crate::{
+    rhi::{AdapterApi, AdapterInfo, DeviceType, MemoryHeap, Surface, SurfaceError},
+    Version,
+};
+
+use super::{VkInstanceInner, VkSurface, VkSurfaceApi};
 
-use super::{VkInstance, VkInstanceApi, VkSurface, VkSurfaceApi};
+pub trait VkAdapterApi {
+    /// Returns the instance the adapter was enumerated from.
+    fn instance(&self) -> &Arc<VkInstanceInner>;
+
+    /// Returns a handle to the vulkan physical device.
+    ///
+    /// # Safety
+    ///
+    /// The handles lifetime is tied to the instance
+    /// and must not be used after the adapter and all other objects holding the instance have been dropped.
+    unsafe fn handle(&self) -> &vk::PhysicalDevice;
+}
 
-pub trait VkAdapterApi {}
+#[derive(Clone)]
+pub struct VkAdapter {
+    instance: Arc<VkInstanceInner>,
+    handle: vk::PhysicalDevice,
+}
 
-#[derive(Clone, Copy)]
-pub struct VkAdapter<'a> {
-    instance: &'a VkInstance,
-    handle: &'a vk::PhysicalDevice,
+impl VkAdapter {
+    /// Creates an adapter for a physical device.
+    ///
+    /// # Arguments
+    ///
+    /// - `instance` - The instance the physical device was enumerated from.
+    /// - `handle` - The physical device.
+    pub fn new(instance: Arc<VkInstanceInner>, handle: vk::PhysicalDevice) -> Self {
+        Self { instance, handle }
+    }
 }
 
-impl<'a> AdapterApi for VkAdapter<'a> {
+impl AdapterApi for VkAdapter {
     fn info(&self) -> AdapterInfo {
-        todo!()
+        // SAFETY: The physical device was enumerated from this instance, which is kept alive by self.
+        let (properties, memory_properties) = unsafe {
+            (
+                self.instance
+                    .handle
+                    .get_physical_device_properties(self.handle),
+                self.instance
+                    .handle
+                    .get_physical_device_memory_properties(self.handle),
+            )
+        };
+
+        // SAFETY: This is safe because the vulkan specification states that VkPhysicalDeviceProperties::deviceName is a null-terminated UTF-8 string.
+        let name = unsafe { CStr::from_ptr(properties.device_name.as_ptr()) };
+
+        let device_type = match properties.device_type {
+            vk::PhysicalDeviceType::DISCRETE_GPU => DeviceType::Discrete,
+            vk::PhysicalDeviceType::INTEGRATED_GPU => DeviceType::Integrated,
+            vk::PhysicalDeviceType::VIRTUAL_GPU => DeviceType::Virtual,
+            vk::PhysicalDeviceType::CPU => DeviceType::Cpu,
+            _ => DeviceType::Other,
+        };
+
+        let api_version = properties.api_version;
+        let memory_heaps = memory_properties.memory_heaps
+            [..memory_properties.memory_heap_count as usize]
+            .iter()
+            .map(|heap| MemoryHeap {
+                size: heap.size,
+                device_local: heap.flags.contains(vk::MemoryHeapFlags::DEVICE_LOCAL),
+            })
+            .collect();
+
+        AdapterInfo {
+            name: name.to_string_lossy().into_owned(),
+            vendor_id: properties.vendor_id,
+            device_id: properties.device_id,
+            device_type,
+            driver_version: properties.driver_version,
+            api_version: Version::new(
+                vk::api_version_major(api_version) as u8,
+                vk::api_version_minor(api_version) as u8,
+                vk::api_version_patch(api_version) as u16,
+            ),
+            memory_heaps,
+        }
     }
 
     fn is_surface_supported(&self, surface: &Surface) -> Result<bool, SurfaceError> {
@@ -26,9 +99,19 @@ impl<'a> AdapterApi for VkAdapter<'a> {
         // SAFETY: This is safe because we don't store the handle.
         let handle = unsafe { surface.handle() };
 
-        match unsafe { ext.get_physical_device_surface_support(*self.handle, 0, *handle) } {
+        match unsafe { ext.get_physical_device_surface_support(self.handle, 0, *handle) } {
             Ok(support) => Ok(support),
             _ => Err(SurfaceError::Unknown),
         }
     }
 }
+
+impl VkAdapterApi for VkAdapter {
+    fn instance(&self) -> &Arc<VkInstanceInner> {
+        &self.instance
+    }
+
+    unsafe fn handle(&self) -> &vk::PhysicalDevice {
+        &self.handle
+    }
+}
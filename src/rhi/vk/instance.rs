@@ -5,12 +5,13 @@ use std::{
 
 use ash::{extensions::*, vk};
 
-use crate::{
-    os::Window,
-    rhi::{Adapter, Backend, InstanceApi, InstanceError, InstanceInfo, Surface, SurfaceError},
+use raw_window_handle::{HasDisplayHandle, HasWindowHandle};
+
+use crate::rhi::{
+    Adapter, Backend, InstanceApi, InstanceError, InstanceInfo, Surface, SurfaceError,
 };
 
-use super::VkSurface;
+use super::{VkAdapter, VkSurface};
 
 pub trait VkInstanceApi {
     /// Returns the entry that holds the global vulkan functions.
@@ -45,9 +46,23 @@ pub struct VkInstanceInner {
     pub entry: ash::Entry,
     pub handle: ash::Instance,
     pub physical_devices: Vec<vk::PhysicalDevice>,
+
+    /// The names of the enabled instance extensions, including the null-terminator.
+    pub enabled_extensions: Vec<&'static str>,
     pub debug_utils: Option<(vk::DebugUtilsMessengerEXT, ext::DebugUtils)>,
 }
 
+impl VkInstanceInner {
+    /// Returns whether an instance extension is enabled.
+    ///
+    /// # Arguments
+    ///
+    /// - `name` - The name of the extension, including the null-terminator.
+    pub fn is_extension_enabled(&self, name: &str) -> bool {
+        self.enabled_extensions.contains(&name)
+    }
+}
+
 impl Drop for VkInstanceInner {
     fn drop(&mut self) {
         if let Some(debug_utils) = &self.debug_utils {
@@ -71,8 +86,18 @@ pub struct VkInstance {
 impl VkInstance {
     // Warning(Bech): The layer- and extension names must all be null-terminated. This is done to make conversion to &CStr trivial.
     const ENABLED_LAYER_NAMES: [&'static str; 0] = [];
-    const ENABLED_EXTENSION_NAMES: [&'static str; 2] =
+
+    // The surface extensions are only enabled if the implementation supports them, e.g. a loader
+    // without a display server may lack all of them. Surfaces for the missing platforms aren't supported.
+    #[cfg(target_os = "windows")]
+    const SURFACE_EXTENSION_NAMES: [&'static str; 2] =
         ["VK_KHR_surface\0", "VK_KHR_win32_surface\0"];
+    #[cfg(target_os = "linux")]
+    const SURFACE_EXTENSION_NAMES: [&'static str; 3] = [
+        "VK_KHR_surface\0",
+        "VK_KHR_xlib_surface\0",
+        "VK_KHR_wayland_surface\0",
+    ];
 
     pub fn new(info: &InstanceInfo) -> Result<Self, InstanceError> {
         // SAFETY: Since we are loading vulkan dynamically, we assume that it is implemented correctly.
@@ -121,38 +146,60 @@ impl VkInstance {
             });
         }
 
-        // SAFETY: This is safe because all strings in Self::ENABLED_EXTENSION_NAMES are null-terminated.
-        let mut enabled_extension_names: Vec<*const i8> = unsafe {
-            Self::ENABLED_EXTENSION_NAMES
-                .iter()
-                .map(|s| CStr::from_bytes_with_nul_unchecked(s.as_bytes()).as_ptr())
-                .collect()
-        };
+        let mut enabled_extensions = vec![];
+        for name in Self::SURFACE_EXTENSION_NAMES {
+            if Self::has_extension(name, &entry)? {
+                enabled_extensions.push(name);
+            }
+        }
 
         const DEBUG_EXTENSION_NAME: &str = "VK_EXT_debug_utils\0";
         if info.debug && Self::has_extension(DEBUG_EXTENSION_NAME, &entry)? {
-            // SAFETY: This is safe because DEBUG_EXTENSION_NAME is null-terminated.
-            enabled_extension_names.push(unsafe {
-                CStr::from_bytes_with_nul_unchecked(DEBUG_EXTENSION_NAME.as_bytes()).as_ptr()
-            });
+            enabled_extensions.push(DEBUG_EXTENSION_NAME);
         }
 
+        // SAFETY: This is safe because all enabled extension names are null-terminated.
+        let enabled_extension_names: Vec<*const i8> = unsafe {
+            enabled_extensions
+                .iter()
+                .map(|s| CStr::from_bytes_with_nul_unchecked(s.as_bytes()).as_ptr())
+                .collect()
+        };
+
         let create_info = create_info
             .enabled_layer_names(&enabled_layer_names)
             .enabled_extension_names(&enabled_extension_names);
 
         // SAFETY: We assume the vulkan implementation is implemented correctly.
-        match unsafe { entry.create_instance(&create_info, None) } {
-            Ok(instance) => Ok(Self {
-                inner: Arc::new(VkInstanceInner {
-                    entry,
-                    handle: instance,
-                    physical_devices: vec![],
-                    debug_utils: None,
-                }),
-            }),
-            _ => Err(InstanceError::Unknown),
+        let handle = match unsafe { entry.create_instance(&create_info, None) } {
+            Ok(instance) => instance,
+            _ => return Err(InstanceError::Unknown),
+        };
+
+        let mut inner = VkInstanceInner {
+            entry,
+            handle,
+            physical_devices: vec![],
+            enabled_extensions,
+            debug_utils: None,
+        };
+
+        // The instance is destroyed by dropping inner on failure.
+        // SAFETY: We assume the vulkan implementation is implemented correctly.
+        inner.physical_devices = match unsafe { inner.handle.enumerate_physical_devices() } {
+            Ok(physical_devices) => physical_devices,
+            Err(vk::Result::ERROR_OUT_OF_HOST_MEMORY | vk::Result::ERROR_OUT_OF_DEVICE_MEMORY) => {
+                return Err(InstanceError::OutOfMemory)
+            }
+            _ => return Err(InstanceError::Unknown),
+        };
+        if inner.physical_devices.is_empty() {
+            return Err(InstanceError::NotSupported);
         }
+
+        Ok(Self {
+            inner: Arc::new(inner),
+        })
     }
 
     fn has_layer(name: &str, entry: &ash::Entry) -> Result<bool, InstanceError> {
@@ -219,14 +266,23 @@ impl InstanceApi for VkInstance {
         Backend::Vulkan
     }
 
-    fn new_surface<'a>(&self, window: &'a Window) -> Result<Surface<'a>, SurfaceError> {
+    fn new_surface<'a, W: HasWindowHandle + HasDisplayHandle>(
+        &self,
+        window: &'a W,
+    ) -> Result<Surface<'a>, SurfaceError> {
         Ok(Surface::Vk(VkSurface::new(
             Arc::clone(&self.inner),
             window,
         )?))
     }
 
-    fn enumerate_adapters<T: ExactSizeIterator<Item = Adapter>>(&self) -> T {
-        todo!()
+    fn enumerate_adapters(&self) -> Vec<Adapter> {
+        self.inner
+            .physical_devices
+            .iter()
+            .map(|&physical_device| {
+                Adapter::Vk(VkAdapter::new(Arc::clone(&self.inner), physical_device))
+            })
+            .collect()
     }
 }
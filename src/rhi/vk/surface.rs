@@ -1,18 +1,32 @@
-use std::{borrow::Borrow, marker::PhantomData, sync::Arc};
+use std::{ffi::c_void, marker::PhantomData, sync::Arc};
 
 use ash::{extensions::khr, vk};
+#[cfg(target_os = "linux")]
+use raw_window_handle::RawDisplayHandle;
+use raw_window_handle::{HasDisplayHandle, HasWindowHandle, RawWindowHandle};
 
-use crate::{
-    os::Window,
-    rhi::{Surface, SurfaceApi, SurfaceError},
-};
+use crate::rhi::{Surface, SurfaceApi, SurfaceError};
 
-use super::{VkInstance, VkInstanceApi, VkInstanceInner};
+use super::VkInstanceInner;
 
-// TODO(Bech): Documentation.
 pub trait VkSurfaceApi {
+    /// Returns the instance the surface was created from.
     fn instance(&self) -> &Arc<VkInstanceInner>;
+
+    /// Returns a handle to the vulkan surface.
+    ///
+    /// # Safety
+    ///
+    /// The handles lifetime is tied to the surface object
+    /// and must not be used after the object has been dropped.
     unsafe fn handle(&self) -> &vk::SurfaceKHR;
+
+    /// Returns a handle to the loaded vulkan VkSurfaceKHR extension.
+    ///
+    /// # Safety
+    ///
+    /// The extensions lifetime is tied to the lifetime of the surface object
+    /// and must not be used after this object has been dropped.
     unsafe fn extension(&self) -> &khr::Surface;
 }
 
@@ -20,15 +34,61 @@ pub struct VkSurface<'a> {
     instance: Arc<VkInstanceInner>,
     handle: vk::SurfaceKHR,
     extension: khr::Surface,
-    _marker: PhantomData<&'a Window>,
+    _marker: PhantomData<&'a ()>,
 }
 
 impl<'a> VkSurface<'a> {
-    pub fn new(instance: Arc<VkInstanceInner>, window: &'a Window) -> Result<Self, SurfaceError> {
+    /// Creates a surface for any window that exposes its native handles.
+    ///
+    /// # Arguments
+    ///
+    /// - `instance` - The instance the surface is created from.
+    /// - `window` - The window to present to, which must outlive the surface.
+    pub fn new<W: HasWindowHandle + HasDisplayHandle>(
+        instance: Arc<VkInstanceInner>,
+        window: &'a W,
+    ) -> Result<Self, SurfaceError> {
+        if !instance.is_extension_enabled("VK_KHR_surface\0") {
+            return Err(SurfaceError::NotSupported);
+        }
         let extension = khr::Surface::new(&instance.entry, &instance.handle);
 
-        #[cfg(target_os = "windows")]
-        let handle = Self::new_win32_surface(&instance, window)?;
+        // Headless windows have nothing to present to.
+        let window_handle = match window.window_handle() {
+            Ok(handle) => handle.as_raw(),
+            _ => return Err(SurfaceError::NotSupported),
+        };
+        let display_handle = match window.display_handle() {
+            Ok(handle) => handle.as_raw(),
+            _ => return Err(SurfaceError::NotSupported),
+        };
+
+        // Only the surface extensions enabled on the instance are supported.
+        let handle = match (window_handle, display_handle) {
+            #[cfg(target_os = "windows")]
+            (RawWindowHandle::Win32(window), _) => Self::new_win32_surface(
+                &instance,
+                window.hinstance.map_or(0, |h| h.get()),
+                window.hwnd.get(),
+            )?,
+
+            #[cfg(target_os = "linux")]
+            (RawWindowHandle::Xlib(window), RawDisplayHandle::Xlib(display)) => {
+                let display = display.display.ok_or(SurfaceError::NotSupported)?;
+                Self::new_xlib_surface(&instance, display.as_ptr(), window.window)?
+            }
+
+            #[cfg(target_os = "linux")]
+            (RawWindowHandle::Wayland(window), RawDisplayHandle::Wayland(display)) => {
+                Self::new_wayland_surface(
+                    &instance,
+                    display.display.as_ptr(),
+                    window.surface.as_ptr(),
+                )?
+            }
+
+            _ => return Err(SurfaceError::NotSupported),
+        };
 
         Ok(Self {
             instance,
@@ -41,14 +101,17 @@ impl<'a> VkSurface<'a> {
     #[cfg(target_os = "windows")]
     fn new_win32_surface(
         instance: &VkInstanceInner,
-        win: &Window,
+        hinstance: isize,
+        hwnd: isize,
     ) -> Result<vk::SurfaceKHR, SurfaceError> {
-        use std::ffi::c_void;
+        if !instance.is_extension_enabled("VK_KHR_win32_surface\0") {
+            return Err(SurfaceError::NotSupported);
+        }
         let extension = khr::Win32Surface::new(&instance.entry, &instance.handle);
 
         let create_info = vk::Win32SurfaceCreateInfoKHR {
-            hinstance: *win.platform_impl().hinstance() as *const c_void,
-            hwnd: *win.platform_impl().hwnd() as *const c_void,
+            hinstance: hinstance as *const c_void,
+            hwnd: hwnd as *const c_void,
             ..Default::default()
         };
 
@@ -58,6 +121,54 @@ impl<'a> VkSurface<'a> {
             _ => Err(SurfaceError::Unknown),
         }
     }
+
+    #[cfg(target_os = "linux")]
+    fn new_xlib_surface(
+        instance: &VkInstanceInner,
+        display: *mut c_void,
+        window: std::os::raw::c_ulong,
+    ) -> Result<vk::SurfaceKHR, SurfaceError> {
+        if !instance.is_extension_enabled("VK_KHR_xlib_surface\0") {
+            return Err(SurfaceError::NotSupported);
+        }
+        let extension = khr::XlibSurface::new(&instance.entry, &instance.handle);
+
+        let create_info = vk::XlibSurfaceCreateInfoKHR {
+            dpy: display as *mut vk::Display,
+            window: window as vk::Window,
+            ..Default::default()
+        };
+
+        // SAFETY: We are calling vulkan over FFI which we assume is safe.
+        match unsafe { extension.create_xlib_surface(&create_info, None) } {
+            Ok(surface) => Ok(surface),
+            _ => Err(SurfaceError::Unknown),
+        }
+    }
+
+    #[cfg(target_os = "linux")]
+    fn new_wayland_surface(
+        instance: &VkInstanceInner,
+        display: *mut c_void,
+        surface: *mut c_void,
+    ) -> Result<vk::SurfaceKHR, SurfaceError> {
+        if !instance.is_extension_enabled("VK_KHR_wayland_surface\0") {
+            return Err(SurfaceError::NotSupported);
+        }
+        let extension = khr::WaylandSurface::new(&instance.entry, &instance.handle);
+
+        let create_info = vk::WaylandSurfaceCreateInfoKHR {
+            display,
+            surface,
+            ..Default::default()
+        };
+
+        // SAFETY: We are calling vulkan over FFI which we assume is safe.
+        match unsafe { extension.create_wayland_surface(&create_info, None) } {
+            Ok(surface) => Ok(surface),
+            _ => Err(SurfaceError::Unknown),
+        }
+    }
 }
 
 impl<'a> Drop for VkSurface<'a> {
@@ -90,6 +201,7 @@ impl<'a> TryFrom<&'a Surface<'a>> for &'a VkSurface<'a> {
     fn try_from(value: &'a Surface<'a>) -> Result<Self, Self::Error> {
         match value {
             Surface::Vk(value) => Ok(value),
+            #[allow(unreachable_patterns)]
             _ => Err(SurfaceError::Unknown),
         }
     }
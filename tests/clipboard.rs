@@ -0,0 +1,77 @@
+//! Exchanges contents between two clipboards, e.g. through the X server of `xvfb-run` in CI.
+//!
+//! The tests are skipped where no clipboard is available, unless `IGLO_TEST_DISPLAY_SERVER` is `x11`.
+
+use std::{env, sync::Mutex};
+
+use iglo::os::{Clipboard, ClipboardError, ImageData};
+
+/// The clipboard is shared by the whole system, so the tests must not run in parallel.
+static SYSTEM_CLIPBOARD: Mutex<()> = Mutex::new(());
+
+fn with_clipboards(f: impl FnOnce(&Clipboard, &Clipboard)) {
+    let _guard = SYSTEM_CLIPBOARD
+        .lock()
+        .unwrap_or_else(|error| error.into_inner());
+
+    let clipboards = Clipboard::new().and_then(|source| Ok((source, Clipboard::new()?)));
+    match clipboards {
+        Ok((source, target)) => f(&source, &target),
+        Err(ClipboardError::NotSupported)
+            if env::var("IGLO_TEST_DISPLAY_SERVER").as_deref() != Ok("x11") => {}
+        Err(error) => panic!("Failed to connect to the clipboard: {:?}", error),
+    }
+}
+
+/// Returns text of at least `len` bytes, which isn't all ASCII.
+fn text(len: usize) -> String {
+    "iglo clipboard äöü 🦀\n".repeat(len / 20 + 1)
+}
+
+#[test]
+fn text_round_trip() {
+    with_clipboards(|source, target| {
+        let text = text(100);
+        source.set_text(&text).unwrap();
+        assert_eq!(target.get_text().unwrap(), text);
+
+        // The contents are replaced, also by the other clipboard.
+        target.set_text("replaced").unwrap();
+        assert_eq!(source.get_text().unwrap(), "replaced");
+    });
+}
+
+#[test]
+fn large_text_round_trip() {
+    with_clipboards(|source, target| {
+        // Larger than any X server accepts in a single request, so it is transferred incrementally.
+        let text = text(4 << 20);
+        source.set_text(&text).unwrap();
+        assert_eq!(target.get_text().unwrap(), text);
+    });
+}
+
+#[test]
+fn image_round_trip() {
+    with_clipboards(|source, target| {
+        // Noise compresses badly, so the encoded image is transferred incrementally as well.
+        let (width, height) = (600, 500);
+        let mut seed = 0x2545_f491_u32;
+        let rgba = (0..width * height * 4)
+            .map(|_| {
+                seed ^= seed << 13;
+                seed ^= seed >> 17;
+                seed ^= seed << 5;
+                seed as u8
+            })
+            .collect();
+        let image = ImageData {
+            width,
+            height,
+            rgba,
+        };
+
+        source.set_image(&image).unwrap();
+        assert_eq!(target.get_image().unwrap(), image);
+    });
+}
@@ -0,0 +1,29 @@
+//! A library for `tests/hot_reload.rs`, which builds it with rustc as a `cdylib`.
+//!
+//! Its state counts how often it was loaded, so the test can tell it was handed over.
+
+use std::{ffi::c_void, ptr};
+
+#[no_mangle]
+pub extern "C" fn iglo_load(state: *mut c_void) -> *mut c_void {
+    let state = match state.is_null() {
+        true => Box::into_raw(Box::new(0u32)),
+        false => state as *mut u32,
+    };
+    unsafe { *state += 1 };
+    state as *mut c_void
+}
+
+#[no_mangle]
+pub extern "C" fn iglo_unload(state: *mut c_void, shutdown: bool) -> *mut c_void {
+    if shutdown {
+        drop(unsafe { Box::from_raw(state as *mut u32) });
+        return ptr::null_mut();
+    }
+    state
+}
+
+#[no_mangle]
+pub extern "C" fn loads(state: *mut c_void) -> u32 {
+    unsafe { *(state as *const u32) }
+}
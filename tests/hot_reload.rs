@@ -0,0 +1,61 @@
+//! Loads and reloads the library in `tests/fixtures/hot_reload.rs`.
+
+use std::{env, ffi::c_void, path::PathBuf, process::Command};
+
+use iglo::os::hot_reload::HotReload;
+
+type LoadsFn = unsafe extern "C" fn(*mut c_void) -> u32;
+
+/// Builds the fixture with rustc, or the one in `RUSTC`, and returns the path of the library.
+fn build_fixture() -> PathBuf {
+    let manifest_dir = PathBuf::from(env!("CARGO_MANIFEST_DIR"));
+    let out_dir = PathBuf::from(env!("CARGO_TARGET_TMPDIR")).join("hot_reload");
+    std::fs::create_dir_all(&out_dir).unwrap();
+
+    let rustc = env::var_os("RUSTC").unwrap_or_else(|| "rustc".into());
+    let status = Command::new(rustc)
+        .args(["--crate-type", "cdylib", "--edition", "2021"])
+        .args(["--crate-name", "hot_reload_fixture"])
+        .arg("--out-dir")
+        .arg(&out_dir)
+        .arg(manifest_dir.join("tests/fixtures/hot_reload.rs"))
+        .status()
+        .unwrap();
+    assert!(status.success());
+
+    out_dir.join(format!(
+        "{}hot_reload_fixture{}",
+        env::consts::DLL_PREFIX,
+        env::consts::DLL_SUFFIX
+    ))
+}
+
+fn loads(reload: &HotReload) -> u32 {
+    unsafe { reload.symbol::<LoadsFn>("loads").unwrap()(reload.state()) }
+}
+
+#[test]
+fn state_is_handed_over() {
+    let path = build_fixture();
+
+    let mut reload = HotReload::new(&path).unwrap();
+    let state = reload.state();
+    assert!(!state.is_null());
+    assert_eq!(reload.version(), 1);
+    assert_eq!(loads(&reload), 1);
+
+    // The new version gets the state of the previous one.
+    reload.reload().unwrap();
+    assert_eq!(reload.version(), 2);
+    assert_eq!(reload.state(), state);
+    assert_eq!(loads(&reload), 2);
+
+    // Instances of the same library don't share their copies, dropping one leaves the other loaded.
+    let other = HotReload::new(&path).unwrap();
+    assert_ne!(other.state(), state);
+    drop(other);
+
+    reload.reload().unwrap();
+    assert_eq!(reload.version(), 3);
+    assert_eq!(loads(&reload), 3);
+}
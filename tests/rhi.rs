@@ -0,0 +1,82 @@
+//! Enumerates the adapters of the Vulkan driver, e.g. lavapipe in CI.
+//!
+//! The tests are skipped where no Vulkan driver is installed, unless `IGLO_TEST_VULKAN_ADAPTER` is set
+//! to part of the name of an adapter that must be found, e.g. `llvmpipe`.
+
+#![cfg(feature = "vulkan")]
+
+use std::env;
+
+use iglo::{
+    rhi::{
+        AdapterApi, AdapterInfo, Backend, DeviceType, Instance, InstanceApi, InstanceError,
+        InstanceInfo,
+    },
+    Version,
+};
+
+/// Returns the info of every adapter, or `None` if no driver is installed.
+fn adapter_infos() -> Option<Vec<AdapterInfo>> {
+    let info = InstanceInfo {
+        app_info: None,
+        validation: false,
+        debug: false,
+    };
+
+    let expected = env::var("IGLO_TEST_VULKAN_ADAPTER").ok();
+    let instance = match Instance::with_backend(Backend::Vulkan, &info) {
+        Ok(instance) => instance,
+        Err(InstanceError::NotSupported) if expected.is_none() => return None,
+        Err(error) => panic!("Failed to create a Vulkan instance: {:?}", error),
+    };
+
+    let infos: Vec<_> = instance
+        .enumerate_adapters()
+        .iter()
+        .map(|adapter| adapter.info())
+        .collect();
+
+    if let Some(expected) = expected {
+        assert!(
+            infos.iter().any(|info| info.name.contains(&expected)),
+            "No adapter named {:?} in {:?}",
+            expected,
+            infos
+        );
+    }
+    Some(infos)
+}
+
+#[test]
+fn enumerate_adapters() {
+    let infos = match adapter_infos() {
+        Some(infos) => infos,
+        None => return,
+    };
+
+    assert!(!infos.is_empty());
+    for info in &infos {
+        assert!(!info.name.is_empty());
+        assert!(!info.memory_heaps.is_empty(), "{:?}", info);
+        assert!(
+            info.memory_heaps.iter().all(|heap| heap.size > 0),
+            "{:?}",
+            info
+        );
+        assert_ne!(info.api_version, Version::new(0, 0, 0), "{:?}", info);
+    }
+}
+
+#[test]
+fn software_adapter() {
+    let infos = match adapter_infos() {
+        Some(infos) => infos,
+        None => return,
+    };
+
+    // lavapipe renders on the CPU, and reports so.
+    for info in infos.iter().filter(|info| info.name.contains("llvmpipe")) {
+        assert_eq!(info.device_type, DeviceType::Cpu, "{:?}", info);
+        assert!(info.api_version >= Version::new(1, 0, 0), "{:?}", info);
+    }
+}
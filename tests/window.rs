@@ -0,0 +1,100 @@
+//! Creates windows on the display server, e.g. the X server of `xvfb-run` or a headless weston in CI.
+//!
+//! Without a display server the windows fall back to headless ones, so the tests still run.
+//! On Linux, `IGLO_TEST_DISPLAY_SERVER` can be set to `x11` or `wayland` to check which one is used.
+
+use std::env;
+
+use iglo::os::{Event, Window, WindowDesc};
+
+fn new_window() -> Window {
+    let window = Window::new(&WindowDesc {
+        title: "iglo test",
+        ..Default::default()
+    })
+    .unwrap();
+
+    // A display server is available, so a native window must have been created.
+    if env::var_os("DISPLAY").is_some() || env::var_os("WAYLAND_DISPLAY").is_some() {
+        assert!(window.platform_impl().is_some());
+    }
+
+    #[cfg(target_os = "linux")]
+    if let Some(expected) = env::var_os("IGLO_TEST_DISPLAY_SERVER") {
+        let display_server = match window.platform_impl() {
+            Some(iglo::os::linux::Window::X11(_)) => "x11",
+            Some(iglo::os::linux::Window::Wayland(_)) => "wayland",
+            None => "headless",
+        };
+        assert_eq!(display_server, expected);
+    }
+    window
+}
+
+#[test]
+fn show_and_poll() {
+    let window = new_window();
+    window.show();
+    for _ in 0..10 {
+        window.poll_events(|_| {});
+    }
+    assert!(!window.is_close_requested());
+}
+
+#[test]
+fn initial_size() {
+    let window = new_window();
+
+    // Headless windows only deliver injected events.
+    if window.platform_impl().is_none() {
+        return;
+    }
+
+    let mut events = vec![];
+    window.poll_events(|event| events.push(event));
+
+    // The compositor may pick another size on the first poll, which is reported after the initial one.
+    let size = window.inner_size();
+    let resized = events
+        .iter()
+        .rev()
+        .find(|event| matches!(event, Event::Resized { .. }));
+    assert_eq!(
+        resized,
+        Some(&Event::Resized {
+            width: size.width,
+            height: size.height,
+        })
+    );
+}
+
+#[test]
+fn injected_close_request() {
+    let window = new_window();
+    window.inject_event(Event::CloseRequested);
+
+    let mut events = vec![];
+    window.poll_events(|event| events.push(event));
+    assert!(events.contains(&Event::CloseRequested));
+    assert!(window.is_close_requested());
+
+    window.cancel_close_request();
+    assert!(!window.is_close_requested());
+}
+
+#[test]
+fn headless_close_request() {
+    let window = Window::new_headless(&WindowDesc::default());
+    let headless = window.headless_impl().unwrap();
+
+    window.inject_event(Event::CloseRequested);
+    assert!(!headless.is_close_requested());
+
+    window.poll_events(|_| {});
+    assert!(window.is_close_requested());
+    assert!(headless.is_close_requested());
+
+    window.cancel_close_request();
+    assert!(!window.is_close_requested());
+    assert!(!headless.is_close_requested());
+}